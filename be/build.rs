@@ -0,0 +1,41 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git_commit() -> Option<String> {
+  let output = Command::new("git")
+    .args(["rev-parse", "--short=12", "HEAD"])
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  String::from_utf8(output.stdout)
+    .ok()
+    .map(|s| String::from(s.trim()))
+}
+
+fn main() {
+  // GIT_COMMIT and SOURCE_DATE_EPOCH let packaged builds without a checkout set these explicitly.
+  let commit = std::env::var("GIT_COMMIT")
+    .ok()
+    .or_else(git_commit)
+    .unwrap_or_else(|| String::from("unknown"));
+  let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+    .ok()
+    .and_then(|s| s.parse::<u64>().ok())
+    .unwrap_or_else(|| {
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+    });
+
+  println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", commit);
+  println!("cargo:rustc-env=BUILD_TIMESTAMP={}", timestamp);
+  println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+  println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed=src");
+  println!("cargo:rerun-if-changed=../.git/HEAD");
+  println!("cargo:rerun-if-changed=../.git/refs");
+}
@@ -0,0 +1,47 @@
+use crate::services::config::ENV_CONFIG_FILE;
+
+const DEFAULT_CONFIG_FILE: &str = "resources/config.json";
+
+pub struct Args {
+  pub config_file: String,
+  pub overrides: Vec<(String, String)>,
+}
+
+pub fn parse() -> Result<Args, String> {
+  let mut args = Args {
+    config_file: std::env::var(ENV_CONFIG_FILE)
+      .unwrap_or_else(|_| String::from(DEFAULT_CONFIG_FILE)),
+    overrides: Vec::new(),
+  };
+
+  let mut argv = std::env::args().skip(1);
+  while let Some(arg) = argv.next() {
+    let (flag, inline_value) = match arg.find('=') {
+      Some(i) if arg.starts_with("--") => (&arg[..i], Some(String::from(&arg[i + 1..]))),
+      _ => (&arg[..], None),
+    };
+    let mut value = || {
+      inline_value
+        .clone()
+        .or_else(|| argv.next())
+        .ok_or(format!("{} requires a value", flag))
+    };
+
+    match flag {
+      "--config" => args.config_file = value()?,
+      "--set" => {
+        let assignment = value()?;
+        match assignment.find('=') {
+          Some(i) => args.overrides.push((
+            String::from(&assignment[..i]),
+            String::from(&assignment[i + 1..]),
+          )),
+          None => return Err(format!("--set expects key=value, got '{}'", assignment)),
+        }
+      }
+      _ => return Err(format!("Unrecognised argument '{}'", arg)),
+    }
+  }
+
+  Ok(args)
+}
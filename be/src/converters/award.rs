@@ -4,6 +4,7 @@ use crate::model;
 pub trait AwardConverter {
   fn alt_id_to_model(&self, dto: dto::AwardAlternateId) -> model::AwardAlternateId;
   fn classification_to_model(&self, dto: dto::AwardClassification) -> model::AwardClassification;
+  fn to_dto(&self, award: &model::Award) -> dto::AwardDto;
 }
 
 pub struct AwardConverterImpl {}
@@ -29,4 +30,33 @@ impl AwardConverter for AwardConverterImpl {
       note: dto.note,
     }
   }
+  fn to_dto(&self, award: &model::Award) -> dto::AwardDto {
+    dto::AwardDto {
+      id: award.id.clone(),
+      external_id: award.external_id.clone(),
+      name: award.name.clone(),
+      industry_name: award.industry_name.clone(),
+      common_rule: award.common_rule.clone(),
+      alternate_ids: award
+        .alternate_ids
+        .iter()
+        .map(|i| dto::AwardAlternateId {
+          id: i.id.clone(),
+          id_type: i.id_type.clone(),
+        })
+        .collect(),
+      operative_date: award.operative_date.0,
+      expired_date: award.expired_date.map(|d| d.0),
+      classifications: award
+        .classifications
+        .iter()
+        .map(|c| dto::AwardClassification {
+          id: c.id.clone(),
+          title: c.title.clone(),
+          active: c.active,
+          note: c.note.clone(),
+        })
+        .collect(),
+    }
+  }
 }
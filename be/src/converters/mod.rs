@@ -1,5 +1,7 @@
 pub mod award;
+pub mod role;
 pub mod user;
 
 pub use user::UserConverter;
+pub use role::RoleConverter;
 pub use award::AwardConverter;
@@ -0,0 +1,25 @@
+use crate::dto::RoleDto;
+use crate::model::Role;
+
+pub trait RoleConverter {
+  fn to_dto(&self, role: &Role) -> RoleDto;
+}
+
+pub struct RoleConverterImpl {}
+
+impl RoleConverterImpl {
+  pub fn new() -> RoleConverterImpl {
+    RoleConverterImpl {}
+  }
+}
+
+impl RoleConverter for RoleConverterImpl {
+  fn to_dto(&self, role: &Role) -> RoleDto {
+    RoleDto {
+      id: role.id.clone(),
+      name: role.name.clone(),
+      permissions: role.permissions.clone(),
+      included_role_ids: role.included_role_ids.clone(),
+    }
+  }
+}
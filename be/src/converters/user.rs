@@ -21,6 +21,7 @@ impl UserConverter for UserConverterImpl {
       company_id: user.company_id.clone(),
       login_id: user.login_id.clone(),
       role_ids: user.role_ids.clone(),
+      disabled: user.disabled,
     }
   }
 }
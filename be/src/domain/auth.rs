@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+const PERMISSION_WILDCARD: &str = "*";
+const PERMISSION_SEPARATOR: char = ':';
+
 #[derive(Serialize, Deserialize)]
 pub struct UserAuth {
   pub sub: String,
@@ -11,3 +14,108 @@ pub struct UserAuth {
   pub iat: i64,
   pub exp: i64,
 }
+
+impl UserAuth {
+  pub fn has_permission(&self, required: &str) -> bool {
+    self
+      .permissions
+      .iter()
+      .any(|p| permission_matches(p, required))
+  }
+
+  pub fn has_any_permission(&self, required: &[&str]) -> bool {
+    required.iter().any(|r| self.has_permission(r))
+  }
+
+  pub fn has_all_permissions(&self, required: &[&str]) -> bool {
+    required.iter().all(|r| self.has_permission(r))
+  }
+}
+
+/// Permissions are `*`, `resource:*` or `resource:action`, e.g. `award:create`.
+pub fn is_valid_permission(permission: &str) -> bool {
+  if permission == PERMISSION_WILDCARD {
+    return true;
+  }
+
+  let mut parts = permission.splitn(2, PERMISSION_SEPARATOR);
+  match (parts.next(), parts.next()) {
+    (Some(resource), Some(action)) => {
+      let is_name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+      is_name(resource) && (action == PERMISSION_WILDCARD || is_name(action))
+    }
+    _ => false,
+  }
+}
+
+pub fn permission_matches(granted: &str, required: &str) -> bool {
+  if granted == PERMISSION_WILDCARD || granted == required {
+    return true;
+  }
+
+  match granted.strip_suffix(PERMISSION_WILDCARD) {
+    Some(prefix) => prefix.ends_with(PERMISSION_SEPARATOR) && required.starts_with(prefix),
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resource_wildcard_matches_actions_on_that_resource_only() {
+    assert!(permission_matches("award:*", "award:create"));
+    assert!(permission_matches(
+      "award:*",
+      "award:updateClassificationNote"
+    ));
+    assert!(!permission_matches("award:*", "user:createAdmin"));
+    assert!(!permission_matches("award:*", "awards:list"));
+  }
+
+  #[test]
+  fn global_wildcard_and_exact_permissions_match() {
+    assert!(permission_matches("*", "role:grant"));
+    assert!(permission_matches("role:grant", "role:grant"));
+    assert!(!permission_matches("role:grant", "role:revoke"));
+  }
+
+  #[test]
+  fn only_namespaced_permissions_are_valid() {
+    assert!(is_valid_permission("*"));
+    assert!(is_valid_permission("award:*"));
+    assert!(is_valid_permission("apiKey:create"));
+    assert!(!is_valid_permission("createAward"));
+    assert!(!is_valid_permission("award:"));
+    assert!(!is_valid_permission(":create"));
+    assert!(!is_valid_permission("award:create:all"));
+  }
+
+  fn auth(permissions: &[&str]) -> UserAuth {
+    UserAuth {
+      sub: String::from("user"),
+      login_id: String::from("user"),
+      session_id: String::from("session"),
+      permissions: permissions.iter().map(|p| p.to_string()).collect(),
+      iat: 0,
+      exp: 0,
+    }
+  }
+
+  #[test]
+  fn all_permissions_are_required_for_a_match_on_all() {
+    let u = auth(&["award:*", "role:list"]);
+    assert!(u.has_all_permissions(&["award:create", "award:get", "role:list"]));
+    assert!(!u.has_all_permissions(&["award:create", "role:grant"]));
+    assert!(u.has_all_permissions(&[]));
+  }
+
+  #[test]
+  fn one_permission_is_enough_for_a_match_on_any() {
+    let u = auth(&["award:*", "role:list"]);
+    assert!(u.has_any_permission(&["role:grant", "role:list"]));
+    assert!(!u.has_any_permission(&["role:grant", "user:list"]));
+    assert!(!u.has_any_permission(&[]));
+  }
+}
@@ -1,27 +1,518 @@
-use serde::Deserialize;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 
-#[derive(Deserialize, Clone)]
+#[derive(Clone, Copy)]
+pub enum ConfigValueKind {
+  String,
+  Boolean,
+  Integer,
+  StringList,
+}
+
+pub struct ConfigKey {
+  pub path: &'static str,
+  pub kind: ConfigValueKind,
+  /// Whether a changed value is picked up on reload, or only on restart.
+  pub reloadable: bool,
+}
+
+pub const OPTIONAL_CONFIG_SECTIONS: &[&str] = &["server.tls", "cors", "oidc"];
+
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+  ConfigKey {
+    path: "app_name",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "log.level",
+    kind: ConfigValueKind::String,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "log.format",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "access_log.enabled",
+    kind: ConfigValueKind::Boolean,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "access_log.log_bodies",
+    kind: ConfigValueKind::Boolean,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "access_log.redacted_fields",
+    kind: ConfigValueKind::StringList,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.session.capacity",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.session.per_minute",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.awards.capacity",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.awards.per_minute",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.users.capacity",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.users.per_minute",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "rate_limit.trusted_proxies",
+    kind: ConfigValueKind::StringList,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "server.host",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "server.port",
+    kind: ConfigValueKind::Integer,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "server.body_limit_bytes",
+    kind: ConfigValueKind::Integer,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "server.tls.cert_path",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "server.tls.key_path",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "cors.allowed_origins",
+    kind: ConfigValueKind::StringList,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "cors.allowed_methods",
+    kind: ConfigValueKind::StringList,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "cors.allowed_headers",
+    kind: ConfigValueKind::StringList,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "cors.max_age_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "health.ready_timeout_ms",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mongo.uri",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "mongo.database",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "session.secret_length",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "session.idle_timeout_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "session.max_lifetime_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "jwt.secret",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "jwt.exp_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mfa.issuer",
+    kind: ConfigValueKind::String,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mfa.challenge_lifetime_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mfa.max_attempts",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mfa.recovery_code_count",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "mfa.recovery_code_length",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "api_key.secret_length",
+    kind: ConfigValueKind::Integer,
+    reloadable: true,
+  },
+  ConfigKey {
+    path: "oidc.issuer",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.client_id",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.client_secret",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.redirect_uri",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.scopes",
+    kind: ConfigValueKind::StringList,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.default_role_id",
+    kind: ConfigValueKind::String,
+    reloadable: false,
+  },
+  ConfigKey {
+    path: "oidc.login_lifetime_sec",
+    kind: ConfigValueKind::Integer,
+    reloadable: false,
+  },
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogConfig {
+  pub level: String,
+  pub format: String,
+}
+
+impl LogConfig {
+  pub fn level_filter(&self) -> LevelFilter {
+    self.level.parse().unwrap_or(LevelFilter::Debug)
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AccessLogConfig {
+  pub enabled: bool,
+  pub log_bodies: bool,
+  pub redacted_fields: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimitGroupConfig {
+  pub capacity: u32,
+  pub per_minute: u32,
+}
+
+impl RateLimitGroupConfig {
+  pub fn refill_per_sec(&self) -> f64 {
+    f64::from(self.per_minute) / 60.0
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimitConfig {
+  pub session: RateLimitGroupConfig,
+  pub awards: RateLimitGroupConfig,
+  pub users: RateLimitGroupConfig,
+  /// Addresses of the reverse proxies whose X-Forwarded-For header is believed.
+  pub trusted_proxies: Vec<String>,
+}
+
+impl RateLimitConfig {
+  pub fn trusted_proxy_addrs(&self) -> Vec<IpAddr> {
+    self
+      .trusted_proxies
+      .iter()
+      .filter_map(|p| p.parse().ok())
+      .collect()
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TlsConfig {
+  pub cert_path: String,
+  pub key_path: String,
+}
+
+impl TlsConfig {
+  /// Checks the certificate and key exist up front, as warp panics when it cannot read them.
+  pub fn check_files(&self) -> Result<(), String> {
+    for path in &[&self.cert_path, &self.key_path] {
+      if let Err(e) = std::fs::metadata(path) {
+        return Err(format!("Could not read TLS file '{}': {}", path, e));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerConfig {
+  pub host: String,
+  pub port: u16,
+  pub body_limit_bytes: u64,
+  pub tls: Option<TlsConfig>,
+}
+
+impl ServerConfig {
+  pub fn bind_addr(&self) -> Result<SocketAddr, String> {
+    match (self.host.as_str(), self.port).to_socket_addrs() {
+      Ok(mut addrs) => addrs
+        .next()
+        .ok_or_else(|| format!("server.host '{}' has no addresses", self.host)),
+      Err(e) => Err(format!("Could not resolve server.host '{}': {}", self.host, e)),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CorsConfig {
+  pub allowed_origins: Vec<String>,
+  pub allowed_methods: Vec<String>,
+  pub allowed_headers: Vec<String>,
+  pub max_age_sec: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HealthConfig {
+  pub ready_timeout_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MongoConfig {
   pub uri: String,
   pub database: String,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SessionConfig {
   pub secret_length: usize,
-  pub lifetime_sec: i64,
+  pub idle_timeout_sec: i64,
+  pub max_lifetime_sec: i64,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct JwtConfig {
   pub secret: String,
   pub exp_sec: i64,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MfaConfig {
+  pub issuer: String,
+  pub challenge_lifetime_sec: i64,
+  pub max_attempts: u32,
+  pub recovery_code_count: usize,
+  pub recovery_code_length: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiKeyConfig {
+  pub secret_length: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OidcConfig {
+  pub issuer: String,
+  pub client_id: String,
+  pub client_secret: String,
+  pub redirect_uri: String,
+  pub scopes: Vec<String>,
+  pub default_role_id: String,
+  pub login_lifetime_sec: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AppConfig {
   pub app_name: String,
+  pub log: LogConfig,
+  pub access_log: AccessLogConfig,
+  pub rate_limit: RateLimitConfig,
+  pub server: ServerConfig,
+  pub cors: Option<CorsConfig>,
+  pub health: HealthConfig,
   pub mongo: MongoConfig,
   pub session: SessionConfig,
   pub jwt: JwtConfig,
+  pub mfa: MfaConfig,
+  pub api_key: ApiKeyConfig,
+  pub oidc: Option<OidcConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing;
+  use serde_json::Value;
+  use std::collections::BTreeMap;
+
+  /// The test config with every optional section set, so all of its keys are serialised.
+  fn full_config() -> AppConfig {
+    let mut config = testing::config();
+    config.server.tls = Some(TlsConfig {
+      cert_path: String::from("cert.pem"),
+      key_path: String::from("key.pem"),
+    });
+    config.cors = Some(CorsConfig {
+      allowed_origins: Vec::new(),
+      allowed_methods: Vec::new(),
+      allowed_headers: Vec::new(),
+      max_age_sec: 0,
+    });
+    config.oidc = Some(OidcConfig {
+      issuer: String::new(),
+      client_id: String::new(),
+      client_secret: String::new(),
+      redirect_uri: String::new(),
+      scopes: Vec::new(),
+      default_role_id: String::new(),
+      login_lifetime_sec: 0,
+    });
+    config
+  }
+
+  fn leaves(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+      Value::Object(fields) => {
+        for (k, v) in fields {
+          let path = if prefix.is_empty() {
+            k.clone()
+          } else {
+            format!("{}.{}", prefix, k)
+          };
+          leaves(&path, v, out);
+        }
+      }
+      _ => {
+        out.insert(String::from(prefix), value.clone());
+      }
+    }
+  }
+
+  #[test]
+  fn config_keys_match_the_config_structs() {
+    let mut config = BTreeMap::new();
+    leaves("", &serde_json::to_value(full_config()).unwrap(), &mut config);
+
+    let listed: Vec<&str> = CONFIG_KEYS.iter().map(|k| k.path).collect();
+    let unlisted: Vec<&String> = config.keys().filter(|k| !listed.contains(&k.as_str())).collect();
+    assert!(unlisted.is_empty(), "missing from CONFIG_KEYS: {:?}", unlisted);
+
+    for key in CONFIG_KEYS {
+      let value = config.get(key.path);
+      let matches_kind = match (key.kind, value) {
+        (ConfigValueKind::String, Some(Value::String(_))) => true,
+        (ConfigValueKind::Boolean, Some(Value::Bool(_))) => true,
+        (ConfigValueKind::Integer, Some(Value::Number(n))) => n.is_i64() || n.is_u64(),
+        (ConfigValueKind::StringList, Some(Value::Array(_))) => true,
+        _ => false,
+      };
+      assert!(matches_kind, "{} is not a config field of its kind", key.path);
+    }
+  }
+
+  #[test]
+  fn bind_addr_resolves_host_and_port() {
+    let mut server = testing::config().server;
+    server.host = String::from("0.0.0.0");
+    server.port = 8443;
+    assert_eq!(
+      server.bind_addr().unwrap(),
+      "0.0.0.0:8443".parse::<SocketAddr>().unwrap()
+    );
+
+    server.host = String::from("localhost");
+    let addr = server.bind_addr().unwrap();
+    assert!(addr.ip().is_loopback());
+    assert_eq!(addr.port(), 8443);
+
+    server.host = String::from("not a host name");
+    assert!(server.bind_addr().is_err());
+  }
+
+  #[test]
+  fn tls_files_must_exist() {
+    let tls = TlsConfig {
+      cert_path: String::from("Cargo.toml"),
+      key_path: String::from("Cargo.toml"),
+    };
+    assert!(tls.check_files().is_ok());
+
+    let tls = TlsConfig {
+      cert_path: String::from("Cargo.toml"),
+      key_path: String::from("missing-key.pem"),
+    };
+    assert!(tls.check_files().unwrap_err().contains("missing-key.pem"));
+  }
+
+  #[test]
+  fn optional_sections_are_config_keys_prefixes() {
+    for section in OPTIONAL_CONFIG_SECTIONS {
+      let prefix = format!("{}.", section);
+      assert!(
+        CONFIG_KEYS.iter().any(|k| k.path.starts_with(&prefix)),
+        "{} has no keys",
+        section
+      );
+    }
+  }
 }
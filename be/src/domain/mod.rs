@@ -3,4 +3,10 @@ mod auth;
 
 pub use config::AppConfig;
 pub use config::JwtConfig;
-pub use auth::UserAuth;
+pub use config::LogConfig;
+pub use config::AccessLogConfig;
+pub use config::CorsConfig;
+pub use config::OidcConfig;
+pub use config::{RateLimitConfig, RateLimitGroupConfig};
+pub use config::{ConfigKey, ConfigValueKind, CONFIG_KEYS, OPTIONAL_CONFIG_SECTIONS};
+pub use auth::{is_valid_permission, permission_matches, UserAuth};
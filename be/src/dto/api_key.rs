@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+  pub name: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "expireAt")]
+  pub expire_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeyCreatedResponse {
+  pub id: String,
+  pub name: String,
+  pub key: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "expireAt")]
+  pub expire_at: Option<DateTime<Utc>>,
+}
@@ -17,6 +17,28 @@ pub struct LogInResponse {
   pub token: String,
 }
 
+#[derive(Serialize)]
+pub struct MfaChallengeResponse {
+  #[serde(rename = "challengeId")]
+  pub challenge_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LogInResult {
+  Success(LogInResponse),
+  MfaRequired(MfaChallengeResponse),
+}
+
+#[derive(Deserialize)]
+pub struct MfaLogInRequest {
+  #[serde(rename = "challengeId")]
+  pub challenge_id: String,
+  pub code: Option<String>,
+  #[serde(rename = "recoveryCode")]
+  pub recovery_code: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct RefreshRequest {
   #[serde(rename = "sessionId")]
@@ -33,6 +33,30 @@ pub struct CreateAwardRequest {
   pub classifications: Vec<AwardClassification>,
 }
 
+#[derive(Serialize)]
+pub struct AwardDto {
+  pub id: String,
+  pub external_id: String,
+  pub name: String,
+  #[serde(rename = "industryName")]
+  pub industry_name: String,
+  #[serde(rename = "commonRule")]
+  pub common_rule: Option<String>,
+  #[serde(rename = "alternateIds")]
+  pub alternate_ids: Vec<AwardAlternateId>,
+  #[serde(rename = "operativeDate")]
+  pub operative_date: DateTime<Utc>,
+  #[serde(rename = "expiredDate")]
+  pub expired_date: Option<DateTime<Utc>>,
+  pub classifications: Vec<AwardClassification>,
+}
+
+#[derive(Deserialize)]
+pub struct ListAwardsQuery {
+  #[serde(rename = "externalId")]
+  pub external_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct UpdateAwardExpiryDate {
   #[serde(rename = "expiredAt")]
@@ -5,6 +5,9 @@ pub const ERR_UNAUTHENTICATED: &str = "ERR1001";
 pub const ERR_UNAUTHORIZED: &str = "ERR1002";
 pub const ERR_NOT_FOUND: &str = "ERR1003";
 pub const ERR_INVALID_PARAMETERS: &str = "ERR1004";
+pub const ERR_PAYLOAD_TOO_LARGE: &str = "ERR1005";
+pub const ERR_LENGTH_REQUIRED: &str = "ERR1006";
+pub const ERR_RATE_LIMITED: &str = "ERR1007";
 
 #[derive(Serialize)]
 pub struct ErrorResponse {
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+pub const HEALTH_STATUS_UP: &str = "UP";
+pub const HEALTH_STATUS_DOWN: &str = "DOWN";
+
+#[derive(Serialize)]
+pub struct LivenessResponse {
+  pub status: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct DependencyStatus {
+  pub name: &'static str,
+  pub status: &'static str,
+  #[serde(rename = "latencyMs")]
+  pub latency_ms: u128,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+  pub status: &'static str,
+  pub dependencies: Vec<DependencyStatus>,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct MfaEnrolmentResponse {
+  pub secret: String,
+  #[serde(rename = "otpauthUri")]
+  pub otpauth_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct MfaConfirmRequest {
+  pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct MfaRecoveryCodesResponse {
+  #[serde(rename = "recoveryCodes")]
+  pub recovery_codes: Vec<String>,
+}
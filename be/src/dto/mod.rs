@@ -1,12 +1,31 @@
+mod api_key;
 mod auth;
 mod award;
 pub mod error;
+pub mod health;
+mod mfa;
+mod oidc;
+mod role;
 mod users;
+mod version;
 
-pub use auth::{LogInRequest, LogInResponse, RefreshRequest, RefreshResponse};
+pub use api_key::{ApiKeyCreatedResponse, CreateApiKeyRequest};
+pub use auth::{
+  LogInRequest, LogInResponse, LogInResult, MfaChallengeResponse, MfaLogInRequest, RefreshRequest,
+  RefreshResponse,
+};
 pub use award::{
-  AwardAlternateId, AwardClassification, CreateAwardRequest, UpdateAwardClassificationNoteRequest,
-  UpdateAwardClassificatonStatusRequest, UpdateAwardExpiryDate,
+  AwardAlternateId, AwardClassification, AwardDto, CreateAwardRequest, ListAwardsQuery,
+  UpdateAwardClassificationNoteRequest, UpdateAwardClassificatonStatusRequest,
+  UpdateAwardExpiryDate,
 };
 pub use error::ErrorResponse;
-pub use users::{CreateSystemAdminUserRequest, UserDto};
+pub use health::{DependencyStatus, LivenessResponse, ReadinessResponse};
+pub use mfa::{MfaConfirmRequest, MfaEnrolmentResponse, MfaRecoveryCodesResponse};
+pub use oidc::{OidcAuthorizationResponse, OidcCallbackRequest};
+pub use role::{CreateRoleRequest, RoleDto};
+pub use users::{
+  CreateServiceUserRequest, CreateSystemAdminUserRequest, ResetPasswordRequest,
+  UpdateUserDisabledRequest, UserDto,
+};
+pub use version::VersionResponse;
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct OidcAuthorizationResponse {
+  #[serde(rename = "authorizationUrl")]
+  pub authorization_url: String,
+  pub state: String,
+}
+
+#[derive(Deserialize)]
+pub struct OidcCallbackRequest {
+  pub code: String,
+  pub state: String,
+}
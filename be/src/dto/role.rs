@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateRoleRequest {
+  pub name: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "includedRoleIds", default)]
+  pub included_role_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RoleDto {
+  pub id: String,
+  pub name: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "includedRoleIds")]
+  pub included_role_ids: Vec<String>,
+}
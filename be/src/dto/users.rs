@@ -9,6 +9,24 @@ pub struct CreateSystemAdminUserRequest {
   pub role_ids: Vec<String>,
 }
 
+#[derive(Deserialize)]
+pub struct CreateServiceUserRequest {
+  #[serde(rename = "loginId")]
+  pub login_id: String,
+  #[serde(rename = "roleIds")]
+  pub role_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateUserDisabledRequest {
+  pub disabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+  pub password: String,
+}
+
 #[derive(Serialize)]
 pub struct UserDto {
   pub id: String,
@@ -20,4 +38,5 @@ pub struct UserDto {
   pub login_id: String,
   #[serde(rename = "roleIds")]
   pub role_ids: Vec<String>,
+  pub disabled: bool,
 }
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct VersionResponse {
+  pub version: &'static str,
+  #[serde(rename = "gitCommit")]
+  pub git_commit: &'static str,
+  #[serde(rename = "buildTimestamp")]
+  pub build_timestamp: DateTime<Utc>,
+  #[serde(rename = "apiVersion")]
+  pub api_version: &'static str,
+  #[serde(rename = "schemaVersion")]
+  pub schema_version: Option<u32>,
+}
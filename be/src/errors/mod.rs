@@ -2,8 +2,10 @@ mod server;
 mod authentication;
 mod authorization;
 mod validation;
+mod rate_limit;
 
 pub use server::ServerError;
 pub use authentication::AuthenticationError;
 pub use authorization::AuthorizationError;
 pub use validation::ValidationError;
+pub use rate_limit::RateLimitError;
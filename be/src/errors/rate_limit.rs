@@ -0,0 +1,14 @@
+use warp::reject::Reject;
+
+#[derive(Debug)]
+pub struct RateLimitError {
+  pub retry_after_sec: u64,
+}
+
+impl RateLimitError {
+  pub fn new(retry_after_sec: u64) -> RateLimitError {
+    RateLimitError { retry_after_sec }
+  }
+}
+
+impl Reject for RateLimitError {}
@@ -0,0 +1,203 @@
+use crate::domain::AccessLogConfig;
+use crate::logging;
+use crate::services::ConfigService;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::http::header::CONTENT_TYPE;
+use warp::hyper::Body;
+use warp::log::{Info, Log};
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::Reply;
+
+const ACCESS_LOG_TARGET: &str = "ausawards_be::access";
+const REDACTED: &str = "[REDACTED]";
+const CONTENT_TYPE_JSON: &str = "application/json";
+/// Redacted from every logged body, on top of the configured `access_log.redacted_fields`.
+const BASELINE_REDACTED_FIELDS: &[&str] = &[
+  "password",
+  "sessionSecret",
+  "token",
+  "secret",
+  "recoveryCodes",
+  "key",
+];
+/// The OIDC authorization code is only redacted on the callback, since `code` names the error
+/// code of every other error response.
+const OIDC_CALLBACK_PATH: &str = "/session/oidc/callback";
+const FIELD_OIDC_CODE: &str = "code";
+
+/// The fields to redact from the bodies of requests to `path`.
+pub fn redacted_fields(path: &str, config: &AccessLogConfig) -> Vec<String> {
+  let mut fields: Vec<String> = BASELINE_REDACTED_FIELDS
+    .iter()
+    .map(|f| String::from(*f))
+    .chain(config.redacted_fields.iter().cloned())
+    .collect();
+  if path == OIDC_CALLBACK_PATH {
+    fields.push(String::from(FIELD_OIDC_CODE));
+  }
+  fields
+}
+
+/// Copies a JSON body, replacing the value of every field named in `fields`, at any depth.
+pub fn redact(value: &Value, fields: &[String]) -> Value {
+  match value {
+    Value::Object(map) => Value::Object(
+      map
+        .iter()
+        .map(|(k, v)| {
+          if fields.iter().any(|f| f == k) {
+            (k.clone(), Value::from(REDACTED))
+          } else {
+            (k.clone(), redact(v, fields))
+          }
+        })
+        .collect(),
+    ),
+    Value::Array(items) => Value::Array(items.iter().map(|v| redact(v, fields)).collect()),
+    _ => value.clone(),
+  }
+}
+
+pub fn filter(
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+) -> Log<impl Fn(Info) + Clone + Send> {
+  warp::log::custom(move |info: Info| {
+    if !config_service.get_config().access_log.enabled {
+      return;
+    }
+
+    let client_ip = info
+      .remote_addr()
+      .map(|a| a.ip().to_string())
+      .unwrap_or_else(|| String::from("-"));
+    let user_id = logging::user_id().unwrap_or_else(|| String::from("-"));
+    tracing::info!(
+      target: ACCESS_LOG_TARGET,
+      method = %info.method(),
+      path = info.path(),
+      status = info.status().as_u16(),
+      latency_ms = info.elapsed().as_millis() as u64,
+      user_id = user_id.as_str(),
+      client_ip = client_ip.as_str(),
+      "request completed"
+    );
+  })
+}
+
+pub async fn log_response_body(
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  path: FullPath,
+  reply: impl Reply,
+) -> Result<Response, Infallible> {
+  let response = reply.into_response();
+  let config = config_service.get_config().access_log;
+  let is_json = response
+    .headers()
+    .get(CONTENT_TYPE)
+    .map(|h| h.as_bytes().starts_with(CONTENT_TYPE_JSON.as_bytes()))
+    .unwrap_or(false);
+  if !config.log_bodies || !is_json {
+    return Ok(response);
+  }
+
+  let (parts, body) = response.into_parts();
+  let body = match warp::hyper::body::to_bytes(body).await {
+    Ok(b) => b,
+    Err(e) => {
+      log::error!("Error reading response body for logging {:?}", e);
+      return Ok(Response::from_parts(parts, Body::empty()));
+    }
+  };
+  if let Ok(value) = serde_json::from_slice::<Value>(&body) {
+    let fields = redacted_fields(path.as_str(), &config);
+    log::info!("Response body {}", redact(&value, &fields));
+  }
+
+  Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn access_log(redacted_fields: &[&str]) -> AccessLogConfig {
+    AccessLogConfig {
+      enabled: true,
+      log_bodies: true,
+      redacted_fields: redacted_fields.iter().map(|f| String::from(*f)).collect(),
+    }
+  }
+
+  fn fields(names: &[&str]) -> Vec<String> {
+    names.iter().map(|f| String::from(*f)).collect()
+  }
+
+  #[test]
+  fn redacts_fields_at_any_depth() {
+    let body = json!({
+      "loginId": "alice",
+      "password": "hunter2",
+      "profile": { "name": "Alice", "secret": { "nested": true } },
+    });
+    assert_eq!(
+      redact(&body, &fields(&["password", "secret"])),
+      json!({
+        "loginId": "alice",
+        "password": REDACTED,
+        "profile": { "name": "Alice", "secret": REDACTED },
+      })
+    );
+  }
+
+  #[test]
+  fn redacts_fields_inside_arrays() {
+    let body = json!([
+      { "id": "k1", "key": "ak_1" },
+      { "id": "k2", "scopes": [{ "key": "ak_2" }] },
+      "key",
+    ]);
+    assert_eq!(
+      redact(&body, &fields(&["key"])),
+      json!([
+        { "id": "k1", "key": REDACTED },
+        { "id": "k2", "scopes": [{ "key": REDACTED }] },
+        "key",
+      ])
+    );
+  }
+
+  #[test]
+  fn baseline_fields_are_redacted_whatever_the_config() {
+    let fields = redacted_fields("/session", &access_log(&[]));
+    let body = json!({ "password": "p", "sessionSecret": "s", "recoveryCodes": ["c"] });
+    assert_eq!(
+      redact(&body, &fields),
+      json!({ "password": REDACTED, "sessionSecret": REDACTED, "recoveryCodes": REDACTED })
+    );
+  }
+
+  #[test]
+  fn configured_fields_add_to_the_baseline() {
+    let fields = redacted_fields("/users", &access_log(&["otpauthUri"]));
+    let body = json!({ "otpauthUri": "otpauth://", "token": "t", "name": "n" });
+    assert_eq!(
+      redact(&body, &fields),
+      json!({ "otpauthUri": REDACTED, "token": REDACTED, "name": "n" })
+    );
+  }
+
+  #[test]
+  fn code_is_only_redacted_on_the_oidc_callback() {
+    let body = json!({ "code": "authz-code", "state": "s" });
+    let callback = redacted_fields(OIDC_CALLBACK_PATH, &access_log(&[]));
+    assert_eq!(redact(&body, &callback)["code"], REDACTED);
+
+    let error = json!({ "code": "VALIDATION_ERROR", "message": "Invalid request" });
+    let elsewhere = redacted_fields("/awards", &access_log(&[]));
+    assert_eq!(redact(&error, &elsewhere), error);
+  }
+}
@@ -1,39 +1,60 @@
 use crate::domain::UserAuth;
 use crate::errors::{AuthenticationError, AuthorizationError};
-use crate::services::TokenService;
+use crate::logging;
+use crate::services::{ApiKeyService, TokenService};
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Rejection};
 
 const AUTH_PREFIX: &str = "Bearer ";
+const API_KEY_PREFIX: &str = "ApiKey ";
 
 pub trait AuthenticationFilter {
   fn auth_user(&self) -> BoxedFilter<(UserAuth,)>;
   fn authenticated(&self) -> BoxedFilter<()>;
   fn permissioned(&self, permission: &'static str) -> BoxedFilter<()>;
+  fn permissioned_user(&self, permission: &'static str) -> BoxedFilter<(UserAuth,)>;
+  fn permissioned_any(&self, permissions: &'static [&'static str]) -> BoxedFilter<()>;
+  fn permissioned_all(&self, permissions: &'static [&'static str]) -> BoxedFilter<()>;
 }
 
 pub struct AuthenticationFilterImpl {
   token_service: Arc<dyn TokenService + Send + Sync>,
+  api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
 }
 
 impl AuthenticationFilterImpl {
-  pub fn new(token_service: Arc<dyn TokenService + Send + Sync>) -> AuthenticationFilterImpl {
-    AuthenticationFilterImpl { token_service }
+  pub fn new(
+    token_service: Arc<dyn TokenService + Send + Sync>,
+    api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
+  ) -> AuthenticationFilterImpl {
+    AuthenticationFilterImpl {
+      token_service,
+      api_key_service,
+    }
   }
 }
 
 impl AuthenticationFilter for AuthenticationFilterImpl {
   fn auth_user(&self) -> BoxedFilter<(UserAuth,)> {
     let token_service = Arc::clone(&self.token_service);
+    let api_key_service = Arc::clone(&self.api_key_service);
     warp::header("Authorization")
       .and_then(move |h: String| {
         let ts_local = Arc::clone(&token_service);
+        let aks_local = Arc::clone(&api_key_service);
         async move {
-          if !h.starts_with(AUTH_PREFIX) {
-            return Err(warp::reject::custom(AuthenticationError::new()));
+          let auth = if let Some(token) = h.strip_prefix(AUTH_PREFIX) {
+            ts_local.parse_token(token)
+          } else if let Some(key) = h.strip_prefix(API_KEY_PREFIX) {
+            aks_local.authenticate(key).await
+          } else {
+            Err(warp::reject::custom(AuthenticationError::new()))
+          };
+          if let Ok(u) = &auth {
+            logging::record_user(&u.sub);
           }
-          ts_local.parse_token(&h[AUTH_PREFIX.len()..])
+          auth
         }
       })
       .boxed()
@@ -48,18 +69,141 @@ impl AuthenticationFilter for AuthenticationFilterImpl {
   }
 
   fn permissioned(&self, permission: &'static str) -> BoxedFilter<()> {
+    self
+      .permissioned_user(permission)
+      .and_then(|_| async { Ok(()) as Result<(), Rejection> })
+      .untuple_one()
+      .boxed()
+  }
+
+  fn permissioned_user(&self, permission: &'static str) -> BoxedFilter<(UserAuth,)> {
     self
       .auth_user()
       .and_then(move |u: UserAuth| async move {
-        for p in u.permissions {
-          if p == permission {
-            return Ok(());
-          }
+        if u.has_permission(permission) {
+          Ok(u)
+        } else {
+          Err(warp::reject::custom(AuthorizationError::new()))
         }
+      })
+      .boxed()
+  }
 
-        Err(warp::reject::custom(AuthorizationError::new()))
+  fn permissioned_any(&self, permissions: &'static [&'static str]) -> BoxedFilter<()> {
+    self
+      .auth_user()
+      .and_then(move |u: UserAuth| async move {
+        if u.has_any_permission(permissions) {
+          Ok(())
+        } else {
+          Err(warp::reject::custom(AuthorizationError::new()))
+        }
       })
       .untuple_one()
       .boxed()
   }
+
+  fn permissioned_all(&self, permissions: &'static [&'static str]) -> BoxedFilter<()> {
+    self
+      .auth_user()
+      .and_then(move |u: UserAuth| async move {
+        if u.has_all_permissions(permissions) {
+          Ok(())
+        } else {
+          Err(warp::reject::custom(AuthorizationError::new()))
+        }
+      })
+      .untuple_one()
+      .boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dto::{ApiKeyCreatedResponse, CreateApiKeyRequest};
+  use crate::model::{Role, User, UserSession};
+  use async_trait::async_trait;
+  use warp::http::StatusCode;
+
+  const REQUIRED: &[&str] = &["role:list", "user:list"];
+
+  /// Accepts tokens listing the caller's permissions, separated by commas.
+  struct StubTokenService {}
+
+  impl TokenService for StubTokenService {
+    fn create_token(
+      &self,
+      _user: &User,
+      _session: &UserSession,
+      _roles: Vec<&Role>,
+    ) -> Result<String, Rejection> {
+      Err(warp::reject())
+    }
+
+    fn parse_token(&self, token: &str) -> Result<UserAuth, Rejection> {
+      Ok(UserAuth {
+        sub: String::from("user"),
+        login_id: String::from("user"),
+        session_id: String::from("session"),
+        permissions: token.split(',').map(String::from).collect(),
+        iat: 0,
+        exp: i64::MAX,
+      })
+    }
+  }
+
+  struct StubApiKeyService {}
+
+  #[async_trait]
+  impl ApiKeyService for StubApiKeyService {
+    async fn create_api_key(
+      &self,
+      _caller: &UserAuth,
+      _user_id: &str,
+      _request: CreateApiKeyRequest,
+    ) -> Result<ApiKeyCreatedResponse, Rejection> {
+      Err(warp::reject())
+    }
+
+    async fn revoke_api_key(&self, _user_id: &str, _key_id: &str) -> Result<(), Rejection> {
+      Err(warp::reject())
+    }
+
+    async fn authenticate(&self, _key: &str) -> Result<UserAuth, Rejection> {
+      Err(warp::reject::custom(AuthenticationError::new()))
+    }
+  }
+
+  async fn status(filter: BoxedFilter<()>, permissions: &str) -> StatusCode {
+    let filter = filter
+      .map(warp::reply)
+      .recover(crate::handlers::error::handler);
+    warp::test::request()
+      .header("Authorization", format!("{}{}", AUTH_PREFIX, permissions))
+      .reply(&filter)
+      .await
+      .status()
+  }
+
+  #[tokio::test]
+  async fn permissioned_all_requires_every_permission() {
+    let filter = AuthenticationFilterImpl::new(
+      Arc::new(StubTokenService {}),
+      Arc::new(StubApiKeyService {}),
+    );
+
+    assert_eq!(
+      status(filter.permissioned_all(REQUIRED), "role:list,user:*").await,
+      StatusCode::OK
+    );
+    assert_eq!(
+      status(filter.permissioned_all(REQUIRED), "role:list").await,
+      StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+      status(filter.permissioned_any(REQUIRED), "role:list").await,
+      StatusCode::OK
+    );
+  }
 }
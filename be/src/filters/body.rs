@@ -0,0 +1,109 @@
+use crate::errors::ValidationError;
+use crate::filters::access_log;
+use crate::services::ConfigService;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+#[derive(Clone)]
+pub struct BodyFilter {
+  limit: u64,
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+}
+
+impl BodyFilter {
+  pub fn new(config_service: Arc<dyn ConfigService + Send + Sync>) -> BodyFilter {
+    BodyFilter {
+      limit: config_service.get_config().server.body_limit_bytes,
+      config_service,
+    }
+  }
+
+  pub fn json<T: DeserializeOwned + Send + 'static>(&self) -> BoxedFilter<(T,)> {
+    let config_service = Arc::clone(&self.config_service);
+    warp::body::content_length_limit(self.limit)
+      .and(warp::path::full())
+      .and(warp::body::bytes())
+      .and_then(move |path: FullPath, body: Bytes| {
+        let cs_local = Arc::clone(&config_service);
+        async move {
+          let value: Value = match serde_json::from_slice(&body) {
+            Ok(v) => v,
+            Err(e) => {
+              log::debug!("Malformed request body {}", e);
+              return Err(warp::reject::custom(ValidationError::new()));
+            }
+          };
+
+          let config = cs_local.get_config().access_log;
+          if config.log_bodies {
+            log::info!(
+              "Request body {}",
+              access_log::redact(&value, &access_log::redacted_fields(path.as_str(), &config))
+            );
+          }
+
+          serde_json::from_value(value).map_err(|e| {
+            log::debug!("Invalid request body {}", e);
+            warp::reject::custom(ValidationError::new())
+          }) as Result<T, Rejection>
+        }
+      })
+      .boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{config, StubConfigService};
+  use serde::Deserialize;
+  use warp::http::StatusCode;
+
+  #[derive(Deserialize)]
+  struct Named {
+    name: String,
+  }
+
+  async fn status(request: warp::test::RequestBuilder) -> StatusCode {
+    let filter = BodyFilter::new(Arc::new(StubConfigService { config: config() }))
+      .json()
+      .map(|n: Named| warp::reply::json(&n.name))
+      .recover(crate::handlers::error::handler);
+    request.method("POST").reply(&filter).await.status()
+  }
+
+  fn json(body: &str) -> warp::test::RequestBuilder {
+    warp::test::request().body(body)
+  }
+
+  #[tokio::test]
+  async fn bodies_within_the_limit_are_parsed() {
+    assert_eq!(status(json(r#"{"name":"award"}"#)).await, StatusCode::OK);
+  }
+
+  #[tokio::test]
+  async fn bodies_over_the_limit_are_rejected() {
+    let limit = config().server.body_limit_bytes as usize;
+    let body = format!(r#"{{"name":"{}"}}"#, "a".repeat(limit));
+    assert_eq!(status(json(&body)).await, StatusCode::PAYLOAD_TOO_LARGE);
+  }
+
+  #[tokio::test]
+  async fn bodies_need_a_content_length() {
+    assert_eq!(
+      status(warp::test::request()).await,
+      StatusCode::LENGTH_REQUIRED
+    );
+  }
+
+  #[tokio::test]
+  async fn malformed_and_mistyped_bodies_are_invalid() {
+    assert_eq!(status(json("{")).await, StatusCode::BAD_REQUEST);
+    assert_eq!(status(json(r#"{"name":1}"#)).await, StatusCode::BAD_REQUEST);
+  }
+}
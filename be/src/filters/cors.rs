@@ -0,0 +1,101 @@
+use crate::domain::CorsConfig;
+use crate::logging::HEADER_REQUEST_ID;
+use warp::http::header::RETRY_AFTER;
+
+pub const ORIGIN_ANY: &str = "*";
+
+pub fn cors(config: &CorsConfig) -> warp::cors::Builder {
+  let builder = warp::cors()
+    .allow_methods(config.allowed_methods.iter().map(|m| m.as_str()))
+    .allow_headers(config.allowed_headers.iter().map(|h| h.as_str()))
+    .expose_headers(vec![HEADER_REQUEST_ID, RETRY_AFTER.as_str()])
+    .max_age(config.max_age_sec);
+
+  if config.allowed_origins.iter().any(|o| o == ORIGIN_ANY) {
+    builder.allow_any_origin()
+  } else {
+    builder.allow_origins(config.allowed_origins.iter().map(|o| o.as_str()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use warp::http::header::{
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+    ACCESS_CONTROL_MAX_AGE,
+  };
+  use warp::http::{Response, StatusCode};
+  use warp::hyper::body::Bytes;
+  use warp::Filter;
+
+  const ORIGIN: &str = "https://awards.example.com";
+
+  fn cors_config(allowed_origins: &[&str]) -> CorsConfig {
+    CorsConfig {
+      allowed_origins: allowed_origins.iter().map(|o| String::from(*o)).collect(),
+      allowed_methods: vec![String::from("GET"), String::from("POST")],
+      allowed_headers: vec![String::from("authorization"), String::from("content-type")],
+      max_age_sec: 600,
+    }
+  }
+
+  async fn reply(config: &CorsConfig, request: warp::test::RequestBuilder) -> Response<Bytes> {
+    let filter = warp::any().map(warp::reply).with(cors(config));
+    request.reply(&filter).await
+  }
+
+  fn preflight(origin: &str, method: &str) -> warp::test::RequestBuilder {
+    warp::test::request()
+      .method("OPTIONS")
+      .header("origin", origin)
+      .header("access-control-request-method", method)
+      .header("access-control-request-headers", "authorization")
+  }
+
+  fn header(res: &Response<Bytes>, name: impl warp::http::header::AsHeaderName) -> &str {
+    res.headers().get(name).unwrap().to_str().unwrap()
+  }
+
+  #[tokio::test]
+  async fn preflights_from_allowed_origins_are_answered() {
+    let res = reply(&cors_config(&[ORIGIN]), preflight(ORIGIN, "POST")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header(&res, ACCESS_CONTROL_ALLOW_ORIGIN), ORIGIN);
+    assert_eq!(header(&res, ACCESS_CONTROL_MAX_AGE), "600");
+    let methods = header(&res, ACCESS_CONTROL_ALLOW_METHODS);
+    assert!(methods.contains("GET") && methods.contains("POST"));
+  }
+
+  #[tokio::test]
+  async fn preflights_from_other_origins_or_for_other_methods_are_refused() {
+    let config = cors_config(&[ORIGIN]);
+    let other_origin = reply(&config, preflight("https://evil.example.com", "POST")).await;
+    assert_eq!(other_origin.status(), StatusCode::FORBIDDEN);
+    let other_method = reply(&config, preflight(ORIGIN, "DELETE")).await;
+    assert_eq!(other_method.status(), StatusCode::FORBIDDEN);
+  }
+
+  #[tokio::test]
+  async fn the_wildcard_allows_any_origin() {
+    let res = reply(
+      &cors_config(&[ORIGIN_ANY]),
+      preflight("https://elsewhere.example.org", "GET"),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+      header(&res, ACCESS_CONTROL_ALLOW_ORIGIN),
+      "https://elsewhere.example.org"
+    );
+  }
+
+  #[tokio::test]
+  async fn responses_expose_the_request_id_and_retry_after() {
+    let request = warp::test::request().header("origin", ORIGIN);
+    let res = reply(&cors_config(&[ORIGIN]), request).await;
+    let exposed = header(&res, ACCESS_CONTROL_EXPOSE_HEADERS).to_lowercase();
+    assert!(exposed.contains(HEADER_REQUEST_ID));
+    assert!(exposed.contains(RETRY_AFTER.as_str()));
+  }
+}
@@ -1,3 +1,9 @@
+pub mod access_log;
 pub mod auth;
+pub mod body;
+pub mod cors;
+pub mod rate_limit;
 
 pub use auth::AuthenticationFilter;
+pub use body::BodyFilter;
+pub use rate_limit::RateLimitFilter;
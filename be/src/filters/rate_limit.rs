@@ -0,0 +1,386 @@
+use crate::domain::{RateLimitConfig, RateLimitGroupConfig};
+use crate::errors::RateLimitError;
+use crate::services::{ConfigService, TokenService};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use warp::filters::BoxedFilter;
+use warp::{Filter, Rejection};
+
+pub const GROUP_SESSION: &str = "session";
+pub const GROUP_AWARDS: &str = "awards";
+pub const GROUP_USERS: &str = "users";
+
+const AUTH_PREFIX: &str = "Bearer ";
+const API_KEY_PREFIX: &str = "ApiKey ";
+const HEADER_FORWARDED_FOR: &str = "X-Forwarded-For";
+const API_KEY_DIGEST_BYTES: usize = 16;
+const PRUNE_THRESHOLD: usize = 10_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+struct Bucket {
+  tokens: f64,
+  updated_at: Instant,
+}
+
+impl Bucket {
+  fn new(limit: &RateLimitGroupConfig, now: Instant) -> Bucket {
+    Bucket {
+      tokens: f64::from(limit.capacity),
+      updated_at: now,
+    }
+  }
+
+  fn refill(&mut self, limit: &RateLimitGroupConfig, now: Instant) {
+    let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * limit.refill_per_sec()).min(f64::from(limit.capacity));
+    self.updated_at = now;
+  }
+
+  /// Takes a token, or returns the seconds until one is available.
+  fn consume(&mut self, limit: &RateLimitGroupConfig, now: Instant) -> Result<(), u64> {
+    self.refill(limit, now);
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      return Ok(());
+    }
+
+    let retry_after_sec = ((1.0 - self.tokens) / limit.refill_per_sec()).ceil() as u64;
+    Err(retry_after_sec.max(1))
+  }
+}
+
+struct Buckets {
+  buckets: HashMap<String, Bucket>,
+  pruned_at: Instant,
+}
+
+fn group_limit(config: RateLimitConfig, group: &str) -> Option<RateLimitGroupConfig> {
+  match group {
+    GROUP_SESSION => Some(config.session),
+    GROUP_AWARDS => Some(config.awards),
+    GROUP_USERS => Some(config.users),
+    _ => None,
+  }
+}
+
+/// The peer address, unless the peer is a trusted proxy; then the right-most X-Forwarded-For
+/// entry that is not itself a trusted proxy, as entries left of it are client supplied.
+fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+  if !trusted.contains(&peer) {
+    return peer;
+  }
+
+  let mut client = peer;
+  for entry in forwarded_for.unwrap_or("").rsplit(',') {
+    match entry.trim().parse::<IpAddr>() {
+      Ok(ip) => {
+        client = ip;
+        if !trusted.contains(&ip) {
+          break;
+        }
+      }
+      Err(_) => break,
+    }
+  }
+  client
+}
+
+/// Identifies an API key without keeping its secret in memory or in the logs.
+fn api_key_digest(key: &str) -> String {
+  Sha256::digest(key.as_bytes())
+    .iter()
+    .take(API_KEY_DIGEST_BYTES)
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+/// Token bucket rate limiting per route group. Requests carrying a valid token are limited by the
+/// authenticated user, requests carrying an API key by that key, and anything else by client IP.
+#[derive(Clone)]
+pub struct RateLimitFilter {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  token_service: Arc<dyn TokenService + Send + Sync>,
+  buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimitFilter {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    token_service: Arc<dyn TokenService + Send + Sync>,
+  ) -> RateLimitFilter {
+    RateLimitFilter {
+      config_service,
+      token_service,
+      buckets: Arc::new(Mutex::new(Buckets {
+        buckets: HashMap::new(),
+        pruned_at: Instant::now(),
+      })),
+    }
+  }
+
+  pub fn limit(&self, group: &'static str) -> BoxedFilter<()> {
+    let limiter = self.clone();
+    warp::addr::remote()
+      .and(warp::header::optional::<String>(HEADER_FORWARDED_FOR))
+      .and(warp::header::optional::<String>("Authorization"))
+      .and_then(
+        move |addr: Option<SocketAddr>, forwarded_for: Option<String>, auth: Option<String>| {
+          let limiter = limiter.clone();
+          async move {
+            let client_key = limiter.client_key(addr, forwarded_for, auth);
+            limiter.take(group, client_key)
+          }
+        },
+      )
+      .untuple_one()
+      .boxed()
+  }
+
+  /// Tokens are verified by their signature alone, so a made-up token falls back to the IP bucket.
+  /// API keys can only be verified against the database, which the limiter keeps clear of, so
+  /// they are keyed by a digest of the presented key instead.
+  fn client_key(
+    &self,
+    addr: Option<SocketAddr>,
+    forwarded_for: Option<String>,
+    auth: Option<String>,
+  ) -> String {
+    if let Some(auth) = auth {
+      if let Some(token) = auth.strip_prefix(AUTH_PREFIX) {
+        if let Ok(u) = self.token_service.parse_token(token) {
+          return format!("user:{}", u.sub);
+        }
+      } else if let Some(key) = auth.strip_prefix(API_KEY_PREFIX) {
+        return format!("key:{}", api_key_digest(key));
+      }
+    }
+
+    let trusted = self.config_service.get_config().rate_limit.trusted_proxy_addrs();
+    match addr {
+      Some(a) => format!(
+        "ip:{}",
+        client_ip(a.ip(), forwarded_for.as_deref(), &trusted)
+      ),
+      None => String::from("ip:unknown"),
+    }
+  }
+
+  fn take(&self, group: &str, client_key: String) -> Result<(), Rejection> {
+    let limit = match group_limit(self.config_service.get_config().rate_limit, group) {
+      Some(l) => l,
+      None => return Ok(()),
+    };
+
+    let now = Instant::now();
+    let mut state = self.buckets.lock().unwrap();
+    if state.buckets.len() > PRUNE_THRESHOLD && now.duration_since(state.pruned_at) >= PRUNE_INTERVAL
+    {
+      state
+        .buckets
+        .retain(|_, b| now.duration_since(b.updated_at) < BUCKET_IDLE_TIMEOUT);
+      state.pruned_at = now;
+    }
+
+    let bucket = state
+      .buckets
+      .entry(format!("{}/{}", group, client_key))
+      .or_insert_with(|| Bucket::new(&limit, now));
+    bucket.consume(&limit, now).map_err(|retry_after_sec| {
+      log::debug!(
+        "Rate limit exceeded for {} on {}; retry after {}s",
+        client_key,
+        group,
+        retry_after_sec
+      );
+      warp::reject::custom(RateLimitError::new(retry_after_sec))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::users::TokenServiceImpl;
+  use crate::testing::{config, StubConfigService};
+  use warp::http::StatusCode;
+  use warp::Reply;
+
+  fn limit(capacity: u32, per_minute: u32) -> RateLimitGroupConfig {
+    RateLimitGroupConfig {
+      capacity,
+      per_minute,
+    }
+  }
+
+  fn ip(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+  }
+
+  #[test]
+  fn buckets_allow_their_capacity_then_report_the_wait() {
+    let limit = limit(2, 30);
+    let now = Instant::now();
+    let mut bucket = Bucket::new(&limit, now);
+    assert_eq!(bucket.consume(&limit, now), Ok(()));
+    assert_eq!(bucket.consume(&limit, now), Ok(()));
+    assert_eq!(bucket.consume(&limit, now), Err(2));
+  }
+
+  #[test]
+  fn buckets_refill_with_elapsed_time_up_to_their_capacity() {
+    let limit = limit(5, 60);
+    let start = Instant::now();
+    let mut bucket = Bucket::new(&limit, start);
+    for _ in 0..5 {
+      assert_eq!(bucket.consume(&limit, start), Ok(()));
+    }
+
+    let later = start + Duration::from_secs(3);
+    for _ in 0..3 {
+      assert_eq!(bucket.consume(&limit, later), Ok(()));
+    }
+    assert_eq!(bucket.consume(&limit, later), Err(1));
+
+    let much_later = later + Duration::from_secs(3600);
+    bucket.refill(&limit, much_later);
+    assert!((bucket.tokens - 5.0).abs() < f64::EPSILON);
+  }
+
+  #[test]
+  fn partial_tokens_round_the_wait_up() {
+    let limit = limit(1, 6);
+    let start = Instant::now();
+    let mut bucket = Bucket::new(&limit, start);
+    assert_eq!(bucket.consume(&limit, start), Ok(()));
+    assert_eq!(
+      bucket.consume(&limit, start + Duration::from_secs(5)),
+      Err(5)
+    );
+  }
+
+  #[test]
+  fn forwarded_for_is_only_believed_from_trusted_proxies() {
+    let trusted = vec![ip("10.0.0.1"), ip("10.0.0.2")];
+    assert_eq!(
+      client_ip(ip("203.0.113.9"), Some("198.51.100.1"), &trusted),
+      ip("203.0.113.9")
+    );
+    assert_eq!(
+      client_ip(ip("10.0.0.1"), Some("6.6.6.6, 198.51.100.1, 10.0.0.2"), &trusted),
+      ip("198.51.100.1")
+    );
+    assert_eq!(
+      client_ip(ip("10.0.0.1"), Some("10.0.0.2"), &trusted),
+      ip("10.0.0.2")
+    );
+    assert_eq!(
+      client_ip(ip("10.0.0.1"), Some("not-an-ip"), &trusted),
+      ip("10.0.0.1")
+    );
+    assert_eq!(client_ip(ip("10.0.0.1"), None, &trusted), ip("10.0.0.1"));
+  }
+
+  #[test]
+  fn api_keys_are_identified_by_a_digest() {
+    let digest = api_key_digest("key1.s3cr3t");
+    assert_eq!(digest.len(), API_KEY_DIGEST_BYTES * 2);
+    assert_eq!(digest, api_key_digest("key1.s3cr3t"));
+    assert_ne!(digest, api_key_digest("key1.other"));
+    assert!(!digest.contains("s3cr3t"));
+  }
+
+  fn filter(trusted_proxies: &[&str]) -> BoxedFilter<(StatusCode,)> {
+    let mut config = config();
+    config.rate_limit.session = limit(1, 1);
+    config.rate_limit.trusted_proxies = trusted_proxies.iter().map(|p| String::from(*p)).collect();
+    let config_service = Arc::new(StubConfigService { config });
+    let token_service = Arc::new(TokenServiceImpl::new(config_service.clone()));
+    RateLimitFilter::new(config_service, token_service)
+      .limit(GROUP_SESSION)
+      .map(|| StatusCode::OK)
+      .recover(|r| async move {
+        crate::handlers::error::handler(r)
+          .await
+          .map(|reply| reply.into_response().status())
+      })
+      .unify()
+      .boxed()
+  }
+
+  async fn status(
+    filter: &BoxedFilter<(StatusCode,)>,
+    peer: &str,
+    headers: &[(&str, &str)],
+  ) -> StatusCode {
+    let mut req = warp::test::request().remote_addr(format!("{}:4000", peer).parse().unwrap());
+    for (name, value) in headers {
+      req = req.header(*name, *value);
+    }
+    req.filter(filter).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn clients_are_limited_separately() {
+    let filter = filter(&[]);
+    assert_eq!(status(&filter, "192.0.2.1", &[]).await, StatusCode::OK);
+    assert_eq!(
+      status(&filter, "192.0.2.1", &[]).await,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(status(&filter, "192.0.2.2", &[]).await, StatusCode::OK);
+  }
+
+  #[tokio::test]
+  async fn invalid_tokens_and_untrusted_forwarding_share_the_ip_bucket() {
+    let filter = filter(&[]);
+    assert_eq!(status(&filter, "192.0.2.1", &[]).await, StatusCode::OK);
+    assert_eq!(
+      status(&filter, "192.0.2.1", &[("Authorization", "Bearer forged")]).await,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+      status(&filter, "192.0.2.1", &[("X-Forwarded-For", "198.51.100.1")]).await,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+  }
+
+  #[tokio::test]
+  async fn clients_behind_trusted_proxies_are_limited_by_forwarded_address() {
+    let filter = filter(&["10.0.0.1"]);
+    let forwarded = |addr| [("X-Forwarded-For", addr)];
+    assert_eq!(
+      status(&filter, "10.0.0.1", &forwarded("198.51.100.1")).await,
+      StatusCode::OK
+    );
+    assert_eq!(
+      status(&filter, "10.0.0.1", &forwarded("198.51.100.2")).await,
+      StatusCode::OK
+    );
+    assert_eq!(
+      status(&filter, "10.0.0.1", &forwarded("198.51.100.1")).await,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+  }
+
+  #[tokio::test]
+  async fn api_keys_are_limited_per_key() {
+    let filter = filter(&[]);
+    let key = |k| [("Authorization", k)];
+    assert_eq!(
+      status(&filter, "192.0.2.1", &key("ApiKey a.1")).await,
+      StatusCode::OK
+    );
+    assert_eq!(
+      status(&filter, "192.0.2.1", &key("ApiKey b.2")).await,
+      StatusCode::OK
+    );
+    assert_eq!(
+      status(&filter, "192.0.2.1", &key("ApiKey a.1")).await,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+  }
+}
@@ -1,13 +1,16 @@
 use crate::dto::error;
 use crate::dto::ErrorResponse;
-use crate::errors::{AuthenticationError, AuthorizationError, ValidationError};
+use crate::errors::{AuthenticationError, AuthorizationError, RateLimitError, ValidationError};
 use std::convert::Infallible;
+use warp::http::header::RETRY_AFTER;
 use warp::http::StatusCode;
+use warp::reject::{LengthRequired, PayloadTooLarge};
 use warp::{Rejection, Reply};
 
 pub async fn handler(err: Rejection) -> Result<impl Reply, Infallible> {
   let status;
   let code;
+  let mut retry_after = None;
 
   if err.is_not_found() {
     status = StatusCode::NOT_FOUND;
@@ -21,13 +24,26 @@ pub async fn handler(err: Rejection) -> Result<impl Reply, Infallible> {
   } else if let Some(_) = err.find::<ValidationError>() {
     status = StatusCode::BAD_REQUEST;
     code = error::ERR_INVALID_PARAMETERS;
+  } else if let Some(e) = err.find::<RateLimitError>() {
+    status = StatusCode::TOO_MANY_REQUESTS;
+    code = error::ERR_RATE_LIMITED;
+    retry_after = Some(e.retry_after_sec);
+  } else if err.find::<PayloadTooLarge>().is_some() {
+    status = StatusCode::PAYLOAD_TOO_LARGE;
+    code = error::ERR_PAYLOAD_TOO_LARGE;
+  } else if err.find::<LengthRequired>().is_some() {
+    status = StatusCode::LENGTH_REQUIRED;
+    code = error::ERR_LENGTH_REQUIRED;
   } else {
     status = StatusCode::INTERNAL_SERVER_ERROR;
     code = error::ERR_UNKNOWN;
   }
 
-  Ok(warp::reply::with_status(
-    warp::reply::json(&ErrorResponse { code }),
-    status,
-  ))
+  let mut response =
+    warp::reply::with_status(warp::reply::json(&ErrorResponse { code }), status).into_response();
+  if let Some(sec) = retry_after {
+    response.headers_mut().insert(RETRY_AFTER, sec.into());
+  }
+
+  Ok(response)
 }
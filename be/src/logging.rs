@@ -0,0 +1,268 @@
+use crate::domain::LogConfig;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Dispatch, Span, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Registry};
+use uuid::Uuid;
+
+pub const HEADER_REQUEST_ID: &str = "x-request-id";
+pub const LOG_FORMAT_TEXT: &str = "text";
+pub const LOG_FORMAT_JSON: &str = "json";
+
+const DEFAULT_FILTER: &str = "warn,ausawards_be=trace";
+const FIELD_REQUEST_ID: &str = "request_id";
+const FIELD_USER_ID: &str = "user_id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+#[derive(Clone, Default)]
+struct RequestContext {
+  request_id: Option<String>,
+  user_id: Option<String>,
+}
+
+/// Keeps the request and user IDs of each request span, so that they can be echoed back in the
+/// response and written to the access log.
+struct RequestContextLayer;
+
+impl Visit for RequestContext {
+  fn record_str(&mut self, field: &Field, value: &str) {
+    match field.name() {
+      FIELD_REQUEST_ID => self.request_id = Some(String::from(value)),
+      FIELD_USER_ID => self.user_id = Some(String::from(value)),
+      _ => (),
+    }
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    self.record_str(field, &format!("{:?}", value));
+  }
+}
+
+impl<S> Layer<S> for RequestContextLayer
+where
+  S: Subscriber + for<'a> LookupSpan<'a>,
+{
+  fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+    let mut context = RequestContext::default();
+    attrs.record(&mut context);
+    if let (Some(_), Some(span)) = (&context.request_id, ctx.span(id)) {
+      span.extensions_mut().insert(context);
+    }
+  }
+
+  fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+    if let Some(span) = ctx.span(id) {
+      if let Some(context) = span.extensions_mut().get_mut::<RequestContext>() {
+        values.record(context);
+      }
+    }
+  }
+}
+
+/// Builds the subscriber for the configured log format, writing formatted records to `writer`.
+fn dispatch<W>(config: &LogConfig, writer: W) -> Dispatch
+where
+  W: MakeWriter + Send + Sync + 'static,
+{
+  let registry = Registry::default()
+    .with(EnvFilter::new(DEFAULT_FILTER))
+    .with(RequestContextLayer);
+  if config.format == LOG_FORMAT_JSON {
+    Dispatch::new(
+      registry.with(
+        tracing_subscriber::fmt::layer()
+          .json()
+          .with_current_span(true)
+          .with_writer(writer),
+      ),
+    )
+  } else {
+    Dispatch::new(registry.with(tracing_subscriber::fmt::layer().with_writer(writer)))
+  }
+}
+
+pub fn init(config: &LogConfig) {
+  if let Err(e) = tracing::dispatcher::set_global_default(dispatch(config, std::io::stdout)) {
+    eprintln!("Could not install log subscriber: {}", e);
+    std::process::exit(1);
+  }
+  if let Err(e) = tracing_log::LogTracer::init() {
+    eprintln!("Could not install log adapter: {}", e);
+    std::process::exit(1);
+  }
+
+  log::set_max_level(config.level_filter());
+}
+
+fn valid_request_id(id: &str) -> bool {
+  !id.is_empty()
+    && id.len() <= MAX_REQUEST_ID_LENGTH
+    && id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Creates the span every request is handled in; log records emitted while it is entered carry
+/// the request ID and, once authenticated, the user ID.
+pub fn request_span(info: warp::trace::Info) -> Span {
+  let request_id = info
+    .request_headers()
+    .get(HEADER_REQUEST_ID)
+    .and_then(|h| h.to_str().ok())
+    .filter(|h| valid_request_id(h))
+    .map(String::from)
+    .unwrap_or_else(|| Uuid::new_v4().to_hyphenated().to_string());
+
+  tracing::info_span!(
+    "request",
+    request_id = request_id.as_str(),
+    user_id = tracing::field::Empty,
+    method = %info.method(),
+    path = info.path(),
+  )
+}
+
+fn request_context() -> Option<RequestContext> {
+  let context = Span::current().with_subscriber(|(id, dispatch)| {
+    let registry = dispatch.downcast_ref::<Registry>()?;
+    registry
+      .span(id)?
+      .scope()
+      .find_map(|s| s.extensions().get::<RequestContext>().cloned())
+  });
+
+  context.flatten()
+}
+
+pub fn request_id() -> String {
+  request_context()
+    .and_then(|c| c.request_id)
+    .unwrap_or_else(|| Uuid::new_v4().to_hyphenated().to_string())
+}
+
+pub fn user_id() -> Option<String> {
+  request_context().and_then(|c| c.user_id)
+}
+
+pub fn record_user(user_id: &str) {
+  Span::current().record(FIELD_USER_ID, &user_id);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io;
+  use std::sync::{Arc, Mutex};
+  use warp::Filter;
+
+  #[derive(Clone, Default)]
+  struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+  impl io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl Buffer {
+    fn lines(&self) -> Vec<String> {
+      let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+      output.lines().map(String::from).collect()
+    }
+  }
+
+  fn log_config(format: &str) -> LogConfig {
+    LogConfig {
+      level: String::from("info"),
+      format: String::from(format),
+    }
+  }
+
+  /// Handles one request in a request span, logging and returning what the span carries.
+  async fn handle(dispatch: &Dispatch, client_id: Option<&str>) -> (String, Option<String>) {
+    let _guard = tracing::dispatcher::set_default(dispatch);
+    let filter = warp::any()
+      .map(|| {
+        record_user("user-1");
+        tracing::info!("handled");
+        (request_id(), user_id())
+      })
+      .map(|ids| warp::reply::json(&ids))
+      .with(warp::trace(request_span));
+    let mut req = warp::test::request();
+    if let Some(id) = client_id {
+      req = req.header(HEADER_REQUEST_ID, id);
+    }
+    let res = req.reply(&filter).await;
+    serde_json::from_slice(res.body()).unwrap()
+  }
+
+  #[test]
+  fn request_ids_are_limited_to_a_safe_alphabet_and_length() {
+    assert!(valid_request_id("a1-B2_c3.d4"));
+    assert!(valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH)));
+    assert!(!valid_request_id(""));
+    assert!(!valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    assert!(!valid_request_id("abc def"));
+    assert!(!valid_request_id("abc\nINFO forged"));
+    assert!(!valid_request_id("abc/def"));
+  }
+
+  #[tokio::test]
+  async fn valid_client_request_ids_are_kept() {
+    let dispatch = dispatch(&log_config(LOG_FORMAT_TEXT), Buffer::default);
+    let (request_id, user_id) = handle(&dispatch, Some("client-id.1")).await;
+    assert_eq!(request_id, "client-id.1");
+    assert_eq!(user_id.as_deref(), Some("user-1"));
+  }
+
+  #[tokio::test]
+  async fn invalid_or_missing_request_ids_are_generated() {
+    let dispatch = dispatch(&log_config(LOG_FORMAT_TEXT), Buffer::default);
+    let (invalid, _) = handle(&dispatch, Some("not valid")).await;
+    let (missing, _) = handle(&dispatch, None).await;
+    assert!(Uuid::parse_str(&invalid).is_ok());
+    assert!(Uuid::parse_str(&missing).is_ok());
+    assert_ne!(invalid, missing);
+  }
+
+  #[tokio::test]
+  async fn json_records_carry_the_request_and_user_ids() {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let dispatch = dispatch(&log_config(LOG_FORMAT_JSON), move || writer.clone());
+    handle(&dispatch, Some("req-1")).await;
+
+    let lines = buffer.lines();
+    let record: serde_json::Value = lines
+      .iter()
+      .map(|l| serde_json::from_str(l).unwrap())
+      .find(|r: &serde_json::Value| r["fields"]["message"] == "handled")
+      .unwrap();
+    assert_eq!(record["level"], "INFO");
+    assert_eq!(record["span"]["name"], "request");
+    assert_eq!(record["span"]["request_id"], "req-1");
+    assert_eq!(record["span"]["user_id"], "user-1");
+  }
+
+  #[tokio::test]
+  async fn text_records_carry_the_request_and_user_ids() {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let dispatch = dispatch(&log_config(LOG_FORMAT_TEXT), move || writer.clone());
+    handle(&dispatch, Some("req-2")).await;
+
+    let lines = buffer.lines();
+    let line = lines.iter().find(|l| l.contains("handled")).unwrap();
+    assert!(line.contains("request_id=\"req-2\""));
+    assert!(line.contains("user_id=\"user-1\""));
+    assert!(serde_json::from_str::<serde_json::Value>(line).is_err());
+  }
+}
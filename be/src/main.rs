@@ -1,28 +1,70 @@
-use log::LevelFilter;
-use simple_logger::SimpleLogger;
+use std::sync::Arc;
 
+mod args;
 mod converters;
 mod domain;
 mod dto;
 mod errors;
 mod filters;
 mod handlers;
+mod logging;
 mod manager;
+mod metrics;
 mod model;
 mod repositories;
 mod routes;
 mod services;
+mod signals;
+#[cfg(test)]
+mod testing;
+mod version;
 
 #[tokio::main]
 async fn main() {
-    SimpleLogger::new()
-        .with_level(LevelFilter::Warn)
-        .with_module_level("ausawards_be", LevelFilter::Debug)
-        .init()
-        .unwrap();
+    let args = match args::parse() {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
 
-    let route = manager::AppManager::build().await;
+    let config_service = manager::AppManager::config_service(args);
+    logging::init(&config_service.get_config().log);
+    tokio::spawn(signals::reload(Arc::clone(&config_service)));
 
-    log::info!("Starting server on port 3030");
-    warp::serve(route).run(([127, 0, 0, 1], 3030)).await;
+    let server_config = config_service.get_config().server;
+    let addr = match server_config.bind_addr() {
+        Ok(a) => a,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let route = manager::AppManager::build(config_service).await;
+
+    match server_config.tls {
+        Some(tls) => {
+            if let Err(e) = tls.check_files() {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+            let (addr, server) = warp::serve(route)
+                .tls()
+                .cert_path(tls.cert_path)
+                .key_path(tls.key_path)
+                .bind_with_graceful_shutdown(addr, signals::shutdown());
+            log::info!("Starting server on https://{}", addr);
+            server.await;
+        }
+        None => {
+            let (addr, server) =
+                warp::serve(route).bind_with_graceful_shutdown(addr, signals::shutdown());
+            log::info!("Starting server on http://{}", addr);
+            server.await;
+        }
+    }
+
+    log::info!("Server stopped");
 }
@@ -1,28 +1,40 @@
+use crate::args::Args;
 use crate::converters::award::AwardConverterImpl;
+use crate::converters::role::RoleConverterImpl;
 use crate::converters::user::UserConverterImpl;
-use crate::converters::{AwardConverter, UserConverter};
+use crate::converters::{AwardConverter, RoleConverter, UserConverter};
 use crate::domain::AppConfig;
 use crate::filters::auth::AuthenticationFilterImpl;
-use crate::filters::AuthenticationFilter;
+use crate::filters::{AuthenticationFilter, RateLimitFilter};
+use crate::repositories::api_key::ApiKeyRepositoryImpl;
 use crate::repositories::award::AwardRespositoryImpl;
+use crate::repositories::common::RepositoryMethods;
+use crate::repositories::mfa::MfaChallengeRepositoryImpl;
+use crate::repositories::migration::MigrationRepositoryImpl;
+use crate::repositories::oidc::OidcLoginRepositoryImpl;
 use crate::repositories::role::RoleRepositoryImpl;
 use crate::repositories::session::UserSessionRepositoryImpl;
 use crate::repositories::users::UsersRepositoryImpl;
 use crate::repositories::{
-  AwardRepository, RoleRepository, UserSessionRepository, UsersRepository,
+  ApiKeyRepository, AwardRepository, MfaChallengeRepository, MigrationRepository, OidcLoginRepository,
+  RoleRepository, UserSessionRepository, UsersRepository,
 };
 use crate::routes;
 use crate::services::award::AwardServiceImpl;
 use crate::services::config::FileConfigService;
+use crate::services::health::HealthServiceImpl;
+use crate::services::schema::SchemaServiceImpl;
 use crate::services::users::{
-  HashServiceImpl, RolesServiceImpl, SessionServiceImpl, TokenServiceImpl, UsersServiceImpl,
+  ApiKeyServiceImpl, HashServiceImpl, MfaServiceImpl, OidcServiceImpl, RolesServiceImpl,
+  SessionServiceImpl, TokenServiceImpl, UsersServiceImpl,
 };
 use crate::services::{
-  AwardService, ConfigService, HashService, RolesService, SessionService, TokenService,
-  UsersService,
+  ApiKeyService, AwardService, ConfigService, HashService, HealthService, MfaService, OidcService,
+  RolesService, SchemaService, SessionService, TokenService, UsersService,
 };
 use mongodb::{Client, Database};
 use std::sync::Arc;
+use std::time::Duration;
 use warp::filters::BoxedFilter;
 use warp::Reply;
 
@@ -30,41 +42,88 @@ const COLLECTION_USERS: &str = "Users";
 const COLLECTION_SESSIONS: &str = "UserSessions";
 const COLLECTION_ROLES: &str = "Roles";
 const COLLECTION_AWARDS: &str = "Awards";
+const COLLECTION_MFA_CHALLENGES: &str = "MfaChallenges";
+const COLLECTION_API_KEYS: &str = "ApiKeys";
+const COLLECTION_OIDC_LOGINS: &str = "OidcLogins";
+const COLLECTION_MIGRATIONS: &str = "Migrations";
+
+const INDEX_RETRY_SEC: u64 = 30;
 
 pub struct AppManager {}
 
 impl AppManager {
-  pub async fn build() -> BoxedFilter<(impl Reply,)> {
-    let config_service = AppManager::config_service();
+  pub async fn build(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+  ) -> BoxedFilter<(impl Reply,)> {
     let database = AppManager::database(config_service.get_config()).await;
+    tokio::spawn(AppManager::indexes(Database::clone(&database)));
     let hash_service = AppManager::hash_service();
-    let users_service =
-      AppManager::users_service(Arc::clone(&hash_service), Database::clone(&database));
+    let roles_service = AppManager::roles_service(Database::clone(&database));
+    let users_service = AppManager::users_service(
+      Arc::clone(&hash_service),
+      Arc::clone(&roles_service),
+      Database::clone(&database),
+    );
     let token_service = AppManager::token_service(Arc::clone(&config_service));
+    let mfa_service = AppManager::mfa_service(
+      Arc::clone(&config_service),
+      Arc::clone(&hash_service),
+      Database::clone(&database),
+    );
+    let api_key_service = AppManager::api_key_service(
+      Arc::clone(&config_service),
+      Arc::clone(&users_service),
+      Database::clone(&database),
+    );
     let session_service = AppManager::session_service(
       Arc::clone(&config_service),
       hash_service,
       Arc::clone(&token_service),
       Arc::clone(&users_service),
+      Arc::clone(&mfa_service),
+      Database::clone(&database),
+    );
+    let oidc_service = AppManager::oidc_service(
+      Arc::clone(&config_service),
+      Arc::clone(&session_service),
       Database::clone(&database),
     );
-    let authentication_filter = AppManager::authentication_filter(token_service);
+    let rate_limit_filter =
+      AppManager::rate_limit_filter(Arc::clone(&config_service), Arc::clone(&token_service));
+    let authentication_filter =
+      AppManager::authentication_filter(token_service, Arc::clone(&api_key_service));
+    let health_service =
+      AppManager::health_service(Arc::clone(&config_service), Database::clone(&database));
+    let schema_service =
+      AppManager::schema_service(Arc::clone(&config_service), Database::clone(&database));
     let award_service = AppManager::award_service(database);
 
     AppManager::router(
       &authentication_filter,
+      &rate_limit_filter,
       config_service,
+      health_service,
+      schema_service,
       users_service,
+      roles_service,
+      mfa_service,
+      api_key_service,
       session_service,
+      oidc_service,
       award_service,
     )
   }
 
-  fn config_service() -> Arc<dyn ConfigService + Send + Sync> {
-    let mut file_config_service = FileConfigService::new("resources/config.json");
+  pub fn config_service(args: Args) -> Arc<dyn ConfigService + Send + Sync> {
+    let mut file_config_service = args
+      .overrides
+      .iter()
+      .fold(FileConfigService::new(&args.config_file), |s, (k, v)| {
+        s.with_override(k, v)
+      });
     if let Err(e) = file_config_service.read_config() {
-      log::error!("Error attempting to load file: {:?}", e);
-      panic!();
+      eprintln!("{}", e);
+      std::process::exit(1);
     }
 
     Arc::new(file_config_service)
@@ -80,10 +139,44 @@ impl AppManager {
     }
   }
 
+  /// Creates the TTL indexes, retrying while the database is unreachable. This runs in the
+  /// background so the server still starts and /health/ready can report the database as down.
+  async fn indexes(database: Database) {
+    let mut pending = vec![
+      COLLECTION_SESSIONS,
+      COLLECTION_MFA_CHALLENGES,
+      COLLECTION_OIDC_LOGINS,
+    ];
+    loop {
+      let mut failed = Vec::new();
+      for collection in pending {
+        let res = RepositoryMethods::ensure_ttl_index(&database, collection, "expireAt").await;
+        if res.is_err() {
+          failed.push(collection);
+        }
+      }
+      if failed.is_empty() {
+        return;
+      }
+
+      log::warn!(
+        "Could not create indexes on {}, retrying in {} seconds",
+        failed.join(", "),
+        INDEX_RETRY_SEC
+      );
+      pending = failed;
+      tokio::time::delay_for(Duration::from_secs(INDEX_RETRY_SEC)).await;
+    }
+  }
+
   fn user_converter() -> Arc<dyn UserConverter + Send + Sync> {
     Arc::new(UserConverterImpl::new())
   }
 
+  fn role_converter() -> Arc<dyn RoleConverter + Send + Sync> {
+    Arc::new(RoleConverterImpl::new())
+  }
+
   fn award_converter() -> Arc<dyn AwardConverter + Send + Sync> {
     Arc::new(AwardConverterImpl::new())
   }
@@ -106,6 +199,30 @@ impl AppManager {
     ))
   }
 
+  fn mfa_challenge_repository(database: Database) -> Arc<dyn MfaChallengeRepository + Send + Sync> {
+    Arc::new(MfaChallengeRepositoryImpl::new(
+      database.collection(COLLECTION_MFA_CHALLENGES),
+    ))
+  }
+
+  fn api_key_repository(database: Database) -> Arc<dyn ApiKeyRepository + Send + Sync> {
+    Arc::new(ApiKeyRepositoryImpl::new(
+      database.collection(COLLECTION_API_KEYS),
+    ))
+  }
+
+  fn oidc_login_repository(database: Database) -> Arc<dyn OidcLoginRepository + Send + Sync> {
+    Arc::new(OidcLoginRepositoryImpl::new(
+      database.collection(COLLECTION_OIDC_LOGINS),
+    ))
+  }
+
+  fn migration_repository(database: Database) -> Arc<dyn MigrationRepository + Send + Sync> {
+    Arc::new(MigrationRepositoryImpl::new(
+      database.collection(COLLECTION_MIGRATIONS),
+    ))
+  }
+
   fn award_repository(database: Database) -> Arc<dyn AwardRepository + Send + Sync> {
     Arc::new(AwardRespositoryImpl::new(
       database.collection(COLLECTION_AWARDS),
@@ -114,8 +231,19 @@ impl AppManager {
 
   fn authentication_filter(
     token_service: Arc<dyn TokenService + Send + Sync>,
+    api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
   ) -> Box<dyn AuthenticationFilter> {
-    Box::new(AuthenticationFilterImpl::new(token_service))
+    Box::new(AuthenticationFilterImpl::new(
+      token_service,
+      api_key_service,
+    ))
+  }
+
+  fn rate_limit_filter(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    token_service: Arc<dyn TokenService + Send + Sync>,
+  ) -> RateLimitFilter {
+    RateLimitFilter::new(config_service, token_service)
   }
 
   fn hash_service() -> Arc<dyn HashService + Send + Sync> {
@@ -130,20 +258,55 @@ impl AppManager {
 
   fn users_service(
     hash_service: Arc<dyn HashService + Send + Sync>,
+    roles_service: Arc<dyn RolesService + Send + Sync>,
     database: Database,
   ) -> Arc<dyn UsersService + Send + Sync> {
     let user_converter = AppManager::user_converter();
-    let users_repository = AppManager::users_repository(database);
+    let users_repository = AppManager::users_repository(Database::clone(&database));
+    let session_repository = AppManager::session_repository(database);
     Arc::new(UsersServiceImpl::new(
       user_converter,
       hash_service,
+      roles_service,
       users_repository,
+      session_repository,
     ))
   }
 
   fn roles_service(database: Database) -> Arc<dyn RolesService + Send + Sync> {
+    let role_converter = AppManager::role_converter();
     let role_repository = AppManager::role_repository(database);
-    Arc::new(RolesServiceImpl::new(role_repository))
+    Arc::new(RolesServiceImpl::new(role_converter, role_repository))
+  }
+
+  fn mfa_service(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    hash_service: Arc<dyn HashService + Send + Sync>,
+    database: Database,
+  ) -> Arc<dyn MfaService + Send + Sync> {
+    let users_repository = AppManager::users_repository(Database::clone(&database));
+    let mfa_challenge_repository = AppManager::mfa_challenge_repository(database);
+    Arc::new(MfaServiceImpl::new(
+      config_service,
+      hash_service,
+      users_repository,
+      mfa_challenge_repository,
+    ))
+  }
+
+  fn api_key_service(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    users_service: Arc<dyn UsersService + Send + Sync>,
+    database: Database,
+  ) -> Arc<dyn ApiKeyService + Send + Sync> {
+    let roles_service = AppManager::roles_service(Database::clone(&database));
+    let api_key_repository = AppManager::api_key_repository(database);
+    Arc::new(ApiKeyServiceImpl::new(
+      config_service,
+      users_service,
+      roles_service,
+      api_key_repository,
+    ))
   }
 
   fn session_service(
@@ -151,6 +314,7 @@ impl AppManager {
     hash_service: Arc<dyn HashService + Send + Sync>,
     token_service: Arc<dyn TokenService + Send + Sync>,
     users_service: Arc<dyn UsersService + Send + Sync>,
+    mfa_service: Arc<dyn MfaService + Send + Sync>,
     database: Database,
   ) -> Arc<dyn SessionService + Send + Sync> {
     let roles_service = AppManager::roles_service(Database::clone(&database));
@@ -162,28 +326,74 @@ impl AppManager {
       token_service,
       users_service,
       roles_service,
+      mfa_service,
       session_repository,
     ))
   }
 
+  fn oidc_service(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    session_service: Arc<dyn SessionService + Send + Sync>,
+    database: Database,
+  ) -> Arc<dyn OidcService + Send + Sync> {
+    let users_repository = AppManager::users_repository(Database::clone(&database));
+    let oidc_login_repository = AppManager::oidc_login_repository(database);
+    Arc::new(OidcServiceImpl::new(
+      config_service,
+      session_service,
+      users_repository,
+      oidc_login_repository,
+    ))
+  }
+
+  fn health_service(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    database: Database,
+  ) -> Arc<dyn HealthService + Send + Sync> {
+    Arc::new(HealthServiceImpl::new(config_service, database))
+  }
+
+  fn schema_service(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    database: Database,
+  ) -> Arc<dyn SchemaService + Send + Sync> {
+    let migration_repository = AppManager::migration_repository(database);
+    Arc::new(SchemaServiceImpl::new(config_service, migration_repository))
+  }
+
   fn award_service(database: Database) -> Arc<dyn AwardService + Send + Sync> {
     let award_converter = AppManager::award_converter();
     let award_repository = AppManager::award_repository(database);
     Arc::new(AwardServiceImpl::new(award_converter, award_repository))
   }
 
+  #[allow(clippy::too_many_arguments)]
   fn router(
     authentication_filter: &Box<dyn AuthenticationFilter>,
+    rate_limit_filter: &RateLimitFilter,
     config_service: Arc<dyn ConfigService + Send + Sync>,
+    health_service: Arc<dyn HealthService + Send + Sync>,
+    schema_service: Arc<dyn SchemaService + Send + Sync>,
     users_service: Arc<dyn UsersService + Send + Sync>,
+    roles_service: Arc<dyn RolesService + Send + Sync>,
+    mfa_service: Arc<dyn MfaService + Send + Sync>,
+    api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
     session_service: Arc<dyn SessionService + Send + Sync>,
+    oidc_service: Arc<dyn OidcService + Send + Sync>,
     award_service: Arc<dyn AwardService + Send + Sync>,
   ) -> BoxedFilter<(impl Reply,)> {
     routes::build(
       authentication_filter,
+      rate_limit_filter,
       config_service,
+      health_service,
+      schema_service,
       users_service,
+      roles_service,
+      mfa_service,
+      api_key_service,
       session_service,
+      oidc_service,
       award_service,
     )
   }
@@ -0,0 +1,150 @@
+use crate::routes::OPENAPI_SPEC;
+use lazy_static::lazy_static;
+use prometheus::{
+  register_counter_vec, register_histogram_vec, CounterVec, Encoder, HistogramTimer, HistogramVec,
+  TextEncoder,
+};
+use serde_json::Value;
+use std::cmp::Reverse;
+
+pub const LOGIN_RESULT_SUCCESS: &str = "success";
+pub const LOGIN_RESULT_FAILURE: &str = "failure";
+
+const ROUTE_UNMATCHED: &str = "unmatched";
+const METHOD_OTHER: &str = "OTHER";
+const KNOWN_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+lazy_static! {
+  static ref ROUTE_TEMPLATES: Vec<String> = route_templates(OPENAPI_SPEC);
+  static ref HTTP_REQUESTS: CounterVec = register_counter_vec!(
+    "http_requests_total",
+    "Number of HTTP requests handled",
+    &["method", "route", "status"]
+  )
+  .unwrap();
+  static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+    "http_request_duration_seconds",
+    "HTTP request latency",
+    &["method", "route", "status"]
+  )
+  .unwrap();
+  static ref LOGINS: CounterVec = register_counter_vec!(
+    "logins_total",
+    "Number of login attempts by result",
+    &["result"]
+  )
+  .unwrap();
+  static ref MONGO_OPERATION_DURATION: HistogramVec = register_histogram_vec!(
+    "mongo_operation_duration_seconds",
+    "MongoDB operation latency",
+    &["collection", "operation"]
+  )
+  .unwrap();
+}
+
+/// Paths from the OpenAPI document, with those having more literal segments first so that
+/// `/users/create/admin` wins over `/users/{userId}/password`.
+fn route_templates(spec: &str) -> Vec<String> {
+  let spec: Value = serde_json::from_str(spec).unwrap_or_default();
+  let mut templates: Vec<String> = spec["paths"]
+    .as_object()
+    .map(|p| p.keys().cloned().collect())
+    .unwrap_or_default();
+  templates.sort_by_key(|t| Reverse(t.split('/').filter(|s| !is_parameter(s)).count()));
+  templates
+}
+
+fn is_parameter(segment: &str) -> bool {
+  segment.starts_with('{') && segment.ends_with('}')
+}
+
+fn template_matches(template: &str, path: &str) -> bool {
+  let template: Vec<&str> = template.split('/').collect();
+  let path: Vec<&str> = path.split('/').collect();
+  template.len() == path.len()
+    && template.iter().zip(path).all(|(t, p)| {
+      if is_parameter(t) {
+        !p.is_empty()
+      } else {
+        *t == p
+      }
+    })
+}
+
+/// Labels requests by route template, so that label cardinality stays bounded whatever paths
+/// clients send.
+fn route_label(path: &str) -> &'static str {
+  ROUTE_TEMPLATES
+    .iter()
+    .find(|t| template_matches(t, path))
+    .map(|t| t.as_str())
+    .unwrap_or(ROUTE_UNMATCHED)
+}
+
+fn method_label(method: &str) -> &str {
+  if KNOWN_METHODS.contains(&method) {
+    method
+  } else {
+    METHOD_OTHER
+  }
+}
+
+pub fn record_request(info: warp::log::Info) {
+  let status = info.status();
+  let labels = [
+    method_label(info.method().as_str()),
+    route_label(info.path()),
+    status.as_str(),
+  ];
+  HTTP_REQUESTS.with_label_values(&labels).inc();
+  HTTP_REQUEST_DURATION
+    .with_label_values(&labels)
+    .observe(info.elapsed().as_secs_f64());
+}
+
+pub fn record_login(result: &str) {
+  LOGINS.with_label_values(&[result]).inc();
+}
+
+pub fn time_mongo(collection: &str, operation: &str) -> HistogramTimer {
+  MONGO_OPERATION_DURATION
+    .with_label_values(&[collection, operation])
+    .start_timer()
+}
+
+pub fn render() -> String {
+  let mut buffer = Vec::new();
+  if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+    log::error!("Error encoding metrics {:?}", e);
+  }
+  String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn route_label_uses_the_matched_template() {
+    assert_eq!(route_label("/awards"), "/awards");
+    assert_eq!(route_label("/awards/4f1c-22"), "/awards/{awardId}");
+    assert_eq!(
+      route_label("/awards/a1/classifications/c1/note"),
+      "/awards/{awardId}/classifications/{classificationId}/note"
+    );
+    assert_eq!(route_label("/users/create/admin"), "/users/create/admin");
+  }
+
+  #[test]
+  fn route_label_is_fixed_for_unknown_paths() {
+    assert_eq!(route_label("/wp-admin/setup.php"), ROUTE_UNMATCHED);
+    assert_eq!(route_label("/awards/a1/extra/segments"), ROUTE_UNMATCHED);
+    assert_eq!(route_label("/awards//classifications"), ROUTE_UNMATCHED);
+  }
+
+  #[test]
+  fn method_label_collapses_extension_methods() {
+    assert_eq!(method_label("GET"), "GET");
+    assert_eq!(method_label("PROPFIND"), METHOD_OTHER);
+  }
+}
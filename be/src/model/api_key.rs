@@ -0,0 +1,30 @@
+use crate::repositories::WithId;
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct ApiKey {
+  #[serde(rename = "_id")]
+  pub id: String,
+  #[serde(rename = "userId")]
+  pub user_id: String,
+  pub name: String,
+  /// Base64 SHA-256 of the secret. Keys created before this used bcrypt, and are rehashed the
+  /// first time they are used.
+  #[serde(rename = "keySecret")]
+  pub key_secret: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime,
+  #[serde(rename = "expireAt")]
+  pub expire_at: Option<DateTime>,
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: Option<DateTime>,
+  pub revoked: bool,
+}
+
+impl WithId for ApiKey {
+  fn get_id(&self) -> &str {
+    &self.id
+  }
+}
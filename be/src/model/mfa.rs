@@ -0,0 +1,23 @@
+use crate::repositories::WithId;
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct MfaChallenge {
+  #[serde(rename = "_id")]
+  pub id: String,
+  #[serde(rename = "userId")]
+  pub user_id: String,
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime,
+  #[serde(rename = "expireAt")]
+  pub expire_at: DateTime,
+  pub attempts: u32,
+  pub completed: bool,
+}
+
+impl WithId for MfaChallenge {
+  fn get_id(&self) -> &str {
+    &self.id
+  }
+}
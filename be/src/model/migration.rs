@@ -0,0 +1,19 @@
+use crate::repositories::WithId;
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+/// Marker left by a migration script once it has been applied.
+#[derive(Serialize, Deserialize)]
+pub struct Migration {
+  #[serde(rename = "_id")]
+  pub name: String,
+  pub version: u32,
+  #[serde(rename = "appliedAt")]
+  pub applied_at: DateTime,
+}
+
+impl WithId for Migration {
+  fn get_id(&self) -> &str {
+    &self.name
+  }
+}
@@ -1,9 +1,17 @@
+mod api_key;
 pub mod award;
+mod mfa;
+mod migration;
+mod oidc;
 mod role;
 pub mod session;
 pub mod user;
 
+pub use api_key::ApiKey;
 pub use award::{Award, AwardAlternateId, AwardClassification};
+pub use mfa::MfaChallenge;
+pub use migration::Migration;
+pub use oidc::OidcLogin;
 pub use role::Role;
 pub use session::{SessionExpiry, UserSession};
-pub use user::User;
+pub use user::{User, UserExternalIdentity, UserMfa};
@@ -0,0 +1,23 @@
+use crate::repositories::WithId;
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct OidcLogin {
+  #[serde(rename = "_id")]
+  pub state: String,
+  pub nonce: String,
+  #[serde(rename = "codeVerifier")]
+  pub code_verifier: String,
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime,
+  #[serde(rename = "expireAt")]
+  pub expire_at: DateTime,
+  pub completed: bool,
+}
+
+impl WithId for OidcLogin {
+  fn get_id(&self) -> &str {
+    &self.state
+  }
+}
@@ -1,3 +1,4 @@
+use crate::repositories::WithId;
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
@@ -6,4 +7,12 @@ pub struct Role {
   pub id: String,
   pub name: String,
   pub permissions: Vec<String>,
+  #[serde(rename = "includedRoleIds", default)]
+  pub included_role_ids: Vec<String>,
+}
+
+impl WithId for Role {
+  fn get_id(&self) -> &str {
+    &self.id
+  }
 }
@@ -30,6 +30,9 @@ pub struct UserSession {
   pub created_at: DateTime,
   #[serde(rename = "expireAt")]
   pub expire_at: DateTime,
+  /// Absent on sessions created before absolute lifetimes were introduced.
+  #[serde(rename = "maxExpireAt", default)]
+  pub max_expire_at: Option<DateTime>,
   pub expired: Option<SessionExpiry>,
 }
 
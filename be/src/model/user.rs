@@ -2,6 +2,24 @@ use crate::repositories::WithId;
 use serde::{Deserialize, Serialize};
 
 pub const USER_TYPE_ADMIN: &str = "ADMIN";
+pub const USER_TYPE_SERVICE: &str = "SERVICE";
+
+#[derive(Serialize, Deserialize)]
+pub struct UserMfa {
+  pub secret: String,
+  pub enabled: bool,
+  #[serde(rename = "recoveryCodes")]
+  pub recovery_codes: Vec<String>,
+  /// The TOTP time step of the last accepted code, so a code cannot be replayed.
+  #[serde(rename = "lastUsedStep", default)]
+  pub last_used_step: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserExternalIdentity {
+  pub issuer: String,
+  pub subject: String,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct User {
@@ -16,6 +34,11 @@ pub struct User {
   pub password: String,
   #[serde(rename = "roleIds")]
   pub role_ids: Vec<String>,
+  pub mfa: Option<UserMfa>,
+  #[serde(rename = "externalIdentity")]
+  pub external_identity: Option<UserExternalIdentity>,
+  #[serde(default)]
+  pub disabled: bool,
 }
 
 impl WithId for User {
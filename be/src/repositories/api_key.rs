@@ -0,0 +1,73 @@
+use crate::model::ApiKey;
+use crate::repositories::common::RepositoryMethods;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::Collection;
+use warp::Rejection;
+
+#[async_trait]
+pub trait ApiKeyRepository {
+  async fn save(&self, api_key: &ApiKey) -> Result<(), Rejection>;
+  async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection>;
+  async fn find_active_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection>;
+  async fn update_key_secret(&self, id: &str, key_secret: &str) -> Result<(), Rejection>;
+  async fn update_last_used_at(
+    &self,
+    id: &str,
+    last_used_at: DateTime<Utc>,
+  ) -> Result<(), Rejection>;
+}
+
+pub struct ApiKeyRepositoryImpl {
+  collection: Collection,
+}
+
+impl ApiKeyRepositoryImpl {
+  pub fn new(collection: Collection) -> ApiKeyRepositoryImpl {
+    ApiKeyRepositoryImpl { collection }
+  }
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+  async fn save(&self, api_key: &ApiKey) -> Result<(), Rejection> {
+    RepositoryMethods::save(&self.collection, api_key).await
+  }
+
+  async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection> {
+    RepositoryMethods::find_by_id(&self.collection, id).await
+  }
+
+  async fn find_active_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection> {
+    RepositoryMethods::find_one(
+      &self.collection,
+      bson::doc! {
+        "_id": id,
+        "revoked": false,
+        "$or": [
+          { "expireAt": bson::Bson::Null },
+          { "expireAt": { "$gt": Utc::now() } },
+        ],
+      },
+    )
+    .await
+  }
+
+  async fn update_key_secret(&self, id: &str, key_secret: &str) -> Result<(), Rejection> {
+    RepositoryMethods::set_fields(&self.collection, id, bson::doc! { "keySecret": key_secret })
+      .await
+  }
+
+  async fn update_last_used_at(
+    &self,
+    id: &str,
+    last_used_at: DateTime<Utc>,
+  ) -> Result<(), Rejection> {
+    RepositoryMethods::set_fields(
+      &self.collection,
+      id,
+      bson::doc! { "lastUsedAt": last_used_at },
+    )
+    .await
+  }
+}
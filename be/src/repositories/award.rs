@@ -8,6 +8,8 @@ use warp::Rejection;
 pub trait AwardRepository {
   async fn save(&self, award: &Award) -> Result<(), Rejection>;
   async fn find_by_id(&self, id: &str) -> Result<Option<Award>, Rejection>;
+  async fn find_by_external_id(&self, external_id: &str) -> Result<Option<Award>, Rejection>;
+  async fn find_all(&self) -> Result<Vec<Award>, Rejection>;
 }
 
 pub struct AwardRespositoryImpl {
@@ -29,4 +31,12 @@ impl AwardRepository for AwardRespositoryImpl {
   async fn find_by_id(&self, id: &str) -> Result<Option<Award>, Rejection> {
     RepositoryMethods::find_by_id(&self.collection, id).await
   }
+
+  async fn find_by_external_id(&self, external_id: &str) -> Result<Option<Award>, Rejection> {
+    RepositoryMethods::find_one(&self.collection, bson::doc! { "external_id": external_id }).await
+  }
+
+  async fn find_all(&self) -> Result<Vec<Award>, Rejection> {
+    RepositoryMethods::find(&self.collection, bson::doc! {}).await
+  }
 }
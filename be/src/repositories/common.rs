@@ -1,7 +1,8 @@
 use crate::errors::ServerError;
+use crate::metrics;
 use bson::Document;
-use mongodb::options::UpdateOptions;
-use mongodb::Collection;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateOptions};
+use mongodb::{Collection, Database};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tokio::stream::StreamExt;
@@ -44,6 +45,7 @@ impl RepositoryMethods {
   where
     T: Serialize + WithId,
   {
+    let _timer = metrics::time_mongo(collection.name(), "save");
     let res = collection
       .update_one(
         bson::doc! { "_id": model.get_id() },
@@ -64,11 +66,107 @@ impl RepositoryMethods {
     }
   }
 
+  /// Sets individual fields, leaving the rest of the document as stored.
+  pub async fn set_fields(
+    collection: &Collection,
+    id: &str,
+    fields: Document,
+  ) -> Result<(), Rejection> {
+    let _timer = metrics::time_mongo(collection.name(), "set_fields");
+    let res = collection
+      .update_one(
+        bson::doc! { "_id": id },
+        bson::doc! { "$set": fields },
+        None,
+      )
+      .await;
+
+    if let Err(e) = res {
+      log::error!(
+        "Error occurred while attempting to update document with id={} {:?}",
+        id,
+        e
+      );
+      Err(warp::reject::custom(ServerError::new()))
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Sets fields on every document matching `query`, returning the number modified.
+  pub async fn update_many(
+    collection: &Collection,
+    query: Document,
+    fields: Document,
+  ) -> Result<i64, Rejection> {
+    let _timer = metrics::time_mongo(collection.name(), "update_many");
+    match collection
+      .update_many(query, bson::doc! { "$set": fields }, None)
+      .await
+    {
+      Ok(r) => Ok(r.modified_count),
+      Err(e) => {
+        log::error!(
+          "Error occurred while attempting to update documents in {} {:?}",
+          collection.name(),
+          e
+        );
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
+  /// Applies `update` to the document matching `query`, returning whether one was modified. The
+  /// query and update are applied atomically, so the query can guard against concurrent changes.
+  pub async fn update_where(
+    collection: &Collection,
+    query: Document,
+    update: Document,
+  ) -> Result<bool, Rejection> {
+    let _timer = metrics::time_mongo(collection.name(), "update_where");
+    match collection.update_one(query, update, None).await {
+      Ok(r) => Ok(r.modified_count > 0),
+      Err(e) => {
+        log::error!(
+          "Error occurred while attempting to update document in {} {:?}",
+          collection.name(),
+          e
+        );
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
+  /// Atomically applies `update` to the document matching `query`, returning the updated document.
+  pub async fn find_one_and_update<T>(
+    collection: &Collection,
+    query: Document,
+    update: Document,
+  ) -> Result<Option<T>, Rejection>
+  where
+    T: DeserializeOwned,
+  {
+    let _timer = metrics::time_mongo(collection.name(), "find_one_and_update");
+    let options = FindOneAndUpdateOptions::builder()
+      .return_document(ReturnDocument::After)
+      .build();
+    match collection.find_one_and_update(query, update, options).await {
+      Ok(Some(d)) => Ok(Some(RepositoryMethods::deserialise(d)?)),
+      Ok(None) => Ok(None),
+      Err(e) => {
+        log::error!("Error executing DB update {:?}", e);
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
   pub async fn find_one<T>(collection: &Collection, query: Document) -> Result<Option<T>, Rejection>
   where
     T: DeserializeOwned,
   {
+    let timer = metrics::time_mongo(collection.name(), "find_one");
     let res = collection.find_one(query, None).await;
+    timer.observe_duration();
     if let Err(e) = res {
       log::error!("Error executing DB query {:?}", e);
       return Err(warp::reject::custom(ServerError::new()));
@@ -92,6 +190,7 @@ impl RepositoryMethods {
   where
     T: DeserializeOwned,
   {
+    let _timer = metrics::time_mongo(collection.name(), "find");
     let res = collection.find(query, None).await;
 
     if let Err(e) = res {
@@ -119,4 +218,62 @@ impl RepositoryMethods {
   {
     RepositoryMethods::find(collection, bson::doc! { "_id": { "$in": ids } }).await
   }
+
+  fn ttl_index_name(field: &str) -> String {
+    format!("{}_ttl", field)
+  }
+
+  /// Documents expire once the date in `field` has passed.
+  fn ttl_index_command(collection: &str, field: &str) -> Document {
+    bson::doc! {
+      "createIndexes": collection,
+      "indexes": [{
+        "key": { field: 1 },
+        "name": RepositoryMethods::ttl_index_name(field),
+        "expireAfterSeconds": 0,
+      }],
+    }
+  }
+
+  pub async fn ensure_ttl_index(
+    database: &Database,
+    collection: &str,
+    field: &str,
+  ) -> Result<(), Rejection> {
+    let name = RepositoryMethods::ttl_index_name(field);
+    let _timer = metrics::time_mongo(collection, "create_index");
+    let res = database
+      .run_command(RepositoryMethods::ttl_index_command(collection, field), None)
+      .await;
+
+    if let Err(e) = res {
+      log::error!(
+        "Error attempting to create TTL index {} on collection {} {:?}",
+        name,
+        collection,
+        e
+      );
+      Err(warp::reject::custom(ServerError::new()))
+    } else {
+      Ok(())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ttl_index_expires_documents_at_the_field_date() {
+    let command = RepositoryMethods::ttl_index_command("UserSessions", "expireAt");
+
+    assert_eq!(command.get_str("createIndexes").unwrap(), "UserSessions");
+    let index = command.get_array("indexes").unwrap()[0]
+      .as_document()
+      .unwrap();
+    assert_eq!(index.get_str("name").unwrap(), "expireAt_ttl");
+    assert_eq!(index.get_document("key").unwrap().get_i32("expireAt"), Ok(1));
+    assert_eq!(index.get_i32("expireAfterSeconds"), Ok(0));
+  }
 }
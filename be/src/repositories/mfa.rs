@@ -0,0 +1,64 @@
+use crate::model::MfaChallenge;
+use crate::repositories::common::RepositoryMethods;
+use async_trait::async_trait;
+use chrono::Utc;
+use mongodb::Collection;
+use warp::Rejection;
+
+#[async_trait]
+pub trait MfaChallengeRepository {
+  async fn save(&self, challenge: &MfaChallenge) -> Result<(), Rejection>;
+  /// Counts an attempt against an active challenge that has attempts remaining, returning the
+  /// updated challenge, or `None` if there is no such challenge.
+  async fn record_attempt(
+    &self,
+    id: &str,
+    max_attempts: u32,
+  ) -> Result<Option<MfaChallenge>, Rejection>;
+  /// Marks the challenge completed, returning false if it already was.
+  async fn complete(&self, id: &str) -> Result<bool, Rejection>;
+}
+
+pub struct MfaChallengeRepositoryImpl {
+  collection: Collection,
+}
+
+impl MfaChallengeRepositoryImpl {
+  pub fn new(collection: Collection) -> MfaChallengeRepositoryImpl {
+    MfaChallengeRepositoryImpl { collection }
+  }
+}
+
+#[async_trait]
+impl MfaChallengeRepository for MfaChallengeRepositoryImpl {
+  async fn save(&self, challenge: &MfaChallenge) -> Result<(), Rejection> {
+    RepositoryMethods::save(&self.collection, challenge).await
+  }
+
+  async fn record_attempt(
+    &self,
+    id: &str,
+    max_attempts: u32,
+  ) -> Result<Option<MfaChallenge>, Rejection> {
+    RepositoryMethods::find_one_and_update(
+      &self.collection,
+      bson::doc! {
+        "_id": id,
+        "expireAt": { "$gt": Utc::now() },
+        "completed": false,
+        "attempts": { "$lt": max_attempts },
+      },
+      bson::doc! { "$inc": { "attempts": 1 } },
+    )
+    .await
+  }
+
+  async fn complete(&self, id: &str) -> Result<bool, Rejection> {
+    RepositoryMethods::update_where(
+      &self.collection,
+      bson::doc! { "_id": id, "completed": false },
+      bson::doc! { "$set": { "completed": true } },
+    )
+    .await
+  }
+}
@@ -0,0 +1,27 @@
+use crate::model::Migration;
+use crate::repositories::common::RepositoryMethods;
+use async_trait::async_trait;
+use mongodb::Collection;
+use warp::Rejection;
+
+#[async_trait]
+pub trait MigrationRepository {
+  async fn find_all(&self) -> Result<Vec<Migration>, Rejection>;
+}
+
+pub struct MigrationRepositoryImpl {
+  collection: Collection,
+}
+
+impl MigrationRepositoryImpl {
+  pub fn new(collection: Collection) -> MigrationRepositoryImpl {
+    MigrationRepositoryImpl { collection }
+  }
+}
+
+#[async_trait]
+impl MigrationRepository for MigrationRepositoryImpl {
+  async fn find_all(&self) -> Result<Vec<Migration>, Rejection> {
+    RepositoryMethods::find(&self.collection, bson::doc! {}).await
+  }
+}
@@ -3,9 +3,17 @@ pub mod session;
 pub mod role;
 pub mod award;
 pub mod common;
+pub mod mfa;
+pub mod api_key;
+pub mod oidc;
+pub mod migration;
 
 pub use users::UsersRepository;
 pub use session::UserSessionRepository;
 pub use role::RoleRepository;
 pub use award::AwardRepository;
 pub use common::WithId;
+pub use mfa::MfaChallengeRepository;
+pub use api_key::ApiKeyRepository;
+pub use oidc::OidcLoginRepository;
+pub use migration::MigrationRepository;
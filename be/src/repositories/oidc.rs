@@ -0,0 +1,41 @@
+use crate::model::OidcLogin;
+use crate::repositories::common::RepositoryMethods;
+use async_trait::async_trait;
+use chrono::Utc;
+use mongodb::Collection;
+use warp::Rejection;
+
+#[async_trait]
+pub trait OidcLoginRepository {
+  async fn save(&self, login: &OidcLogin) -> Result<(), Rejection>;
+  async fn find_active_by_state(&self, state: &str) -> Result<Option<OidcLogin>, Rejection>;
+}
+
+pub struct OidcLoginRepositoryImpl {
+  collection: Collection,
+}
+
+impl OidcLoginRepositoryImpl {
+  pub fn new(collection: Collection) -> OidcLoginRepositoryImpl {
+    OidcLoginRepositoryImpl { collection }
+  }
+}
+
+#[async_trait]
+impl OidcLoginRepository for OidcLoginRepositoryImpl {
+  async fn save(&self, login: &OidcLogin) -> Result<(), Rejection> {
+    RepositoryMethods::save(&self.collection, login).await
+  }
+
+  async fn find_active_by_state(&self, state: &str) -> Result<Option<OidcLogin>, Rejection> {
+    RepositoryMethods::find_one(
+      &self.collection,
+      bson::doc! {
+        "_id": state,
+        "expireAt": { "$gt": Utc::now() },
+        "completed": false,
+      },
+    )
+    .await
+  }
+}
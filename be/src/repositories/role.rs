@@ -6,7 +6,10 @@ use warp::Rejection;
 
 #[async_trait]
 pub trait RoleRepository {
+  async fn save(&self, role: &Role) -> Result<(), Rejection>;
   async fn find_by_ids(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection>;
+  async fn find_by_name(&self, name: &str) -> Result<Option<Role>, Rejection>;
+  async fn find_all(&self) -> Result<Vec<Role>, Rejection>;
 }
 
 pub struct RoleRepositoryImpl {
@@ -21,7 +24,19 @@ impl RoleRepositoryImpl {
 
 #[async_trait]
 impl RoleRepository for RoleRepositoryImpl {
+  async fn save(&self, role: &Role) -> Result<(), Rejection> {
+    RepositoryMethods::save(&self.collection, role).await
+  }
+
   async fn find_by_ids(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection> {
     RepositoryMethods::find_by_ids(&self.collection, role_ids).await
   }
+
+  async fn find_by_name(&self, name: &str) -> Result<Option<Role>, Rejection> {
+    RepositoryMethods::find_one(&self.collection, bson::doc! { "name": name }).await
+  }
+
+  async fn find_all(&self) -> Result<Vec<Role>, Rejection> {
+    RepositoryMethods::find(&self.collection, bson::doc! {}).await
+  }
 }
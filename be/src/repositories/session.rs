@@ -1,4 +1,4 @@
-use crate::model::UserSession;
+use crate::model::{SessionExpiry, UserSession};
 use crate::repositories::common::RepositoryMethods;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -9,6 +9,8 @@ use warp::Rejection;
 pub trait UserSessionRepository {
   async fn save(&self, session: &UserSession) -> Result<(), Rejection>;
   async fn find_active_by_id(&self, id: &str) -> Result<Option<UserSession>, Rejection>;
+  async fn expire_by_user_id(&self, user_id: &str, expiry: &SessionExpiry)
+    -> Result<i64, Rejection>;
 }
 
 pub struct UserSessionRepositoryImpl {
@@ -28,14 +30,35 @@ impl UserSessionRepository for UserSessionRepositoryImpl {
   }
 
   async fn find_active_by_id(&self, id: &str) -> Result<Option<UserSession>, Rejection> {
+    let now = Utc::now();
     RepositoryMethods::find_one(
       &self.collection,
       bson::doc! {
         "_id": id,
-        "expireAt": { "$gt": Utc::now() },
+        "expireAt": { "$gt": now },
+        "$or": [
+          { "maxExpireAt": { "$gt": now } },
+          { "maxExpireAt": { "$exists": false } },
+        ],
         "expired": bson::Bson::Null,
       },
     )
     .await
   }
+
+  async fn expire_by_user_id(
+    &self,
+    user_id: &str,
+    expiry: &SessionExpiry,
+  ) -> Result<i64, Rejection> {
+    RepositoryMethods::update_many(
+      &self.collection,
+      bson::doc! {
+        "userId": user_id,
+        "expired": bson::Bson::Null,
+      },
+      bson::doc! { "expired": RepositoryMethods::serialise(expiry)? },
+    )
+    .await
+  }
 }
@@ -1,4 +1,4 @@
-use crate::model::User;
+use crate::model::{User, UserExternalIdentity};
 use crate::repositories::common::RepositoryMethods;
 use async_trait::async_trait;
 use mongodb::Collection;
@@ -9,6 +9,16 @@ pub trait UsersRepository {
   async fn save(&self, user: &User) -> Result<(), Rejection>;
   async fn find_by_id(&self, user_id: &str) -> Result<Option<User>, Rejection>;
   async fn find_by_login_id(&self, login_id: &str) -> Result<Option<User>, Rejection>;
+  async fn find_all(&self) -> Result<Vec<User>, Rejection>;
+  async fn find_by_external_identity(
+    &self,
+    identity: &UserExternalIdentity,
+  ) -> Result<Option<User>, Rejection>;
+  /// Records `step` as the last accepted TOTP step, returning false if it is not newer than the
+  /// stored one.
+  async fn claim_mfa_step(&self, user_id: &str, step: i64) -> Result<bool, Rejection>;
+  /// Removes a hashed recovery code, returning false if it had already been removed.
+  async fn remove_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool, Rejection>;
 }
 
 pub struct UsersRepositoryImpl {
@@ -34,4 +44,46 @@ impl UsersRepository for UsersRepositoryImpl {
   async fn find_by_login_id(&self, login_id: &str) -> Result<Option<User>, Rejection> {
     RepositoryMethods::find_one(&self.collection, bson::doc! { "loginId": login_id }).await
   }
+
+  async fn find_all(&self) -> Result<Vec<User>, Rejection> {
+    RepositoryMethods::find(&self.collection, bson::doc! {}).await
+  }
+
+  async fn find_by_external_identity(
+    &self,
+    identity: &UserExternalIdentity,
+  ) -> Result<Option<User>, Rejection> {
+    RepositoryMethods::find_one(
+      &self.collection,
+      bson::doc! {
+        "externalIdentity.issuer": &identity.issuer,
+        "externalIdentity.subject": &identity.subject,
+      },
+    )
+    .await
+  }
+
+  async fn claim_mfa_step(&self, user_id: &str, step: i64) -> Result<bool, Rejection> {
+    RepositoryMethods::update_where(
+      &self.collection,
+      bson::doc! {
+        "_id": user_id,
+        "$or": [
+          { "mfa.lastUsedStep": { "$lt": step } },
+          { "mfa.lastUsedStep": null },
+        ],
+      },
+      bson::doc! { "$set": { "mfa.lastUsedStep": step } },
+    )
+    .await
+  }
+
+  async fn remove_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool, Rejection> {
+    RepositoryMethods::update_where(
+      &self.collection,
+      bson::doc! { "_id": user_id, "mfa.recoveryCodes": code_hash },
+      bson::doc! { "$pull": { "mfa.recoveryCodes": code_hash } },
+    )
+    .await
+  }
 }
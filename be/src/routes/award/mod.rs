@@ -1,20 +1,37 @@
-use crate::filters::AuthenticationFilter;
+use crate::filters::rate_limit::GROUP_AWARDS;
+use crate::filters::{AuthenticationFilter, BodyFilter, RateLimitFilter};
 use crate::services::AwardService;
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
 mod create;
+mod read;
 mod update;
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  body_filter: &BodyFilter,
+  rate_limit_filter: &RateLimitFilter,
   award_service: Arc<dyn AwardService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
   warp::path!("awards" / ..)
+    .and(rate_limit_filter.limit(GROUP_AWARDS))
     .and(
-      create::route(authentication_filter, Arc::clone(&award_service))
-        .or(update::route(authentication_filter, award_service)),
+      create::route(
+        authentication_filter,
+        body_filter,
+        Arc::clone(&award_service),
+      )
+      .or(read::route(
+        authentication_filter.as_ref(),
+        Arc::clone(&award_service),
+      ))
+      .or(update::route(
+        authentication_filter,
+        body_filter,
+        award_service,
+      )),
     )
     .boxed()
 }
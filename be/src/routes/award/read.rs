@@ -0,0 +1,41 @@
+use crate::dto::ListAwardsQuery;
+use crate::filters::AuthenticationFilter;
+use crate::services::AwardService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  award_service: Arc<dyn AwardService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let as1 = Arc::clone(&award_service);
+  let list_awards = warp::path::end()
+    .and(warp::get())
+    .and(authentication_filter.permissioned("award:list"))
+    .and(warp::query::<ListAwardsQuery>())
+    .and_then(move |query: ListAwardsQuery| {
+      let as_local = Arc::clone(&as1);
+      async move {
+        as_local
+          .list_awards(query.external_id)
+          .await
+          .map(|a| warp::reply::json(&a))
+      }
+    });
+
+  let get_award = warp::path!(String)
+    .and(warp::get())
+    .and(authentication_filter.permissioned("award:get"))
+    .and_then(move |award_id: String| {
+      let as_local = Arc::clone(&award_service);
+      async move {
+        as_local
+          .get_award(&award_id)
+          .await
+          .map(|a| warp::reply::json(&a))
+      }
+    });
+
+  list_awards.or(get_award).boxed()
+}
@@ -2,7 +2,7 @@ use crate::dto::{
   AwardAlternateId, AwardClassification, UpdateAwardClassificationNoteRequest,
   UpdateAwardClassificatonStatusRequest, UpdateAwardExpiryDate,
 };
-use crate::filters::AuthenticationFilter;
+use crate::filters::{AuthenticationFilter, BodyFilter};
 use crate::services::AwardService;
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
@@ -10,13 +10,14 @@ use warp::{Filter, Reply};
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  body_filter: &BodyFilter,
   award_service: Arc<dyn AwardService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
   let as1 = Arc::clone(&award_service);
   let add_alt_id = warp::path!(String / "alternateIds")
     .and(warp::post())
-    .and(authentication_filter.permissioned("addAwardAlternateId"))
-    .and(warp::body::json())
+    .and(authentication_filter.permissioned("award:addAlternateId"))
+    .and(body_filter.json())
     .and_then(move |award_id: String, alt_id: AwardAlternateId| {
       let as_local = Arc::clone(&as1);
       async move {
@@ -30,8 +31,8 @@ pub fn route(
   let as2 = Arc::clone(&award_service);
   let add_classification = warp::path!(String / "classifications")
     .and(warp::post())
-    .and(authentication_filter.permissioned("addAwardClassification"))
-    .and(warp::body::json())
+    .and(authentication_filter.permissioned("award:addClassification"))
+    .and(body_filter.json())
     .and_then(
       move |award_id: String, classification: AwardClassification| {
         let as_local = Arc::clone(&as2);
@@ -47,8 +48,8 @@ pub fn route(
   let as3 = Arc::clone(&award_service);
   let update_expiry_date = warp::path!(String / "expired")
     .and(warp::put())
-    .and(authentication_filter.permissioned("updateAwardExpiryDate"))
-    .and(warp::body::json())
+    .and(authentication_filter.permissioned("award:updateExpiryDate"))
+    .and(body_filter.json())
     .and_then(move |award_id: String, expired: UpdateAwardExpiryDate| {
       let as_local = Arc::clone(&as3);
       async move {
@@ -62,7 +63,7 @@ pub fn route(
   let as4 = Arc::clone(&award_service);
   let remove_expiry_date = warp::path!(String / "expired")
     .and(warp::delete())
-    .and(authentication_filter.permissioned("removeAwardExpiryDate"))
+    .and(authentication_filter.permissioned("award:removeExpiryDate"))
     .and_then(move |award_id: String| {
       let as_local = Arc::clone(&as4);
       async move {
@@ -76,8 +77,8 @@ pub fn route(
   let as5 = Arc::clone(&award_service);
   let update_classification_status = warp::path!(String / "classifications" / String / "active")
     .and(warp::put())
-    .and(authentication_filter.permissioned("updateAwardClassificationActive"))
-    .and(warp::body::json())
+    .and(authentication_filter.permissioned("award:updateClassificationActive"))
+    .and(body_filter.json())
     .and_then(
       move |award_id: String,
             classification_id: String,
@@ -94,8 +95,8 @@ pub fn route(
 
   let update_classification_note = warp::path!(String / "classifications" / String / "note")
     .and(warp::put())
-    .and(authentication_filter.permissioned("updateAwardClassificationNote"))
-    .and(warp::body::json())
+    .and(authentication_filter.permissioned("award:updateClassificationNote"))
+    .and(body_filter.json())
     .and_then(
       move |award_id: String,
             classification_id: String,
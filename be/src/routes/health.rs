@@ -1,23 +1,31 @@
+use crate::dto::health::HEALTH_STATUS_UP;
+use crate::dto::LivenessResponse;
 use crate::filters::AuthenticationFilter;
-use crate::services::ConfigService;
+use crate::services::{ConfigService, HealthService};
+use crate::version;
 use serde::Serialize;
+use std::convert::Infallible;
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
 use warp::{Filter, Reply};
 
 #[derive(Serialize)]
 struct Response {
   name: String,
+  version: &'static str,
 }
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
   config_service: Arc<dyn ConfigService + Send + Sync>,
+  health_service: Arc<dyn HealthService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
   let cs1 = Arc::clone(&config_service);
   let health = warp::path::end().and(warp::get()).map(move || {
     warp::reply::json(&Response {
       name: cs1.get_config().app_name,
+      version: version::VERSION,
     })
   });
 
@@ -27,8 +35,33 @@ pub fn route(
     .map(move || {
       warp::reply::json(&Response {
         name: config_service.get_config().app_name,
+        version: version::VERSION,
       })
     });
 
-  warp::path!("health" / ..).and(health.or(health_secure)).boxed()
+  let live = warp::path!("live").and(warp::get()).map(|| {
+    warp::reply::json(&LivenessResponse {
+      status: HEALTH_STATUS_UP,
+    })
+  });
+
+  let ready = warp::path!("ready").and(warp::get()).and_then(move || {
+    let hs_local = Arc::clone(&health_service);
+    async move {
+      let readiness = hs_local.readiness().await;
+      let status = if readiness.status == HEALTH_STATUS_UP {
+        StatusCode::OK
+      } else {
+        StatusCode::SERVICE_UNAVAILABLE
+      };
+      Ok(warp::reply::with_status(
+        warp::reply::json(&readiness),
+        status,
+      )) as Result<_, Infallible>
+    }
+  });
+
+  warp::path!("health" / ..)
+    .and(health.or(health_secure).or(live).or(ready))
+    .boxed()
 }
@@ -0,0 +1,12 @@
+use crate::metrics;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+const CONTENT_TYPE_PROMETHEUS: &str = "text/plain; version=0.0.4";
+
+pub fn route() -> BoxedFilter<(impl Reply,)> {
+  warp::path!("metrics")
+    .and(warp::get())
+    .map(|| warp::reply::with_header(metrics::render(), "content-type", CONTENT_TYPE_PROMETHEUS))
+    .boxed()
+}
@@ -1,26 +1,268 @@
-use crate::filters::AuthenticationFilter;
+use crate::filters::{access_log, cors, AuthenticationFilter, BodyFilter, RateLimitFilter};
 use crate::handlers;
-use crate::services::{AwardService, ConfigService, SessionService, UsersService};
+use crate::logging;
+use crate::services::{
+  ApiKeyService, AwardService, ConfigService, HealthService, MfaService, OidcService, RolesService,
+  SchemaService, SessionService, UsersService,
+};
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
 mod award;
 mod health;
+mod metrics;
+mod openapi;
+mod roles;
 mod session;
 mod users;
+mod version;
 
+pub use openapi::SPEC as OPENAPI_SPEC;
+
+#[allow(clippy::too_many_arguments)]
 pub fn build(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  rate_limit_filter: &RateLimitFilter,
   config_service: Arc<dyn ConfigService + Send + Sync>,
+  health_service: Arc<dyn HealthService + Send + Sync>,
+  schema_service: Arc<dyn SchemaService + Send + Sync>,
   users_service: Arc<dyn UsersService + Send + Sync>,
+  roles_service: Arc<dyn RolesService + Send + Sync>,
+  mfa_service: Arc<dyn MfaService + Send + Sync>,
+  api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
   session_service: Arc<dyn SessionService + Send + Sync>,
+  oidc_service: Arc<dyn OidcService + Send + Sync>,
   award_service: Arc<dyn AwardService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
-  health::route(authentication_filter, config_service)
-    .or(users::route(authentication_filter, users_service))
-    .or(session::route(authentication_filter, session_service))
-    .or(award::route(authentication_filter, award_service))
-    .recover(handlers::error::handler)
+  let body_filter = BodyFilter::new(Arc::clone(&config_service));
+  let cs1 = Arc::clone(&config_service);
+  let cs2 = Arc::clone(&config_service);
+  let cs3 = Arc::clone(&config_service);
+  let routes = health::route(authentication_filter, cs3, health_service)
+    .or(users::route(
+      authentication_filter,
+      &body_filter,
+      rate_limit_filter,
+      users_service,
+      mfa_service,
+      api_key_service,
+    ))
+    .or(roles::route(
+      authentication_filter.as_ref(),
+      &body_filter,
+      rate_limit_filter,
+      roles_service,
+    ))
+    .or(session::route(
+      authentication_filter,
+      &body_filter,
+      rate_limit_filter,
+      session_service,
+      oidc_service,
+    ))
+    .or(award::route(
+      authentication_filter,
+      &body_filter,
+      rate_limit_filter,
+      award_service,
+    ))
+    .or(metrics::route())
+    .or(openapi::route())
+    .or(version::route(schema_service))
+    .recover(handlers::error::handler);
+  let routes = match config_service.get_config().cors {
+    Some(c) => routes
+      .with(cors::cors(&c))
+      .map(Reply::into_response)
+      .boxed(),
+    None => routes.map(Reply::into_response).boxed(),
+  };
+
+  warp::any()
+    .map(logging::request_id)
+    .and(warp::path::full())
+    .and(routes)
+    .and_then(move |request_id: String, path, reply| {
+      let reply = warp::reply::with_header(reply, logging::HEADER_REQUEST_ID, request_id);
+      access_log::log_response_body(Arc::clone(&cs1), path, reply)
+    })
+    .with(access_log::filter(cs2))
+    .with(warp::trace(logging::request_span))
+    .with(warp::log::custom(crate::metrics::record_request))
     .boxed()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::OPENAPI_SPEC;
+  use crate::manager::AppManager;
+  use crate::services::config::FileConfigService;
+  use serde_json::Value;
+  use std::collections::BTreeSet;
+  use std::sync::Arc;
+  use warp::filters::BoxedFilter;
+  use warp::http::StatusCode;
+  use warp::reply::Response;
+  use warp::{Filter, Reply};
+
+  const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+  /// Every route `build` serves, with the status it answers an unauthenticated, bodyless request
+  /// with while MongoDB and the identity provider are unreachable.
+  const REGISTERED_ROUTES: &[(&str, &str, u16)] = &[
+    ("GET", "/health", 200),
+    ("GET", "/health/secure", 401),
+    ("GET", "/health/live", 200),
+    ("GET", "/health/ready", 503),
+    ("GET", "/version", 200),
+    ("GET", "/metrics", 200),
+    ("GET", "/openapi.json", 200),
+    ("POST", "/session", 411),
+    ("DELETE", "/session", 401),
+    ("POST", "/session/mfa", 411),
+    ("POST", "/session/refresh", 411),
+    ("GET", "/session/oidc", 500),
+    ("POST", "/session/oidc/callback", 411),
+    ("POST", "/users/create/admin", 401),
+    ("POST", "/users/create/service", 401),
+    ("POST", "/users/mfa", 401),
+    ("POST", "/users/mfa/confirm", 401),
+    ("GET", "/users", 401),
+    ("GET", "/users/{userId}", 401),
+    ("PUT", "/users/{userId}/disabled", 401),
+    ("PUT", "/users/{userId}/password", 401),
+    ("PUT", "/users/{userId}/roles/{roleId}", 401),
+    ("DELETE", "/users/{userId}/roles/{roleId}", 401),
+    ("POST", "/users/{userId}/apiKeys", 401),
+    ("DELETE", "/users/{userId}/apiKeys/{keyId}", 401),
+    ("GET", "/roles", 401),
+    ("POST", "/roles", 401),
+    ("GET", "/awards", 401),
+    ("POST", "/awards", 401),
+    ("GET", "/awards/{awardId}", 401),
+    ("POST", "/awards/{awardId}/alternateIds", 401),
+    ("POST", "/awards/{awardId}/classifications", 401),
+    ("PUT", "/awards/{awardId}/expired", 401),
+    ("DELETE", "/awards/{awardId}/expired", 401),
+    (
+      "PUT",
+      "/awards/{awardId}/classifications/{classificationId}/active",
+      401,
+    ),
+    (
+      "PUT",
+      "/awards/{awardId}/classifications/{classificationId}/note",
+      401,
+    ),
+  ];
+
+  fn spec_paths() -> serde_json::Map<String, Value> {
+    let spec: Value = serde_json::from_str(OPENAPI_SPEC).unwrap();
+    spec["paths"].as_object().unwrap().clone()
+  }
+
+  fn concrete_path(template: &str) -> String {
+    template
+      .split('/')
+      .map(|s| if s.starts_with('{') { "x" } else { s })
+      .collect::<Vec<&str>>()
+      .join("/")
+  }
+
+  async fn probe(
+    routes: &BoxedFilter<(Response,)>,
+    method: &str,
+    path: &str,
+  ) -> StatusCode {
+    warp::test::request()
+      .method(method)
+      .path(&concrete_path(path))
+      .header("Authorization", "Bearer invalid")
+      .reply(routes)
+      .await
+      .status()
+  }
+
+  async fn routes() -> BoxedFilter<(Response,)> {
+    let mut config_service = FileConfigService::new("resources/config.json")
+      .with_override(
+        "mongo.uri",
+        "mongodb://127.0.0.1:1/?serverSelectionTimeoutMS=100",
+      )
+      .with_override("rate_limit.session.capacity", "1000")
+      .with_override("rate_limit.awards.capacity", "1000")
+      .with_override("rate_limit.users.capacity", "1000")
+      .with_override("oidc.issuer", "http://127.0.0.1:1")
+      .with_override("oidc.client_id", "ausawards")
+      .with_override("oidc.client_secret", "secret")
+      .with_override("oidc.redirect_uri", "http://localhost:3000/oidc")
+      .with_override("oidc.scopes", "openid,email")
+      .with_override("oidc.default_role_id", "reader")
+      .with_override("oidc.login_lifetime_sec", "600");
+    config_service.read_config().unwrap();
+    AppManager::build(Arc::new(config_service))
+      .await
+      .map(Reply::into_response)
+      .boxed()
+  }
+
+  #[test]
+  fn registered_routes_match_spec_operations() {
+    let registered: BTreeSet<(String, String)> = REGISTERED_ROUTES
+      .iter()
+      .map(|(m, p, _)| (m.to_string(), p.to_string()))
+      .collect();
+    let documented: BTreeSet<(String, String)> = spec_paths()
+      .iter()
+      .flat_map(|(p, ops)| {
+        ops
+          .as_object()
+          .unwrap()
+          .keys()
+          .map(move |m| (m.to_uppercase(), p.clone()))
+      })
+      .collect();
+    assert_eq!(registered.len(), REGISTERED_ROUTES.len());
+    assert_eq!(registered, documented);
+  }
+
+  #[tokio::test]
+  async fn registered_routes_are_served_with_documented_responses() {
+    let routes = routes().await;
+    let paths = spec_paths();
+    for (method, path, expected) in REGISTERED_ROUTES {
+      let status = probe(&routes, method, path).await;
+      assert_eq!(status.as_u16(), *expected, "{} {}", method, path);
+      let responses = &paths[*path][method.to_lowercase()]["responses"];
+      assert!(
+        responses.get(expected.to_string()).is_some(),
+        "{} {} does not document {}",
+        method,
+        path,
+        expected
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn undocumented_methods_are_not_served() {
+    let routes = routes().await;
+    for (path, ops) in spec_paths() {
+      for method in METHODS {
+        if ops.get(method.to_lowercase()).is_some() {
+          continue;
+        }
+        // warp's method-not-allowed rejection reaches the error handler as an unknown error.
+        let status = probe(&routes, method, &path).await;
+        assert!(
+          status == StatusCode::NOT_FOUND || status == StatusCode::INTERNAL_SERVER_ERROR,
+          "{} {} answered {}",
+          method,
+          path,
+          status
+        );
+      }
+    }
+  }
+}
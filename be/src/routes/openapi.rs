@@ -0,0 +1,12 @@
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+const CONTENT_TYPE_JSON: &str = "application/json";
+pub const SPEC: &str = include_str!("../../resources/openapi.json");
+
+pub fn route() -> BoxedFilter<(impl Reply,)> {
+  warp::path!("openapi.json")
+    .and(warp::get())
+    .map(|| warp::reply::with_header(SPEC, "content-type", CONTENT_TYPE_JSON))
+    .boxed()
+}
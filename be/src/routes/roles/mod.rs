@@ -0,0 +1,44 @@
+use crate::dto::CreateRoleRequest;
+use crate::filters::rate_limit::GROUP_USERS;
+use crate::filters::{AuthenticationFilter, BodyFilter, RateLimitFilter};
+use crate::services::RolesService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+const LIST_ROLES_PERMISSIONS: &[&str] = &["role:list", "role:grant", "role:revoke"];
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  body_filter: &BodyFilter,
+  rate_limit_filter: &RateLimitFilter,
+  roles_service: Arc<dyn RolesService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let rs1 = Arc::clone(&roles_service);
+  let list_roles = warp::path::end()
+    .and(warp::get())
+    .and(authentication_filter.permissioned_any(LIST_ROLES_PERMISSIONS))
+    .and_then(move || {
+      let rs_local = Arc::clone(&rs1);
+      async move { rs_local.list_roles().await.map(|r| warp::reply::json(&r)) }
+    });
+
+  let create_role = warp::path::end()
+    .and(warp::post())
+    .and(authentication_filter.permissioned("role:create"))
+    .and(body_filter.json())
+    .and_then(move |request: CreateRoleRequest| {
+      let rs_local = Arc::clone(&roles_service);
+      async move {
+        rs_local
+          .create_role(request)
+          .await
+          .map(|r| warp::reply::json(&r))
+      }
+    });
+
+  warp::path!("roles" / ..)
+    .and(rate_limit_filter.limit(GROUP_USERS))
+    .and(list_roles.or(create_role))
+    .boxed()
+}
@@ -1,19 +1,23 @@
 use crate::domain::UserAuth;
-use crate::dto::{LogInRequest, RefreshRequest};
-use crate::filters::AuthenticationFilter;
-use crate::services::SessionService;
+use crate::dto::{LogInRequest, MfaLogInRequest, OidcCallbackRequest, RefreshRequest};
+use crate::filters::rate_limit::GROUP_SESSION;
+use crate::filters::{AuthenticationFilter, BodyFilter, RateLimitFilter};
+use crate::services::{OidcService, SessionService};
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Rejection, Reply};
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  body_filter: &BodyFilter,
+  rate_limit_filter: &RateLimitFilter,
   session_service: Arc<dyn SessionService + Send + Sync>,
+  oidc_service: Arc<dyn OidcService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
   let ss1 = Arc::clone(&session_service);
   let create_session = warp::path::end()
     .and(warp::post())
-    .and(warp::body::json())
+    .and(body_filter.json())
     .and_then(move |r: LogInRequest| {
       let ss_local = Arc::clone(&ss1);
       async move {
@@ -25,11 +29,25 @@ pub fn route(
     });
 
   let ss2 = Arc::clone(&session_service);
+  let complete_mfa = warp::path!("mfa")
+    .and(warp::post())
+    .and(body_filter.json())
+    .and_then(move |r: MfaLogInRequest| {
+      let ss_local = Arc::clone(&ss2);
+      async move {
+        ss_local
+          .complete_mfa(&r)
+          .await
+          .map(|s| warp::reply::json(&s))
+      }
+    });
+
+  let ss3 = Arc::clone(&session_service);
   let refresh_token = warp::path!("refresh")
     .and(warp::post())
-    .and(warp::body::json())
+    .and(body_filter.json())
     .and_then(move |r: RefreshRequest| {
-      let ss_local = Arc::clone(&ss2);
+      let ss_local = Arc::clone(&ss3);
       async move {
         ss_local
           .refresh_token(&r)
@@ -49,7 +67,29 @@ pub fn route(
       }
     });
 
+  let os1 = Arc::clone(&oidc_service);
+  let oidc_authorize = warp::path!("oidc").and(warp::get()).and_then(move || {
+    let os_local = Arc::clone(&os1);
+    async move { os_local.authorize().await.map(|r| warp::reply::json(&r)) }
+  });
+
+  let oidc_callback = warp::path!("oidc" / "callback")
+    .and(warp::post())
+    .and(body_filter.json())
+    .and_then(move |r: OidcCallbackRequest| {
+      let os_local = Arc::clone(&oidc_service);
+      async move { os_local.callback(&r).await.map(|s| warp::reply::json(&s)) }
+    });
+
   warp::path!("session" / ..)
-    .and(create_session.or(refresh_token).or(sign_out))
+    .and(rate_limit_filter.limit(GROUP_SESSION))
+    .and(
+      create_session
+        .or(complete_mfa)
+        .or(refresh_token)
+        .or(oidc_authorize)
+        .or(oidc_callback)
+        .or(sign_out),
+    )
     .boxed()
 }
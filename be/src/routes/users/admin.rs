@@ -0,0 +1,86 @@
+use crate::domain::UserAuth;
+use crate::dto::{ResetPasswordRequest, UpdateUserDisabledRequest};
+use crate::filters::{AuthenticationFilter, BodyFilter};
+use crate::services::UsersService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+/// The first segments of the other /users routes, which are never user ids.
+const RESERVED_SEGMENTS: &[&str] = &["create", "mfa"];
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  body_filter: &BodyFilter,
+  users_service: Arc<dyn UsersService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let us1 = Arc::clone(&users_service);
+  let list_users = warp::path::end()
+    .and(warp::get())
+    .and(authentication_filter.permissioned("user:list"))
+    .and_then(move || {
+      let us_local = Arc::clone(&us1);
+      async move { us_local.list_users().await.map(|u| warp::reply::json(&u)) }
+    });
+
+  let us2 = Arc::clone(&users_service);
+  let get_user = warp::path::param()
+    .and(warp::path::end())
+    .and_then(|user_id: String| async move {
+      if RESERVED_SEGMENTS.contains(&user_id.as_str()) {
+        Err(warp::reject::not_found())
+      } else {
+        Ok(user_id)
+      }
+    })
+    .and(warp::get())
+    .and(authentication_filter.permissioned("user:get"))
+    .and_then(move |user_id: String| {
+      let us_local = Arc::clone(&us2);
+      async move {
+        us_local
+          .get_user_dto(&user_id)
+          .await
+          .map(|u| warp::reply::json(&u))
+      }
+    });
+
+  let us3 = Arc::clone(&users_service);
+  let disable_user = warp::path!(String / "disabled")
+    .and(warp::put())
+    .and(authentication_filter.permissioned_user("user:disable"))
+    .and(body_filter.json())
+    .and_then(
+      move |user_id: String, u: UserAuth, request: UpdateUserDisabledRequest| {
+        let us_local = Arc::clone(&us3);
+        async move {
+          us_local
+            .set_disabled(&u, &user_id, request.disabled)
+            .await
+            .map(|u| warp::reply::json(&u))
+        }
+      },
+    );
+
+  let reset_password = warp::path!(String / "password")
+    .and(warp::put())
+    .and(authentication_filter.permissioned_user("user:resetPassword"))
+    .and(body_filter.json())
+    .and_then(
+      move |user_id: String, u: UserAuth, request: ResetPasswordRequest| {
+        let us_local = Arc::clone(&users_service);
+        async move {
+          us_local
+            .reset_password(&u, &user_id, &request.password)
+            .await
+            .map(|u| warp::reply::json(&u))
+        }
+      },
+    );
+
+  list_users
+    .or(get_user)
+    .or(disable_user)
+    .or(reset_password)
+    .boxed()
+}
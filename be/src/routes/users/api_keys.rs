@@ -0,0 +1,45 @@
+use crate::domain::UserAuth;
+use crate::dto::CreateApiKeyRequest;
+use crate::filters::{AuthenticationFilter, BodyFilter};
+use crate::services::ApiKeyService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  body_filter: &BodyFilter,
+  api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let aks1 = Arc::clone(&api_key_service);
+  let create_api_key = warp::path!(String / "apiKeys")
+    .and(warp::post())
+    .and(authentication_filter.permissioned_user("apiKey:create"))
+    .and(body_filter.json())
+    .and_then(
+      move |user_id: String, u: UserAuth, request: CreateApiKeyRequest| {
+        let aks_local = Arc::clone(&aks1);
+        async move {
+          aks_local
+            .create_api_key(&u, &user_id, request)
+            .await
+            .map(|r| warp::reply::json(&r))
+        }
+      },
+    );
+
+  let revoke_api_key = warp::path!(String / "apiKeys" / String)
+    .and(warp::delete())
+    .and(authentication_filter.permissioned("apiKey:revoke"))
+    .and_then(move |user_id: String, key_id: String| {
+      let aks_local = Arc::clone(&api_key_service);
+      async move {
+        aks_local
+          .revoke_api_key(&user_id, &key_id)
+          .await
+          .map(|_| warp::reply())
+      }
+    });
+
+  create_api_key.or(revoke_api_key).boxed()
+}
@@ -1,5 +1,6 @@
-use crate::dto::CreateSystemAdminUserRequest;
-use crate::filters::AuthenticationFilter;
+use crate::domain::UserAuth;
+use crate::dto::{CreateServiceUserRequest, CreateSystemAdminUserRequest};
+use crate::filters::{AuthenticationFilter, BodyFilter};
 use crate::services::UsersService;
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
@@ -7,21 +8,37 @@ use warp::{Filter, Reply};
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  body_filter: &BodyFilter,
   users_service: Arc<dyn UsersService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
+  let us1 = Arc::clone(&users_service);
   let create_admin = warp::path!("create" / "admin")
     .and(warp::post())
-    .and(authentication_filter.permissioned("createAdminUser"))
-    .and(warp::body::json())
-    .and_then(move |request: CreateSystemAdminUserRequest| {
+    .and(authentication_filter.permissioned_user("user:createAdmin"))
+    .and(body_filter.json())
+    .and_then(move |u: UserAuth, request: CreateSystemAdminUserRequest| {
+      let us_local = Arc::clone(&us1);
+      async move {
+        us_local
+          .create_admin_user(&u, request)
+          .await
+          .map(|r| warp::reply::json(&r))
+      }
+    });
+
+  let create_service = warp::path!("create" / "service")
+    .and(warp::post())
+    .and(authentication_filter.permissioned_user("user:createService"))
+    .and(body_filter.json())
+    .and_then(move |u: UserAuth, request: CreateServiceUserRequest| {
       let us_local = Arc::clone(&users_service);
       async move {
         us_local
-          .create_admin_user(request)
+          .create_service_user(&u, request)
           .await
           .map(|r| warp::reply::json(&r))
       }
     });
 
-  create_admin.boxed()
+  create_admin.or(create_service).boxed()
 }
@@ -0,0 +1,43 @@
+use crate::domain::UserAuth;
+use crate::dto::MfaConfirmRequest;
+use crate::filters::{AuthenticationFilter, BodyFilter};
+use crate::services::MfaService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  body_filter: &BodyFilter,
+  mfa_service: Arc<dyn MfaService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let ms1 = Arc::clone(&mfa_service);
+  let start_enrolment = warp::path!("mfa")
+    .and(warp::post())
+    .and(authentication_filter.auth_user())
+    .and_then(move |u: UserAuth| {
+      let ms_local = Arc::clone(&ms1);
+      async move {
+        ms_local
+          .start_enrolment(&u.sub)
+          .await
+          .map(|r| warp::reply::json(&r))
+      }
+    });
+
+  let confirm_enrolment = warp::path!("mfa" / "confirm")
+    .and(warp::post())
+    .and(authentication_filter.auth_user())
+    .and(body_filter.json())
+    .and_then(move |u: UserAuth, request: MfaConfirmRequest| {
+      let ms_local = Arc::clone(&mfa_service);
+      async move {
+        ms_local
+          .confirm_enrolment(&u.sub, &request.code)
+          .await
+          .map(|r| warp::reply::json(&r))
+      }
+    });
+
+  start_enrolment.or(confirm_enrolment).boxed()
+}
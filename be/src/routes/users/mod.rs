@@ -1,16 +1,48 @@
-use crate::filters::AuthenticationFilter;
-use crate::services::UsersService;
+use crate::filters::rate_limit::GROUP_USERS;
+use crate::filters::{AuthenticationFilter, BodyFilter, RateLimitFilter};
+use crate::services::{ApiKeyService, MfaService, UsersService};
 use std::sync::Arc;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
+mod admin;
+mod api_keys;
 mod create;
+mod mfa;
+mod roles;
 
 pub fn route(
   authentication_filter: &Box<dyn AuthenticationFilter>,
+  body_filter: &BodyFilter,
+  rate_limit_filter: &RateLimitFilter,
   users_service: Arc<dyn UsersService + Send + Sync>,
+  mfa_service: Arc<dyn MfaService + Send + Sync>,
+  api_key_service: Arc<dyn ApiKeyService + Send + Sync>,
 ) -> BoxedFilter<(impl Reply,)> {
   warp::path!("users" / ..)
-    .and(create::route(authentication_filter, users_service))
+    .and(rate_limit_filter.limit(GROUP_USERS))
+    .and(
+      create::route(
+        authentication_filter,
+        body_filter,
+        Arc::clone(&users_service),
+      )
+      .or(admin::route(
+        authentication_filter.as_ref(),
+        body_filter,
+        Arc::clone(&users_service),
+      ))
+      .or(roles::route(authentication_filter.as_ref(), users_service))
+      .or(mfa::route(
+        authentication_filter.as_ref(),
+        body_filter,
+        mfa_service,
+      ))
+      .or(api_keys::route(
+        authentication_filter.as_ref(),
+        body_filter,
+        api_key_service,
+      )),
+    )
     .boxed()
 }
@@ -0,0 +1,40 @@
+use crate::domain::UserAuth;
+use crate::filters::AuthenticationFilter;
+use crate::services::UsersService;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+pub fn route(
+  authentication_filter: &dyn AuthenticationFilter,
+  users_service: Arc<dyn UsersService + Send + Sync>,
+) -> BoxedFilter<(impl Reply,)> {
+  let us1 = Arc::clone(&users_service);
+  let grant_role = warp::path!(String / "roles" / String)
+    .and(warp::put())
+    .and(authentication_filter.permissioned_user("role:grant"))
+    .and_then(move |user_id: String, role_id: String, u: UserAuth| {
+      let us_local = Arc::clone(&us1);
+      async move {
+        us_local
+          .grant_role(&u, &user_id, &role_id)
+          .await
+          .map(|u| warp::reply::json(&u))
+      }
+    });
+
+  let revoke_role = warp::path!(String / "roles" / String)
+    .and(warp::delete())
+    .and(authentication_filter.permissioned("role:revoke"))
+    .and_then(move |user_id: String, role_id: String| {
+      let us_local = Arc::clone(&users_service);
+      async move {
+        us_local
+          .revoke_role(&user_id, &role_id)
+          .await
+          .map(|u| warp::reply::json(&u))
+      }
+    });
+
+  grant_role.or(revoke_role).boxed()
+}
@@ -0,0 +1,25 @@
+use crate::dto::VersionResponse;
+use crate::services::SchemaService;
+use crate::version;
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+pub fn route(schema_service: Arc<dyn SchemaService + Send + Sync>) -> BoxedFilter<(impl Reply,)> {
+  warp::path!("version")
+    .and(warp::get())
+    .and_then(move || {
+      let ss_local = Arc::clone(&schema_service);
+      async move {
+        Ok(warp::reply::json(&VersionResponse {
+          version: version::VERSION,
+          git_commit: version::GIT_COMMIT,
+          build_timestamp: version::build_timestamp(),
+          api_version: version::API_VERSION,
+          schema_version: ss_local.schema_version().await,
+        })) as Result<_, Infallible>
+      }
+    })
+    .boxed()
+}
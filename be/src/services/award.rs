@@ -1,6 +1,6 @@
 use crate::converters::AwardConverter;
 use crate::dto;
-use crate::dto::CreateAwardRequest;
+use crate::dto::{AwardDto, CreateAwardRequest};
 use crate::model::{Award, AwardClassification};
 use crate::repositories::AwardRepository;
 use async_trait::async_trait;
@@ -11,6 +11,8 @@ use warp::Rejection;
 
 #[async_trait]
 pub trait AwardService {
+  async fn get_award(&self, award_id: &str) -> Result<AwardDto, Rejection>;
+  async fn list_awards(&self, external_id: Option<String>) -> Result<Vec<AwardDto>, Rejection>;
   async fn create_award(&self, award: CreateAwardRequest) -> Result<(), Rejection>;
   async fn add_alt_id(
     &self,
@@ -63,7 +65,7 @@ impl AwardServiceImpl {
       Some(a) => Ok(a),
       None => {
         log::warn!(
-          "Attempted to update Award(id={}), but could not be found",
+          "Attempted to load Award(id={}), but could not be found",
           award_id
         );
         Err(warp::reject::not_found())
@@ -117,6 +119,30 @@ impl AwardServiceImpl {
 
 #[async_trait]
 impl AwardService for AwardServiceImpl {
+  async fn get_award(&self, award_id: &str) -> Result<AwardDto, Rejection> {
+    let award = self.load_award(award_id).await?;
+    Ok(self.award_converter.to_dto(&award))
+  }
+
+  async fn list_awards(&self, external_id: Option<String>) -> Result<Vec<AwardDto>, Rejection> {
+    let awards = match external_id {
+      Some(e) => self
+        .award_repository
+        .find_by_external_id(&e)
+        .await?
+        .into_iter()
+        .collect(),
+      None => self.award_repository.find_all().await?,
+    };
+
+    Ok(
+      awards
+        .iter()
+        .map(|a| self.award_converter.to_dto(a))
+        .collect(),
+    )
+  }
+
   async fn create_award(&self, award: CreateAwardRequest) -> Result<(), Rejection> {
     self
       .award_repository
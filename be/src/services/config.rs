@@ -1,35 +1,544 @@
-use crate::domain::AppConfig;
+use crate::domain::{
+  AppConfig, ConfigKey, ConfigValueKind, CorsConfig, CONFIG_KEYS, OPTIONAL_CONFIG_SECTIONS,
+};
+use crate::filters::cors::ORIGIN_ANY;
+use crate::logging::{LOG_FORMAT_JSON, LOG_FORMAT_TEXT};
+use log::LevelFilter;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::RwLock;
+use warp::http::header::HeaderName;
+use warp::http::{Method, Uri};
 
+pub const ENV_CONFIG_FILE: &str = "AUSAWARDS_CONFIG";
+const ENV_PREFIX: &str = "AUSAWARDS_";
+const ENV_PATH_SEPARATOR: &str = "__";
+const ENV_FILE_SUFFIX: &str = "_FILE";
 
 pub trait ConfigService {
   fn get_config(&self) -> AppConfig;
+  fn reload(&self) -> Result<(), ConfigError>;
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+  Read(String, std::io::Error),
+  Parse(String, serde_json::Error),
+  Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ConfigError::Read(fname, e) => write!(f, "Could not read config file '{}': {}", fname, e),
+      ConfigError::Parse(fname, e) => write!(f, "Could not parse config file '{}': {}", fname, e),
+      ConfigError::Invalid(errors) => {
+        writeln!(f, "Configuration is invalid:")?;
+        for e in errors {
+          writeln!(f, "  - {}", e)?;
+        }
+        Ok(())
+      }
+    }
+  }
 }
 
 pub struct FileConfigService {
-  fname: &'static str,
-  config: Option<AppConfig>,
+  fname: String,
+  overrides: Vec<(String, String)>,
+  root: RwLock<Value>,
+  config: RwLock<Option<AppConfig>>,
 }
 
 impl FileConfigService {
-  pub fn new(fname: &'static str) -> FileConfigService {
+  pub fn new(fname: &str) -> FileConfigService {
     FileConfigService {
-      fname,
-      config: None,
+      fname: String::from(fname),
+      overrides: Vec::new(),
+      root: RwLock::new(Value::Null),
+      config: RwLock::new(None),
     }
   }
 
-  pub fn read_config(&mut self) -> Result<(), std::io::Error> {
-    log::debug!("Loading config from file '{}'", self.fname);
-    let content = std::fs::read_to_string(&self.fname)?;
-    let config: AppConfig = serde_json::from_str(&content)?;
-    self.config = Some(config);
+  pub fn with_override(mut self, key: &str, value: &str) -> FileConfigService {
+    self
+      .overrides
+      .push((String::from(key), String::from(value)));
+    self
+  }
+
+  pub fn read_config(&mut self) -> Result<(), ConfigError> {
+    let root = self.load()?;
+    let config = FileConfigService::parse(&root)?;
 
+    *self.root.write().unwrap() = root;
+    *self.config.write().unwrap() = Some(config);
     Ok(())
   }
+
+  fn load(&self) -> Result<Value, ConfigError> {
+    let mut errors = Vec::new();
+    let mut root = self.read_file()?;
+
+    for (key, value) in FileConfigService::env_overrides(&mut errors) {
+      FileConfigService::apply(&mut root, &key, &value, &mut errors);
+    }
+    for (key, value) in &self.overrides {
+      log::debug!("Applying config override for '{}'", key);
+      FileConfigService::apply(&mut root, key, value, &mut errors);
+    }
+
+    FileConfigService::validate(&root, &mut errors);
+    if !errors.is_empty() {
+      return Err(ConfigError::Invalid(errors));
+    }
+
+    Ok(root)
+  }
+
+  fn parse(root: &Value) -> Result<AppConfig, ConfigError> {
+    let config: AppConfig = serde_json::from_value(root.clone())
+      .map_err(|e| ConfigError::Invalid(vec![e.to_string()]))?;
+    let mut errors = Vec::new();
+    if config.log.level.parse::<LevelFilter>().is_err() {
+      errors.push(format!("log.level: unknown level '{}'", config.log.level));
+    }
+    if ![LOG_FORMAT_TEXT, LOG_FORMAT_JSON].contains(&config.log.format.as_str()) {
+      errors.push(format!(
+        "log.format: unknown format '{}'",
+        config.log.format
+      ));
+    }
+    if let Some(cors) = &config.cors {
+      FileConfigService::validate_cors(cors, &mut errors);
+    }
+    let rate_limits = &config.rate_limit;
+    for (group, limit) in &[
+      ("session", &rate_limits.session),
+      ("awards", &rate_limits.awards),
+      ("users", &rate_limits.users),
+    ] {
+      if limit.capacity == 0 || limit.per_minute == 0 {
+        errors.push(format!(
+          "rate_limit.{}: capacity and per_minute must be positive",
+          group
+        ));
+      }
+    }
+    for proxy in &rate_limits.trusted_proxies {
+      if proxy.parse::<IpAddr>().is_err() {
+        errors.push(format!(
+          "rate_limit.trusted_proxies: invalid address '{}'",
+          proxy
+        ));
+      }
+    }
+    if !errors.is_empty() {
+      return Err(ConfigError::Invalid(errors));
+    }
+
+    Ok(config)
+  }
+
+  fn read_file(&self) -> Result<Value, ConfigError> {
+    log::debug!("Loading config from file '{}'", self.fname);
+    let content = match std::fs::read_to_string(&self.fname) {
+      Ok(c) => c,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+        log::warn!(
+          "Config file '{}' not found; configuration must be supplied through the environment",
+          self.fname
+        );
+        return Ok(Value::Object(Map::new()));
+      }
+      Err(e) => return Err(ConfigError::Read(self.fname.clone(), e)),
+    };
+
+    serde_json::from_str(&content).map_err(|e| ConfigError::Parse(self.fname.clone(), e))
+  }
+
+  /// Maps an environment variable to the config key it overrides, and whether its value names a
+  /// file to read. Variables with a section separator are always config, so typos are reported;
+  /// others only when they name a top-level key, leaving room for other AUSAWARDS_ variables.
+  fn env_config_key(name: &str) -> Option<(String, bool)> {
+    if name == ENV_CONFIG_FILE {
+      return None;
+    }
+    let var = name.strip_prefix(ENV_PREFIX)?;
+    let (var, from_file) = match var.strip_suffix(ENV_FILE_SUFFIX) {
+      Some(v) => (v, true),
+      None => (var, false),
+    };
+
+    let key = var
+      .split(ENV_PATH_SEPARATOR)
+      .map(|s| s.to_lowercase())
+      .collect::<Vec<String>>()
+      .join(".");
+    if var.contains(ENV_PATH_SEPARATOR) || FileConfigService::find_key(&key).is_some() {
+      Some((key, from_file))
+    } else {
+      None
+    }
+  }
+
+  fn env_overrides(errors: &mut Vec<String>) -> Vec<(String, String)> {
+    let mut overrides = Vec::new();
+    for (name, value) in std::env::vars() {
+      let (key, from_file) = match FileConfigService::env_config_key(&name) {
+        Some(k) => k,
+        None => continue,
+      };
+
+      let value = if from_file {
+        match std::fs::read_to_string(&value) {
+          Ok(content) => String::from(content.trim_end()),
+          Err(e) => {
+            errors.push(format!("{}: could not read '{}': {}", name, value, e));
+            continue;
+          }
+        }
+      } else {
+        value
+      };
+
+      log::debug!("Applying config override for '{}' from {}", key, name);
+      overrides.push((key, value));
+    }
+
+    overrides
+  }
+
+  fn find_key(key: &str) -> Option<&'static ConfigKey> {
+    CONFIG_KEYS.iter().find(|k| k.path == key)
+  }
+
+  fn apply(root: &mut Value, key: &str, value: &str, errors: &mut Vec<String>) {
+    let config_key = match FileConfigService::find_key(key) {
+      Some(k) => k,
+      None => {
+        errors.push(format!("{}: unknown configuration key", key));
+        return;
+      }
+    };
+
+    let value = match config_key.kind {
+      ConfigValueKind::String => Value::from(value),
+      ConfigValueKind::Boolean => match value.parse::<bool>() {
+        Ok(b) => Value::from(b),
+        Err(_) => {
+          errors.push(format!("{}: expected true or false, got '{}'", key, value));
+          return;
+        }
+      },
+      ConfigValueKind::Integer => match value.parse::<i64>() {
+        Ok(i) => Value::from(i),
+        Err(_) => {
+          errors.push(format!("{}: expected an integer, got '{}'", key, value));
+          return;
+        }
+      },
+      ConfigValueKind::StringList => Value::from(
+        value
+          .split(',')
+          .map(|s| s.trim())
+          .filter(|s| !s.is_empty())
+          .collect::<Vec<&str>>(),
+      ),
+    };
+
+    FileConfigService::apply_value(root, key, value);
+  }
+
+  fn apply_value(root: &mut Value, key: &str, value: Value) {
+    let mut node = root;
+    for segment in key.split('.') {
+      if !node.is_object() {
+        *node = Value::Object(Map::new());
+      }
+      node = node
+        .as_object_mut()
+        .unwrap()
+        .entry(segment)
+        .or_insert(Value::Null);
+    }
+    *node = value;
+  }
+
+  fn validate_cors(cors: &CorsConfig, errors: &mut Vec<String>) {
+    for origin in &cors.allowed_origins {
+      let valid = origin == ORIGIN_ANY
+        || match origin.parse::<Uri>() {
+          Ok(u) => {
+            u.scheme().is_some() && u.authority().is_some() && (u.path() == "/" || u.path() == "")
+          }
+          Err(_) => false,
+        };
+      if !valid {
+        errors.push(format!("cors.allowed_origins: invalid origin '{}'", origin));
+      }
+    }
+    for method in &cors.allowed_methods {
+      if Method::from_bytes(method.as_bytes()).is_err() {
+        errors.push(format!("cors.allowed_methods: invalid method '{}'", method));
+      }
+    }
+    for header in &cors.allowed_headers {
+      if HeaderName::from_bytes(header.as_bytes()).is_err() {
+        errors.push(format!("cors.allowed_headers: invalid header '{}'", header));
+      }
+    }
+  }
+
+  fn lookup<'a>(root: &'a Value, key: &str) -> Option<&'a Value> {
+    key
+      .split('.')
+      .try_fold(root, |node, segment| node.get(segment))
+      .filter(|v| !v.is_null())
+  }
+
+  fn validate(root: &Value, errors: &mut Vec<String>) {
+    for key in CONFIG_KEYS {
+      let absent_section = OPTIONAL_CONFIG_SECTIONS.iter().any(|s| {
+        key.path.starts_with(&format!("{}.", s)) && FileConfigService::lookup(root, s).is_none()
+      });
+      if absent_section {
+        continue;
+      }
+
+      let valid = match (FileConfigService::lookup(root, key.path), key.kind) {
+        (None, _) => {
+          errors.push(format!("{}: missing", key.path));
+          continue;
+        }
+        (Some(v), ConfigValueKind::String) => v.is_string(),
+        (Some(v), ConfigValueKind::Boolean) => v.is_boolean(),
+        (Some(v), ConfigValueKind::Integer) => v.is_i64() || v.is_u64(),
+        (Some(v), ConfigValueKind::StringList) => v
+          .as_array()
+          .map(|a| a.iter().all(|i| i.is_string()))
+          .unwrap_or(false),
+      };
+      if !valid {
+        let expected = match key.kind {
+          ConfigValueKind::String => "a string",
+          ConfigValueKind::Boolean => "true or false",
+          ConfigValueKind::Integer => "an integer",
+          ConfigValueKind::StringList => "a list of strings",
+        };
+        errors.push(format!("{}: expected {}", key.path, expected));
+      }
+    }
+  }
 }
 
 impl ConfigService for FileConfigService {
   fn get_config(&self) -> AppConfig {
-    self.config.as_ref().unwrap().clone()
+    self.config.read().unwrap().as_ref().unwrap().clone()
+  }
+
+  fn reload(&self) -> Result<(), ConfigError> {
+    log::info!("Reloading configuration from '{}'", self.fname);
+    let mut root = self.load()?;
+    let current = self.root.read().unwrap().clone();
+
+    for section in OPTIONAL_CONFIG_SECTIONS {
+      let old = FileConfigService::lookup(&current, section).cloned();
+      if old.is_some() == FileConfigService::lookup(&root, section).is_some() {
+        continue;
+      }
+
+      log::warn!(
+        "Configuration section '{}' was added or removed but cannot be reloaded; restart to apply",
+        section
+      );
+      FileConfigService::apply_value(&mut root, section, old.unwrap_or(Value::Null));
+    }
+
+    let mut changed = Vec::new();
+    for key in CONFIG_KEYS {
+      let old = FileConfigService::lookup(&current, key.path).cloned();
+      if FileConfigService::lookup(&root, key.path) == old.as_ref() {
+        continue;
+      }
+      if key.reloadable {
+        changed.push(key.path);
+        continue;
+      }
+
+      log::warn!(
+        "Configuration key '{}' changed but cannot be reloaded; restart to apply",
+        key.path
+      );
+      FileConfigService::apply_value(&mut root, key.path, old.unwrap_or(Value::Null));
+    }
+    let config = FileConfigService::parse(&root)?;
+
+    *self.root.write().unwrap() = root;
+    *self.config.write().unwrap() = Some(config);
+    if changed.is_empty() {
+      log::info!("Configuration reloaded; no changes applied");
+    } else {
+      log::info!("Configuration reloaded; applied {}", changed.join(", "));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn env_config_key_maps_sections_and_files() {
+    assert_eq!(
+      FileConfigService::env_config_key("AUSAWARDS_MONGO__URI"),
+      Some((String::from("mongo.uri"), false))
+    );
+    assert_eq!(
+      FileConfigService::env_config_key("AUSAWARDS_JWT__SECRET_FILE"),
+      Some((String::from("jwt.secret"), true))
+    );
+    assert_eq!(
+      FileConfigService::env_config_key("AUSAWARDS_APP_NAME"),
+      Some((String::from("app_name"), false))
+    );
+  }
+
+  #[test]
+  fn env_config_key_keeps_unknown_sectioned_keys_to_report_them() {
+    assert_eq!(
+      FileConfigService::env_config_key("AUSAWARDS_MONGO__URL"),
+      Some((String::from("mongo.url"), false))
+    );
+  }
+
+  #[test]
+  fn env_config_key_ignores_other_variables() {
+    assert_eq!(FileConfigService::env_config_key("AUSAWARDS_PROFILE"), None);
+    assert_eq!(FileConfigService::env_config_key(ENV_CONFIG_FILE), None);
+    assert_eq!(FileConfigService::env_config_key("MONGO__URI"), None);
+  }
+
+  #[test]
+  fn validate_cors_reports_invalid_origins_methods_and_headers() {
+    let cors = CorsConfig {
+      allowed_origins: vec![
+        String::from(ORIGIN_ANY),
+        String::from("https://awards.example.com"),
+        String::from("awards.example.com"),
+        String::from("https://awards.example.com/app"),
+      ],
+      allowed_methods: vec![String::from("GET"), String::from("NOT A METHOD")],
+      allowed_headers: vec![String::from("authorization"), String::from("bad header")],
+      max_age_sec: 600,
+    };
+    let mut errors = Vec::new();
+    FileConfigService::validate_cors(&cors, &mut errors);
+    assert_eq!(
+      errors,
+      vec![
+        "cors.allowed_origins: invalid origin 'awards.example.com'",
+        "cors.allowed_origins: invalid origin 'https://awards.example.com/app'",
+        "cors.allowed_methods: invalid method 'NOT A METHOD'",
+        "cors.allowed_headers: invalid header 'bad header'",
+      ]
+    );
+  }
+
+  /// A copy of the bundled config in a temporary file, which tests can then edit.
+  struct TempConfig {
+    path: std::path::PathBuf,
+  }
+
+  impl TempConfig {
+    fn new(name: &str) -> TempConfig {
+      let path = std::env::temp_dir().join(format!(
+        "ausawards-{}-{}.json",
+        name,
+        std::process::id()
+      ));
+      std::fs::copy("resources/config.json", &path).unwrap();
+      TempConfig { path }
+    }
+
+    fn service(&self) -> FileConfigService {
+      let mut service = FileConfigService::new(self.path.to_str().unwrap());
+      service.read_config().unwrap();
+      service
+    }
+
+    fn set(&self, key: &str, value: Value) {
+      let mut root: Value =
+        serde_json::from_str(&std::fs::read_to_string(&self.path).unwrap()).unwrap();
+      FileConfigService::apply_value(&mut root, key, value);
+      std::fs::write(&self.path, root.to_string()).unwrap();
+    }
+  }
+
+  impl Drop for TempConfig {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_file(&self.path);
+    }
+  }
+
+  #[test]
+  fn reload_applies_reloadable_keys_only() {
+    let file = TempConfig::new("reload-keys");
+    let service = file.service();
+    let before = service.get_config();
+
+    file.set("log.level", Value::from("warn"));
+    file.set("rate_limit.awards.capacity", Value::from(7));
+    file.set("server.port", Value::from(4040));
+    file.set("mongo.database", Value::from("other"));
+    service.reload().unwrap();
+
+    let after = service.get_config();
+    assert_eq!(after.log.level, "warn");
+    assert_eq!(after.rate_limit.awards.capacity, 7);
+    assert_eq!(after.server.port, before.server.port);
+    assert_eq!(after.mongo.database, before.mongo.database);
+  }
+
+  #[test]
+  fn reload_keeps_optional_sections_as_they_were() {
+    let file = TempConfig::new("reload-sections");
+    let service = file.service();
+    assert!(service.get_config().oidc.is_none());
+
+    file.set(
+      "oidc",
+      serde_json::json!({
+        "issuer": "https://idp.example",
+        "client_id": "ausawards",
+        "client_secret": "secret",
+        "redirect_uri": "https://app.example/callback",
+        "scopes": ["openid"],
+        "default_role_id": "reader",
+        "login_lifetime_sec": 300,
+      }),
+    );
+    file.set("cors", Value::Null);
+    service.reload().unwrap();
+
+    let after = service.get_config();
+    assert!(after.oidc.is_none());
+    assert!(after.cors.is_some());
+  }
+
+  #[test]
+  fn reload_of_invalid_config_keeps_the_current_config() {
+    let file = TempConfig::new("reload-invalid");
+    let service = file.service();
+
+    file.set("log.level", Value::from("loud"));
+    file.set("rate_limit.awards.capacity", Value::from(7));
+    assert!(matches!(service.reload(), Err(ConfigError::Invalid(_))));
+
+    let config = service.get_config();
+    assert_eq!(config.log.level, "debug");
+    assert_ne!(config.rate_limit.awards.capacity, 7);
   }
 }
@@ -0,0 +1,124 @@
+use crate::dto::health::{HEALTH_STATUS_DOWN, HEALTH_STATUS_UP};
+use crate::dto::{DependencyStatus, ReadinessResponse};
+use crate::services::ConfigService;
+use async_trait::async_trait;
+use mongodb::Database;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEPENDENCY_MONGO: &str = "mongo";
+
+#[async_trait]
+pub trait HealthService {
+  async fn readiness(&self) -> ReadinessResponse;
+}
+
+pub struct HealthServiceImpl {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  database: Database,
+}
+
+impl HealthServiceImpl {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    database: Database,
+  ) -> HealthServiceImpl {
+    HealthServiceImpl {
+      config_service,
+      database,
+    }
+  }
+
+  async fn check_mongo(&self) -> DependencyStatus {
+    let timeout = Duration::from_millis(self.config_service.get_config().health.ready_timeout_ms);
+    let started = Instant::now();
+    let ping = self.database.run_command(bson::doc! { "ping": 1 }, None);
+    let error = match tokio::time::timeout(timeout, ping).await {
+      Ok(Ok(_)) => None,
+      Ok(Err(e)) => Some(e.to_string()),
+      Err(_) => Some(format!("timed out after {}ms", timeout.as_millis())),
+    };
+
+    if let Some(e) = &error {
+      log::warn!("Readiness check for {} failed: {}", DEPENDENCY_MONGO, e);
+    }
+    DependencyStatus {
+      name: DEPENDENCY_MONGO,
+      status: if error.is_none() {
+        HEALTH_STATUS_UP
+      } else {
+        HEALTH_STATUS_DOWN
+      },
+      latency_ms: started.elapsed().as_millis(),
+      error,
+    }
+  }
+
+  /// Ready only when every dependency is up.
+  fn overall_status(dependencies: &[DependencyStatus]) -> &'static str {
+    if dependencies.iter().all(|d| d.status == HEALTH_STATUS_UP) {
+      HEALTH_STATUS_UP
+    } else {
+      HEALTH_STATUS_DOWN
+    }
+  }
+}
+
+#[async_trait]
+impl HealthService for HealthServiceImpl {
+  async fn readiness(&self) -> ReadinessResponse {
+    let dependencies = vec![self.check_mongo().await];
+    ReadinessResponse {
+      status: HealthServiceImpl::overall_status(&dependencies),
+      dependencies,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{config, StubConfigService};
+  use mongodb::Client;
+
+  fn dependency(status: &'static str) -> DependencyStatus {
+    DependencyStatus {
+      name: DEPENDENCY_MONGO,
+      status,
+      latency_ms: 0,
+      error: None,
+    }
+  }
+
+  #[test]
+  fn ready_only_when_every_dependency_is_up() {
+    let up = || dependency(HEALTH_STATUS_UP);
+    let down = || dependency(HEALTH_STATUS_DOWN);
+    assert_eq!(
+      HealthServiceImpl::overall_status(&[up(), up()]),
+      HEALTH_STATUS_UP
+    );
+    assert_eq!(
+      HealthServiceImpl::overall_status(&[up(), down()]),
+      HEALTH_STATUS_DOWN
+    );
+  }
+
+  #[tokio::test]
+  async fn unreachable_mongo_is_reported_down_within_the_timeout() {
+    let config = config();
+    let timeout = Duration::from_millis(config.health.ready_timeout_ms);
+    let database = Client::with_uri_str(&config.mongo.uri)
+      .await
+      .unwrap()
+      .database(&config.mongo.database);
+    let service = HealthServiceImpl::new(Arc::new(StubConfigService { config }), database);
+
+    let started = Instant::now();
+    let res = service.readiness().await;
+    assert!(started.elapsed() < timeout * 10);
+    assert_eq!(res.status, HEALTH_STATUS_DOWN);
+    assert_eq!(res.dependencies[0].status, HEALTH_STATUS_DOWN);
+    assert!(res.dependencies[0].error.is_some());
+  }
+}
@@ -1,6 +1,8 @@
 pub mod config;
 pub mod users;
 pub mod award;
+pub mod health;
+pub mod schema;
 
 pub use config::ConfigService;
 pub use users::service::UsersService;
@@ -8,4 +10,9 @@ pub use users::hash::HashService;
 pub use users::token::TokenService;
 pub use users::roles::RolesService;
 pub use users::session::SessionService;
+pub use users::mfa::MfaService;
+pub use users::api_key::ApiKeyService;
+pub use users::oidc::OidcService;
 pub use award::AwardService;
+pub use health::HealthService;
+pub use schema::SchemaService;
@@ -0,0 +1,102 @@
+use crate::repositories::MigrationRepository;
+use crate::services::ConfigService;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_trait]
+pub trait SchemaService {
+  /// Highest migration version recorded in the database, or None when no migration has been
+  /// applied or the database cannot be read in time.
+  async fn schema_version(&self) -> Option<u32>;
+}
+
+pub struct SchemaServiceImpl {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  migration_repository: Arc<dyn MigrationRepository + Send + Sync>,
+}
+
+impl SchemaServiceImpl {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    migration_repository: Arc<dyn MigrationRepository + Send + Sync>,
+  ) -> SchemaServiceImpl {
+    SchemaServiceImpl {
+      config_service,
+      migration_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl SchemaService for SchemaServiceImpl {
+  async fn schema_version(&self) -> Option<u32> {
+    let timeout = Duration::from_millis(self.config_service.get_config().health.ready_timeout_ms);
+    match tokio::time::timeout(timeout, self.migration_repository.find_all()).await {
+      Ok(Ok(migrations)) => migrations.iter().map(|m| m.version).max(),
+      Ok(Err(_)) => None,
+      Err(_) => {
+        log::warn!(
+          "Reading the schema version timed out after {}ms",
+          timeout.as_millis()
+        );
+        None
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::Migration;
+  use crate::repositories::migration::MigrationRepositoryImpl;
+  use crate::testing::{config, MemoryMigrationRepository, StubConfigService};
+  use chrono::Utc;
+  use mongodb::Client;
+
+  fn migration(name: &str, version: u32) -> Migration {
+    Migration {
+      name: name.to_string(),
+      version,
+      applied_at: Utc::now().into(),
+    }
+  }
+
+  fn service(repository: Arc<dyn MigrationRepository + Send + Sync>) -> SchemaServiceImpl {
+    SchemaServiceImpl::new(Arc::new(StubConfigService { config: config() }), repository)
+  }
+
+  #[tokio::test]
+  async fn schema_version_is_the_highest_applied_migration() {
+    let repository = MemoryMigrationRepository::default();
+    repository.migrations.lock().unwrap().extend(vec![
+      migration("permissions-resource-action", 2),
+      migration("super-admin-role", 1),
+    ]);
+    let service = service(Arc::new(repository));
+
+    assert_eq!(service.schema_version().await, Some(2));
+  }
+
+  #[tokio::test]
+  async fn schema_version_is_unknown_without_migrations() {
+    let service = service(Arc::new(MemoryMigrationRepository::default()));
+
+    assert_eq!(service.schema_version().await, None);
+  }
+
+  #[tokio::test]
+  async fn schema_version_is_unknown_when_mongo_is_unreachable() {
+    let config = config();
+    let database = Client::with_uri_str(&config.mongo.uri)
+      .await
+      .unwrap()
+      .database(&config.mongo.database);
+    let service = service(Arc::new(MigrationRepositoryImpl::new(
+      database.collection("Migrations"),
+    )));
+
+    assert_eq!(service.schema_version().await, None);
+  }
+}
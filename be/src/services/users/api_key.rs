@@ -0,0 +1,366 @@
+use crate::domain::{permission_matches, UserAuth};
+use crate::dto::{ApiKeyCreatedResponse, CreateApiKeyRequest};
+use crate::errors::{AuthenticationError, AuthorizationError, ValidationError};
+use crate::model::user::USER_TYPE_SERVICE;
+use crate::model::{ApiKey, User};
+use crate::repositories::ApiKeyRepository;
+use crate::services::{ConfigService, RolesService, UsersService};
+use crate::services::users::hash::constant_time_eq;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use pwhash::bcrypt;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::Rejection;
+
+const KEY_SEPARATOR: char = '.';
+const BCRYPT_PREFIX: &str = "$2";
+const LAST_USED_RESOLUTION_SEC: i64 = 60;
+
+#[async_trait]
+pub trait ApiKeyService {
+  async fn create_api_key(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    request: CreateApiKeyRequest,
+  ) -> Result<ApiKeyCreatedResponse, Rejection>;
+  async fn revoke_api_key(&self, user_id: &str, key_id: &str) -> Result<(), Rejection>;
+  async fn authenticate(&self, key: &str) -> Result<UserAuth, Rejection>;
+}
+
+pub struct ApiKeyServiceImpl {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  users_service: Arc<dyn UsersService + Send + Sync>,
+  roles_service: Arc<dyn RolesService + Send + Sync>,
+  api_key_repository: Arc<dyn ApiKeyRepository + Send + Sync>,
+}
+
+impl ApiKeyServiceImpl {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    users_service: Arc<dyn UsersService + Send + Sync>,
+    roles_service: Arc<dyn RolesService + Send + Sync>,
+    api_key_repository: Arc<dyn ApiKeyRepository + Send + Sync>,
+  ) -> ApiKeyServiceImpl {
+    ApiKeyServiceImpl {
+      config_service,
+      users_service,
+      roles_service,
+      api_key_repository,
+    }
+  }
+
+  fn create_secret(&self) -> String {
+    rand::thread_rng()
+      .sample_iter(&Alphanumeric)
+      .take(self.config_service.get_config().api_key.secret_length)
+      .collect()
+  }
+
+  /// Secrets are long and random, so a fast hash is enough and keeps per-request cost low.
+  fn hash_secret(secret: &str) -> String {
+    base64::encode(Sha256::digest(secret.as_bytes()))
+  }
+
+  fn secret_matches(secret: &str, key_secret: &str) -> bool {
+    if key_secret.starts_with(BCRYPT_PREFIX) {
+      return bcrypt::verify(secret, key_secret);
+    }
+
+    constant_time_eq(&ApiKeyServiceImpl::hash_secret(secret), key_secret)
+  }
+
+  async fn load_service_user(&self, user_id: &str) -> Result<User, Rejection> {
+    match self.users_service.get_user(user_id).await? {
+      Some(u) if u.user_type == USER_TYPE_SERVICE => Ok(u),
+      Some(_) => {
+        log::debug!("User(id={}) is not a service account", user_id);
+        Err(warp::reject::custom(ValidationError::new()))
+      }
+      None => Err(warp::reject::not_found()),
+    }
+  }
+
+  async fn user_permissions(&self, user: &User) -> Result<HashSet<String>, Rejection> {
+    let roles = self
+      .roles_service
+      .resolve_roles(user.role_ids.iter().map(|i| i as &str).collect())
+      .await?;
+
+    Ok(roles.into_iter().flat_map(|r| r.permissions).collect())
+  }
+}
+
+#[async_trait]
+impl ApiKeyService for ApiKeyServiceImpl {
+  async fn create_api_key(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    request: CreateApiKeyRequest,
+  ) -> Result<ApiKeyCreatedResponse, Rejection> {
+    let user = self.load_service_user(user_id).await?;
+    if request.expire_at.map(|e| e <= Utc::now()).unwrap_or(false) {
+      log::debug!("Attempted to create an already expired API key");
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    let not_held = request
+      .permissions
+      .iter()
+      .find(|p| !caller.has_permission(p));
+    if let Some(p) = not_held {
+      log::warn!(
+        "User(id={}) attempted to create an API key with permission {} it does not hold",
+        caller.sub,
+        p
+      );
+      return Err(warp::reject::custom(AuthorizationError::new()));
+    }
+
+    let secret = self.create_secret();
+    let api_key = ApiKey {
+      id: Uuid::new_v4().to_hyphenated().to_string(),
+      user_id: user.id.clone(),
+      name: request.name,
+      key_secret: ApiKeyServiceImpl::hash_secret(&secret),
+      permissions: request.permissions,
+      created_at: bson::DateTime::from(Utc::now()),
+      expire_at: request.expire_at.map(bson::DateTime::from),
+      last_used_at: None,
+      revoked: false,
+    };
+    self.api_key_repository.save(&api_key).await?;
+
+    log::info!(
+      "ApiKey(id={}, name={}) created for User(id={})",
+      api_key.id,
+      api_key.name,
+      user_id
+    );
+    Ok(ApiKeyCreatedResponse {
+      key: format!("{}{}{}", api_key.id, KEY_SEPARATOR, secret),
+      id: api_key.id,
+      name: api_key.name,
+      permissions: api_key.permissions,
+      expire_at: request.expire_at,
+    })
+  }
+
+  async fn revoke_api_key(&self, user_id: &str, key_id: &str) -> Result<(), Rejection> {
+    let api_key = self.api_key_repository.find_by_id(key_id).await?;
+    let mut api_key = match api_key {
+      Some(k) if k.user_id == user_id => k,
+      _ => {
+        log::warn!(
+          "Attempted to revoke ApiKey(id={}, userId={}), but could not be found",
+          key_id,
+          user_id
+        );
+        return Err(warp::reject::not_found());
+      }
+    };
+
+    api_key.revoked = true;
+    self.api_key_repository.save(&api_key).await?;
+
+    log::info!("ApiKey(id={}) revoked", key_id);
+    Ok(())
+  }
+
+  async fn authenticate(&self, key: &str) -> Result<UserAuth, Rejection> {
+    let mut parts = key.splitn(2, KEY_SEPARATOR);
+    let (key_id, secret) = match (parts.next(), parts.next()) {
+      (Some(i), Some(s)) => (i, s),
+      _ => return Err(warp::reject::custom(AuthenticationError::new())),
+    };
+
+    let api_key = self.api_key_repository.find_active_by_id(key_id).await?;
+    let api_key = match api_key {
+      Some(k) => k,
+      None => {
+        log::debug!("Could not locate active API key {}", key_id);
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    };
+    if !ApiKeyServiceImpl::secret_matches(secret, &api_key.key_secret) {
+      log::debug!("Invalid secret for API key {}", key_id);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    if api_key.key_secret.starts_with(BCRYPT_PREFIX) {
+      self
+        .api_key_repository
+        .update_key_secret(&api_key.id, &ApiKeyServiceImpl::hash_secret(secret))
+        .await?;
+    }
+
+    let user = match self.users_service.get_user(&api_key.user_id).await? {
+      Some(u) => u,
+      None => {
+        log::warn!(
+          "Could not find User(id={}), but ApiKey(id={}) is active",
+          api_key.user_id,
+          api_key.id
+        );
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    };
+    if user.disabled {
+      log::debug!(
+        "ApiKey(id={}) belongs to disabled User(id={})",
+        api_key.id,
+        user.id
+      );
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    let granted = self.user_permissions(&user).await?;
+
+    let now = Utc::now();
+    let last_used_stale = api_key
+      .last_used_at
+      .map(|l| *l + Duration::seconds(LAST_USED_RESOLUTION_SEC) <= now)
+      .unwrap_or(true);
+    if last_used_stale {
+      self
+        .api_key_repository
+        .update_last_used_at(&api_key.id, now)
+        .await?;
+    }
+
+    Ok(UserAuth {
+      sub: user.id,
+      login_id: user.login_id,
+      session_id: api_key.id,
+      permissions: api_key
+        .permissions
+        .into_iter()
+        .filter(|p| granted.iter().any(|g| permission_matches(g, p)))
+        .collect(),
+      iat: now.timestamp(),
+      exp: api_key.expire_at.map(|e| e.timestamp()).unwrap_or(i64::MAX),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::converters::role::RoleConverterImpl;
+  use crate::converters::user::UserConverterImpl;
+  use crate::model::Role;
+  use crate::repositories::{RoleRepository, UsersRepository};
+  use crate::services::users::{RolesServiceImpl, UsersServiceImpl};
+  use chrono::DateTime;
+  use crate::testing::{
+    config, MemoryApiKeyRepository, MemoryRoleRepository, MemorySessionRepository,
+    MemoryUsersRepository, PlainHashService, StubConfigService,
+  };
+
+  const SERVICE_USER_ID: &str = "service";
+
+  async fn service() -> ApiKeyServiceImpl {
+    let roles = Arc::new(MemoryRoleRepository::default());
+    roles
+      .save(&Role {
+        id: String::from("editor"),
+        name: String::from("editor"),
+        permissions: vec![String::from("award:*")],
+        included_role_ids: Vec::new(),
+      })
+      .await
+      .unwrap();
+    let users = Arc::new(MemoryUsersRepository::default());
+    users
+      .save(&User {
+        id: String::from(SERVICE_USER_ID),
+        user_type: String::from(USER_TYPE_SERVICE),
+        company_id: None,
+        login_id: String::from(SERVICE_USER_ID),
+        password: String::from("hashed:password"),
+        role_ids: vec![String::from("editor")],
+        mfa: None,
+        external_identity: None,
+        disabled: false,
+      })
+      .await
+      .unwrap();
+
+    let roles_service = Arc::new(RolesServiceImpl::new(
+      Arc::new(RoleConverterImpl::new()),
+      roles,
+    ));
+    let users_service = Arc::new(UsersServiceImpl::new(
+      Arc::new(UserConverterImpl::new()),
+      Arc::new(PlainHashService {}),
+      Arc::clone(&roles_service) as Arc<dyn RolesService + Send + Sync>,
+      users as Arc<dyn UsersRepository + Send + Sync>,
+      Arc::new(MemorySessionRepository::default()),
+    ));
+    ApiKeyServiceImpl::new(
+      Arc::new(StubConfigService { config: config() }),
+      users_service,
+      roles_service,
+      Arc::new(MemoryApiKeyRepository::default()),
+    )
+  }
+
+  fn caller(permissions: &[&str]) -> UserAuth {
+    UserAuth {
+      sub: String::from("caller"),
+      login_id: String::from("caller"),
+      session_id: String::from("caller-session"),
+      permissions: permissions.iter().map(|p| p.to_string()).collect(),
+      iat: 0,
+      exp: i64::MAX,
+    }
+  }
+
+  fn request(permissions: &[&str], expire_at: Option<DateTime<Utc>>) -> CreateApiKeyRequest {
+    CreateApiKeyRequest {
+      name: String::from("ci"),
+      permissions: permissions.iter().map(|p| p.to_string()).collect(),
+      expire_at,
+    }
+  }
+
+  #[tokio::test]
+  async fn keys_are_limited_to_the_permissions_of_the_caller() {
+    let service = service().await;
+    let caller = caller(&["apiKey:create", "award:get"]);
+
+    let res = service
+      .create_api_key(&caller, SERVICE_USER_ID, request(&["award:create"], None))
+      .await;
+    assert!(res.err().unwrap().find::<AuthorizationError>().is_some());
+
+    let created = service
+      .create_api_key(&caller, SERVICE_USER_ID, request(&["award:get"], None))
+      .await
+      .unwrap();
+    let auth = service.authenticate(&created.key).await.unwrap();
+    assert_eq!(auth.sub, SERVICE_USER_ID);
+    assert_eq!(auth.permissions, vec![String::from("award:get")]);
+  }
+
+  #[tokio::test]
+  async fn keys_cannot_be_created_already_expired() {
+    let service = service().await;
+    let caller = caller(&["*"]);
+
+    let expired = Some(Utc::now() - Duration::seconds(1));
+    let res = service
+      .create_api_key(&caller, SERVICE_USER_ID, request(&["award:get"], expired))
+      .await;
+    assert!(res.err().unwrap().find::<ValidationError>().is_some());
+
+    let future = Some(Utc::now() + Duration::days(1));
+    assert!(service
+      .create_api_key(&caller, SERVICE_USER_ID, request(&["award:get"], future))
+      .await
+      .is_ok());
+  }
+}
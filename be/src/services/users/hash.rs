@@ -2,6 +2,16 @@ use crate::errors::ServerError;
 use pwhash::bcrypt;
 use warp::Rejection;
 
+/// Compares two secrets without short-circuiting on the first differing byte.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+  a.len() == b.len()
+    && a
+      .bytes()
+      .zip(b.bytes())
+      .fold(0, |acc, (x, y)| acc | (x ^ y))
+      == 0
+}
+
 pub trait HashService {
   fn hash_pw(&self, password: &str) -> Result<String, Rejection>;
 }
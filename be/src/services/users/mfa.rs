@@ -0,0 +1,459 @@
+use crate::dto::{
+  MfaChallengeResponse, MfaEnrolmentResponse, MfaLogInRequest, MfaRecoveryCodesResponse,
+};
+use crate::errors::{AuthenticationError, ServerError, ValidationError};
+use crate::model::{MfaChallenge, User, UserMfa};
+use crate::repositories::{MfaChallengeRepository, UsersRepository};
+use crate::services::users::hash::constant_time_eq;
+use crate::services::{ConfigService, HashService};
+use async_trait::async_trait;
+use base32::Alphabet;
+use chrono::{Duration, Utc};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use pwhash::bcrypt;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::sync::Arc;
+use totp_lite::Sha1;
+use uuid::Uuid;
+use warp::Rejection;
+
+const TOTP_STEP: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_ALLOWED_SKEW: u64 = 1;
+const SECRET_ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
+
+#[async_trait]
+pub trait MfaService {
+  async fn start_enrolment(&self, user_id: &str) -> Result<MfaEnrolmentResponse, Rejection>;
+  async fn confirm_enrolment(
+    &self,
+    user_id: &str,
+    code: &str,
+  ) -> Result<MfaRecoveryCodesResponse, Rejection>;
+  async fn create_challenge(&self, user: &User) -> Result<MfaChallengeResponse, Rejection>;
+  async fn verify_challenge(&self, request: &MfaLogInRequest) -> Result<User, Rejection>;
+}
+
+pub struct MfaServiceImpl {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  hash_service: Arc<dyn HashService + Send + Sync>,
+  users_repository: Arc<dyn UsersRepository + Send + Sync>,
+  mfa_challenge_repository: Arc<dyn MfaChallengeRepository + Send + Sync>,
+}
+
+impl MfaServiceImpl {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    hash_service: Arc<dyn HashService + Send + Sync>,
+    users_repository: Arc<dyn UsersRepository + Send + Sync>,
+    mfa_challenge_repository: Arc<dyn MfaChallengeRepository + Send + Sync>,
+  ) -> MfaServiceImpl {
+    MfaServiceImpl {
+      config_service,
+      hash_service,
+      users_repository,
+      mfa_challenge_repository,
+    }
+  }
+
+  async fn load_user(&self, user_id: &str) -> Result<User, Rejection> {
+    match self.users_repository.find_by_id(user_id).await? {
+      Some(u) => Ok(u),
+      None => {
+        log::error!("Could not find User(id={}) for MFA operation", user_id);
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
+  fn create_secret(&self) -> String {
+    let secret: [u8; 20] = rand::thread_rng().gen();
+    base32::encode(SECRET_ALPHABET, &secret)
+  }
+
+  fn otpauth_uri(&self, login_id: &str, secret: &str) -> String {
+    let issuer = self.config_service.get_config().mfa.issuer;
+    let issuer = utf8_percent_encode(&issuer, NON_ALPHANUMERIC).to_string();
+    format!(
+      "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+      issuer,
+      utf8_percent_encode(login_id, NON_ALPHANUMERIC),
+      secret,
+      issuer,
+      TOTP_DIGITS,
+      TOTP_STEP
+    )
+  }
+
+  /// Returns the time step the code is valid for, if it is valid and newer than the last code
+  /// accepted for this user.
+  fn check_code(&self, mfa: &UserMfa, code: &str) -> Option<i64> {
+    MfaServiceImpl::check_code_at(mfa, code, Utc::now().timestamp() as u64)
+  }
+
+  fn check_code_at(mfa: &UserMfa, code: &str, now: u64) -> Option<i64> {
+    let secret = match base32::decode(SECRET_ALPHABET, &mfa.secret) {
+      Some(s) => s,
+      None => {
+        log::error!("Stored MFA secret could not be decoded");
+        return None;
+      }
+    };
+
+    (0..=2 * TOTP_ALLOWED_SKEW)
+      .map(|i| now + i * TOTP_STEP - TOTP_ALLOWED_SKEW * TOTP_STEP)
+      .filter(|t| (t / TOTP_STEP) as i64 > mfa.last_used_step.unwrap_or(-1))
+      .find(|t| {
+        constant_time_eq(
+          &totp_lite::totp_custom::<Sha1>(TOTP_STEP, TOTP_DIGITS, &secret, *t),
+          code,
+        )
+      })
+      .map(|t| (t / TOTP_STEP) as i64)
+  }
+
+  fn create_recovery_codes(&self) -> Vec<String> {
+    let config = self.config_service.get_config().mfa;
+    (0..config.recovery_code_count)
+      .map(|_| {
+        rand::thread_rng()
+          .sample_iter(&Alphanumeric)
+          .take(config.recovery_code_length)
+          .collect()
+      })
+      .collect()
+  }
+
+  /// Checks a TOTP or recovery code. Accepting either is recorded with a conditional update, so a
+  /// code raced through concurrent requests is only accepted once.
+  async fn verify(
+    &self,
+    user: &User,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+  ) -> Result<bool, Rejection> {
+    let mfa = match user.mfa.as_ref() {
+      Some(m) if m.enabled => m,
+      _ => return Ok(false),
+    };
+
+    if let Some(c) = code {
+      return match self.check_code(mfa, c) {
+        Some(step) => self.users_repository.claim_mfa_step(&user.id, step).await,
+        None => Ok(false),
+      };
+    }
+
+    if let Some(c) = recovery_code {
+      if let Some(h) = mfa.recovery_codes.iter().find(|h| bcrypt::verify(c, h)) {
+        let removed = self
+          .users_repository
+          .remove_recovery_code(&user.id, h)
+          .await?;
+        if removed {
+          log::info!("MFA recovery code used for User(id={})", user.id);
+        }
+        return Ok(removed);
+      }
+    }
+
+    Ok(false)
+  }
+}
+
+#[async_trait]
+impl MfaService for MfaServiceImpl {
+  async fn start_enrolment(&self, user_id: &str) -> Result<MfaEnrolmentResponse, Rejection> {
+    let mut user = self.load_user(user_id).await?;
+    if user.mfa.as_ref().map(|m| m.enabled).unwrap_or(false) {
+      log::debug!("User(id={}) attempted to re-enrol in MFA", user_id);
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+
+    let secret = self.create_secret();
+    user.mfa = Some(UserMfa {
+      secret: secret.clone(),
+      enabled: false,
+      recovery_codes: Vec::new(),
+      last_used_step: None,
+    });
+    self.users_repository.save(&user).await?;
+
+    log::info!("MFA enrolment started for User(id={})", user_id);
+    Ok(MfaEnrolmentResponse {
+      otpauth_uri: self.otpauth_uri(&user.login_id, &secret),
+      secret,
+    })
+  }
+
+  async fn confirm_enrolment(
+    &self,
+    user_id: &str,
+    code: &str,
+  ) -> Result<MfaRecoveryCodesResponse, Rejection> {
+    let mut user = self.load_user(user_id).await?;
+    let mfa = match user.mfa.as_mut() {
+      Some(m) if !m.enabled => m,
+      _ => {
+        log::debug!(
+          "User(id={}) attempted to confirm MFA without a pending enrolment",
+          user_id
+        );
+        return Err(warp::reject::custom(ValidationError::new()));
+      }
+    };
+
+    let step = match self.check_code(mfa, code) {
+      Some(s) => s,
+      None => {
+        log::debug!("Invalid MFA confirmation code for User(id={})", user_id);
+        return Err(warp::reject::custom(ValidationError::new()));
+      }
+    };
+
+    let recovery_codes = self.create_recovery_codes();
+    let mut hashed = Vec::new();
+    for c in &recovery_codes {
+      hashed.push(self.hash_service.hash_pw(c)?);
+    }
+    mfa.enabled = true;
+    mfa.recovery_codes = hashed;
+    mfa.last_used_step = Some(step);
+    self.users_repository.save(&user).await?;
+
+    log::info!("MFA enabled for User(id={})", user_id);
+    Ok(MfaRecoveryCodesResponse { recovery_codes })
+  }
+
+  async fn create_challenge(&self, user: &User) -> Result<MfaChallengeResponse, Rejection> {
+    let created_at = Utc::now();
+    let expire_at =
+      created_at + Duration::seconds(self.config_service.get_config().mfa.challenge_lifetime_sec);
+    let challenge = MfaChallenge {
+      id: Uuid::new_v4().to_hyphenated().to_string(),
+      user_id: user.id.clone(),
+      created_at: bson::DateTime::from(created_at),
+      expire_at: bson::DateTime::from(expire_at),
+      attempts: 0,
+      completed: false,
+    };
+
+    self.mfa_challenge_repository.save(&challenge).await?;
+
+    Ok(MfaChallengeResponse {
+      challenge_id: challenge.id,
+    })
+  }
+
+  async fn verify_challenge(&self, request: &MfaLogInRequest) -> Result<User, Rejection> {
+    let max_attempts = self.config_service.get_config().mfa.max_attempts;
+    let challenge = self
+      .mfa_challenge_repository
+      .record_attempt(&request.challenge_id, max_attempts)
+      .await?;
+    let challenge = match challenge {
+      Some(c) => c,
+      None => {
+        log::debug!(
+          "Could not locate MFA challenge {} with attempts remaining",
+          request.challenge_id
+        );
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    };
+
+    let user = self.load_user(&challenge.user_id).await?;
+    let verified = self
+      .verify(
+        &user,
+        request.code.as_deref(),
+        request.recovery_code.as_deref(),
+      )
+      .await?;
+    if !verified {
+      log::debug!("Invalid MFA code for challenge {}", challenge.id);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    if !self.mfa_challenge_repository.complete(&challenge.id).await? {
+      log::debug!("MFA challenge {} was already completed", challenge.id);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    Ok(user)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::user::USER_TYPE_ADMIN;
+  use crate::testing::{
+    config, MemoryMfaChallengeRepository, MemoryUsersRepository, PlainHashService,
+    StubConfigService,
+  };
+
+  const USER_ID: &str = "user";
+  // The RFC 6238 SHA-1 test secret, "12345678901234567890".
+  const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+  fn mfa(last_used_step: Option<i64>) -> UserMfa {
+    UserMfa {
+      secret: String::from(RFC_SECRET),
+      enabled: true,
+      recovery_codes: vec![bcrypt::hash("recovery-1").unwrap()],
+      last_used_step,
+    }
+  }
+
+  fn current_code() -> String {
+    let secret = base32::decode(SECRET_ALPHABET, RFC_SECRET).unwrap();
+    let now = Utc::now().timestamp() as u64;
+    totp_lite::totp_custom::<Sha1>(TOTP_STEP, TOTP_DIGITS, &secret, now)
+  }
+
+  struct Fixture {
+    users: Arc<MemoryUsersRepository>,
+    challenges: Arc<MemoryMfaChallengeRepository>,
+    service: MfaServiceImpl,
+  }
+
+  impl Fixture {
+    async fn new(mfa: Option<UserMfa>) -> Fixture {
+      let users = Arc::new(MemoryUsersRepository::default());
+      users
+        .save(&User {
+          id: String::from(USER_ID),
+          user_type: String::from(USER_TYPE_ADMIN),
+          company_id: None,
+          login_id: String::from("user@example.com"),
+          password: String::from("hashed:password"),
+          role_ids: Vec::new(),
+          mfa,
+          external_identity: None,
+          disabled: false,
+        })
+        .await
+        .unwrap();
+      let challenges = Arc::new(MemoryMfaChallengeRepository::default());
+      let service = MfaServiceImpl::new(
+        Arc::new(StubConfigService { config: config() }),
+        Arc::new(PlainHashService {}),
+        Arc::clone(&users) as Arc<dyn UsersRepository + Send + Sync>,
+        Arc::clone(&challenges) as Arc<dyn MfaChallengeRepository + Send + Sync>,
+      );
+      Fixture {
+        users,
+        challenges,
+        service,
+      }
+    }
+
+    async fn challenge(&self) -> String {
+      let user = self.users.get(USER_ID);
+      self.service.create_challenge(&user).await.unwrap().challenge_id
+    }
+
+    async fn answer(
+      &self,
+      challenge_id: &str,
+      code: Option<&str>,
+      recovery_code: Option<&str>,
+    ) -> bool {
+      let request = MfaLogInRequest {
+        challenge_id: String::from(challenge_id),
+        code: code.map(String::from),
+        recovery_code: recovery_code.map(String::from),
+      };
+      self.service.verify_challenge(&request).await.is_ok()
+    }
+  }
+
+  #[test]
+  fn codes_match_the_rfc_test_vector_within_the_allowed_skew() {
+    // At T=59 the 8-digit RFC code is 94287082, in time step 1.
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(None), "287082", 59), Some(1));
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(None), "287082", 89), Some(1));
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(None), "287082", 119), None);
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(None), "287083", 59), None);
+  }
+
+  #[test]
+  fn codes_from_used_time_steps_are_rejected() {
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(Some(1)), "287082", 59), None);
+    assert_eq!(MfaServiceImpl::check_code_at(&mfa(Some(0)), "287082", 59), Some(1));
+  }
+
+  #[tokio::test]
+  async fn enrolment_is_confirmed_with_a_code_from_the_issued_secret() {
+    let f = Fixture::new(None).await;
+    let enrolment = f.service.start_enrolment(USER_ID).await.unwrap();
+    assert!(enrolment
+      .otpauth_uri
+      .contains(&format!("secret={}", enrolment.secret)));
+    assert!(f.service.confirm_enrolment(USER_ID, "000000x").await.is_err());
+
+    let secret = base32::decode(SECRET_ALPHABET, &enrolment.secret).unwrap();
+    let code = totp_lite::totp_custom::<Sha1>(
+      TOTP_STEP,
+      TOTP_DIGITS,
+      &secret,
+      Utc::now().timestamp() as u64,
+    );
+    let res = f.service.confirm_enrolment(USER_ID, &code).await.unwrap();
+
+    let mfa = f.users.get(USER_ID).mfa.unwrap();
+    assert!(mfa.enabled);
+    assert_eq!(res.recovery_codes.len(), config().mfa.recovery_code_count);
+    assert_eq!(mfa.recovery_codes.len(), res.recovery_codes.len());
+    assert!(f.service.start_enrolment(USER_ID).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn a_code_completes_only_one_challenge() {
+    let f = Fixture::new(Some(mfa(None))).await;
+    let code = current_code();
+    let first = f.challenge().await;
+    let second = f.challenge().await;
+
+    assert!(f.answer(&first, Some(&code), None).await);
+    assert!(!f.answer(&first, Some(&code), None).await);
+    assert!(!f.answer(&second, Some(&code), None).await);
+  }
+
+  #[tokio::test]
+  async fn recovery_codes_are_consumed() {
+    let f = Fixture::new(Some(mfa(None))).await;
+    let first = f.challenge().await;
+    let second = f.challenge().await;
+
+    assert!(!f.answer(&first, None, Some("recovery-2")).await);
+    assert!(f.answer(&first, None, Some("recovery-1")).await);
+    assert!(f.users.get(USER_ID).mfa.unwrap().recovery_codes.is_empty());
+    assert!(!f.answer(&second, None, Some("recovery-1")).await);
+  }
+
+  #[tokio::test]
+  async fn challenges_are_locked_after_the_attempt_limit() {
+    let f = Fixture::new(Some(mfa(None))).await;
+    let challenge = f.challenge().await;
+    for _ in 0..config().mfa.max_attempts {
+      assert!(!f.answer(&challenge, Some("000000x"), None).await);
+    }
+
+    assert!(!f.answer(&challenge, Some(&current_code()), None).await);
+    let challenges = f.challenges.challenges.lock().unwrap();
+    assert_eq!(challenges[0].attempts, config().mfa.max_attempts);
+  }
+
+  #[tokio::test]
+  async fn expired_challenges_are_rejected() {
+    let f = Fixture::new(Some(mfa(None))).await;
+    let challenge = f.challenge().await;
+    f.challenges.challenges.lock().unwrap()[0].expire_at =
+      bson::DateTime::from(Utc::now() - Duration::seconds(1));
+
+    assert!(!f.answer(&challenge, Some(&current_code()), None).await);
+  }
+}
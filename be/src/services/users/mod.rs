@@ -3,9 +3,15 @@ pub mod session;
 pub mod hash;
 pub mod token;
 pub mod roles;
+pub mod mfa;
+pub mod api_key;
+pub mod oidc;
 
 pub use service::UsersServiceImpl;
 pub use session::SessionServiceImpl;
 pub use hash::HashServiceImpl;
 pub use token::TokenServiceImpl;
 pub use roles::RolesServiceImpl;
+pub use mfa::MfaServiceImpl;
+pub use api_key::ApiKeyServiceImpl;
+pub use oidc::OidcServiceImpl;
@@ -0,0 +1,881 @@
+use crate::domain::OidcConfig;
+use crate::dto::{LogInResult, OidcAuthorizationResponse, OidcCallbackRequest};
+use crate::errors::{AuthenticationError, ServerError};
+use crate::model::user::USER_TYPE_ADMIN;
+use crate::model::{OidcLogin, User, UserExternalIdentity};
+use crate::repositories::{OidcLoginRepository, UsersRepository};
+use crate::services::{ConfigService, SessionService};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration as StdDuration, Instant};
+use uuid::Uuid;
+use warp::Rejection;
+
+const DISCOVERY_PATH: &str = ".well-known/openid-configuration";
+const STATE_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 32;
+const CODE_VERIFIER_LENGTH: usize = 64;
+/// An ID token signed with an unknown key only refetches the JWKS this long after the last fetch,
+/// so forged tokens cannot make every request call the identity provider.
+const JWKS_REFETCH_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+#[derive(Deserialize, Clone)]
+struct ProviderMetadata {
+  issuer: String,
+  authorization_endpoint: String,
+  token_endpoint: String,
+  jwks_uri: String,
+}
+
+#[derive(Deserialize, Clone)]
+struct Jwk {
+  kid: Option<String>,
+  kty: String,
+  n: Option<String>,
+  e: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+struct Jwks {
+  keys: Vec<Jwk>,
+}
+
+#[derive(Clone)]
+struct CachedJwks {
+  jwks: Jwks,
+  fetched_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+  id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+  iss: String,
+  sub: String,
+  nonce: Option<String>,
+  email: Option<String>,
+  email_verified: Option<bool>,
+  preferred_username: Option<String>,
+}
+
+#[async_trait]
+pub trait OidcService {
+  async fn authorize(&self) -> Result<OidcAuthorizationResponse, Rejection>;
+  async fn callback(&self, request: &OidcCallbackRequest) -> Result<LogInResult, Rejection>;
+}
+
+pub struct OidcServiceImpl {
+  config_service: Arc<dyn ConfigService + Send + Sync>,
+  session_service: Arc<dyn SessionService + Send + Sync>,
+  users_repository: Arc<dyn UsersRepository + Send + Sync>,
+  oidc_login_repository: Arc<dyn OidcLoginRepository + Send + Sync>,
+  client: Client,
+  metadata: RwLock<Option<ProviderMetadata>>,
+  jwks: RwLock<Option<CachedJwks>>,
+}
+
+impl OidcServiceImpl {
+  pub fn new(
+    config_service: Arc<dyn ConfigService + Send + Sync>,
+    session_service: Arc<dyn SessionService + Send + Sync>,
+    users_repository: Arc<dyn UsersRepository + Send + Sync>,
+    oidc_login_repository: Arc<dyn OidcLoginRepository + Send + Sync>,
+  ) -> OidcServiceImpl {
+    OidcServiceImpl {
+      config_service,
+      session_service,
+      users_repository,
+      oidc_login_repository,
+      client: Client::new(),
+      metadata: RwLock::new(None),
+      jwks: RwLock::new(None),
+    }
+  }
+
+  fn oidc_config(&self) -> Result<OidcConfig, Rejection> {
+    match self.config_service.get_config().oidc {
+      Some(c) => Ok(c),
+      None => {
+        log::debug!("OIDC login attempted, but no identity provider is configured");
+        Err(warp::reject::not_found())
+      }
+    }
+  }
+
+  fn random_string(length: usize) -> String {
+    rand::thread_rng()
+      .sample_iter(&Alphanumeric)
+      .take(length)
+      .collect()
+  }
+
+  fn code_challenge(code_verifier: &str) -> String {
+    base64::encode_config(
+      Sha256::digest(code_verifier.as_bytes()),
+      base64::URL_SAFE_NO_PAD,
+    )
+  }
+
+  async fn fetch<T>(&self, url: &str) -> Result<T, Rejection>
+  where
+    T: DeserializeOwned,
+  {
+    let res = self
+      .client
+      .get(url)
+      .send()
+      .await
+      .and_then(|r| r.error_for_status());
+    let res = match res {
+      Ok(r) => r.json().await,
+      Err(e) => Err(e),
+    };
+
+    res.map_err(|e| {
+      log::error!("Error fetching {} from identity provider {:?}", url, e);
+      warp::reject::custom(ServerError::new())
+    })
+  }
+
+  async fn metadata(&self, config: &OidcConfig) -> Result<ProviderMetadata, Rejection> {
+    if let Some(m) = self.read_cache(&self.metadata)? {
+      return Ok(m);
+    }
+
+    let url = format!("{}/{}", config.issuer.trim_end_matches('/'), DISCOVERY_PATH);
+    let metadata: ProviderMetadata = self.fetch(&url).await?;
+    if metadata.issuer != config.issuer {
+      log::error!(
+        "Identity provider reported issuer {}, but {} is configured",
+        metadata.issuer,
+        config.issuer
+      );
+      return Err(warp::reject::custom(ServerError::new()));
+    }
+
+    self.write_cache(&self.metadata, metadata.clone())?;
+    Ok(metadata)
+  }
+
+  async fn signing_key(
+    &self,
+    metadata: &ProviderMetadata,
+    kid: Option<&str>,
+  ) -> Result<Jwk, Rejection> {
+    let find = |jwks: &Jwks| {
+      jwks
+        .keys
+        .iter()
+        .find(|k| k.kty == "RSA" && (kid.is_none() || k.kid.as_deref() == kid))
+        .cloned()
+    };
+
+    if let Some(cached) = self.read_cache(&self.jwks)? {
+      if let Some(k) = find(&cached.jwks) {
+        return Ok(k);
+      }
+      if cached.fetched_at.elapsed() < JWKS_REFETCH_INTERVAL {
+        log::warn!(
+          "No signing key found for ID token with kid={:?}, and the JWKS was recently fetched",
+          kid
+        );
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    }
+
+    let jwks: Jwks = self.fetch(&metadata.jwks_uri).await?;
+    let key = find(&jwks);
+    self.write_cache(
+      &self.jwks,
+      CachedJwks {
+        jwks,
+        fetched_at: Instant::now(),
+      },
+    )?;
+
+    match key {
+      Some(k) => Ok(k),
+      None => {
+        log::warn!("No signing key found for ID token with kid={:?}", kid);
+        Err(warp::reject::custom(AuthenticationError::new()))
+      }
+    }
+  }
+
+  fn read_cache<T>(&self, cache: &RwLock<Option<T>>) -> Result<Option<T>, Rejection>
+  where
+    T: Clone,
+  {
+    match cache.read() {
+      Ok(c) => Ok(c.clone()),
+      Err(e) => {
+        log::error!("Error attempting to obtain read lock on OIDC cache {:?}", e);
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
+  fn write_cache<T>(&self, cache: &RwLock<Option<T>>, value: T) -> Result<(), Rejection> {
+    match cache.write() {
+      Ok(mut c) => {
+        *c = Some(value);
+        Ok(())
+      }
+      Err(e) => {
+        log::error!(
+          "Error attempting to obtain write lock on OIDC cache {:?}",
+          e
+        );
+        Err(warp::reject::custom(ServerError::new()))
+      }
+    }
+  }
+
+  async fn exchange_code(
+    &self,
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    code: &str,
+    code_verifier: &str,
+  ) -> Result<TokenResponse, Rejection> {
+    let res = self
+      .client
+      .post(&metadata.token_endpoint)
+      .form(&[
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", &config.redirect_uri),
+        ("client_id", &config.client_id),
+        ("client_secret", &config.client_secret),
+        ("code_verifier", code_verifier),
+      ])
+      .send()
+      .await
+      .and_then(|r| r.error_for_status());
+    let res = match res {
+      Ok(r) => r.json().await,
+      Err(e) => Err(e),
+    };
+
+    res.map_err(|e| {
+      log::warn!("Authorization code exchange failed {:?}", e);
+      warp::reject::custom(AuthenticationError::new())
+    })
+  }
+
+  async fn validate_id_token(
+    &self,
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+    nonce: &str,
+  ) -> Result<IdTokenClaims, Rejection> {
+    let header = jsonwebtoken::decode_header(id_token).map_err(|e| {
+      log::warn!("Malformed ID token {:?}", e);
+      warp::reject::custom(AuthenticationError::new())
+    })?;
+    if header.alg != Algorithm::RS256 {
+      log::warn!("Unsupported ID token algorithm {:?}", header.alg);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    let jwk = self.signing_key(metadata, header.kid.as_deref()).await?;
+    let (n, e) = match (&jwk.n, &jwk.e) {
+      (Some(n), Some(e)) => (n, e),
+      _ => {
+        log::error!("Identity provider signing key is missing RSA components");
+        return Err(warp::reject::custom(ServerError::new()));
+      }
+    };
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.iss = Some(metadata.issuer.clone());
+    validation.set_audience(&[&config.client_id]);
+    let claims: IdTokenClaims = match jsonwebtoken::decode(
+      id_token,
+      &DecodingKey::from_rsa_components(n, e),
+      &validation,
+    ) {
+      Ok(t) => t.claims,
+      Err(e) => {
+        log::warn!("ID token failed validation {:?}", e);
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    };
+
+    if claims.nonce.as_deref() != Some(nonce) {
+      log::warn!("ID token nonce does not match login request");
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    Ok(claims)
+  }
+
+  async fn resolve_user(
+    &self,
+    config: &OidcConfig,
+    claims: IdTokenClaims,
+  ) -> Result<User, Rejection> {
+    let identity = UserExternalIdentity {
+      issuer: claims.iss,
+      subject: claims.sub,
+    };
+    if let Some(u) = self
+      .users_repository
+      .find_by_external_identity(&identity)
+      .await?
+    {
+      return Ok(u);
+    }
+
+    if claims.email.is_some() && claims.email_verified != Some(true) {
+      log::warn!(
+        "Refusing to provision external subject {} with an unverified email",
+        identity.subject
+      );
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    let login_id = claims
+      .email
+      .or(claims.preferred_username)
+      .unwrap_or_else(|| identity.subject.clone());
+    if let Some(u) = self.users_repository.find_by_login_id(&login_id).await? {
+      log::warn!(
+        "Refusing to provision external subject {} as loginId={}, which belongs to User(id={})",
+        identity.subject,
+        login_id,
+        u.id
+      );
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    log::info!(
+      "Provisioning user for external subject {} with loginId={}",
+      identity.subject,
+      login_id
+    );
+    let user = User {
+      id: Uuid::new_v4().to_hyphenated().to_string(),
+      user_type: String::from(USER_TYPE_ADMIN),
+      company_id: None,
+      login_id,
+      password: String::new(),
+      role_ids: vec![config.default_role_id.clone()],
+      mfa: None,
+      external_identity: Some(identity),
+      disabled: false,
+    };
+    self.users_repository.save(&user).await?;
+
+    log::info!(
+      "User successfully provisioned with id={}, loginId={}",
+      user.id,
+      user.login_id
+    );
+    Ok(user)
+  }
+}
+
+#[async_trait]
+impl OidcService for OidcServiceImpl {
+  async fn authorize(&self) -> Result<OidcAuthorizationResponse, Rejection> {
+    let config = self.oidc_config()?;
+    let metadata = self.metadata(&config).await?;
+
+    let created_at = Utc::now();
+    let login = OidcLogin {
+      state: OidcServiceImpl::random_string(STATE_LENGTH),
+      nonce: OidcServiceImpl::random_string(NONCE_LENGTH),
+      code_verifier: OidcServiceImpl::random_string(CODE_VERIFIER_LENGTH),
+      created_at: bson::DateTime::from(created_at),
+      expire_at: bson::DateTime::from(created_at + Duration::seconds(config.login_lifetime_sec)),
+      completed: false,
+    };
+
+    let url = Url::parse_with_params(
+      &metadata.authorization_endpoint,
+      &[
+        ("response_type", "code"),
+        ("client_id", &config.client_id),
+        ("redirect_uri", &config.redirect_uri),
+        ("scope", &config.scopes.join(" ")),
+        ("state", &login.state),
+        ("nonce", &login.nonce),
+        (
+          "code_challenge",
+          &OidcServiceImpl::code_challenge(&login.code_verifier),
+        ),
+        ("code_challenge_method", "S256"),
+      ],
+    );
+    let url = match url {
+      Ok(u) => u,
+      Err(e) => {
+        log::error!(
+          "Identity provider authorization endpoint is invalid {:?}",
+          e
+        );
+        return Err(warp::reject::custom(ServerError::new()));
+      }
+    };
+
+    self.oidc_login_repository.save(&login).await?;
+
+    Ok(OidcAuthorizationResponse {
+      authorization_url: url.into_string(),
+      state: login.state,
+    })
+  }
+
+  async fn callback(&self, request: &OidcCallbackRequest) -> Result<LogInResult, Rejection> {
+    let config = self.oidc_config()?;
+    let login = self
+      .oidc_login_repository
+      .find_active_by_state(&request.state)
+      .await?;
+    let mut login = match login {
+      Some(l) => l,
+      None => {
+        log::debug!("Could not locate OIDC login for state {}", request.state);
+        return Err(warp::reject::custom(AuthenticationError::new()));
+      }
+    };
+
+    login.completed = true;
+    self.oidc_login_repository.save(&login).await?;
+
+    let metadata = self.metadata(&config).await?;
+    let tokens = self
+      .exchange_code(&config, &metadata, &request.code, &login.code_verifier)
+      .await?;
+    let claims = self
+      .validate_id_token(&config, &metadata, &tokens.id_token, &login.nonce)
+      .await?;
+    let user = self.resolve_user(&config, claims).await?;
+
+    self.session_service.create_session_for_user(&user).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dto::{LogInRequest, LogInResponse, MfaLogInRequest, RefreshRequest, RefreshResponse};
+  use crate::testing::{
+    config, MemoryOidcLoginRepository, MemoryUsersRepository, StubConfigService,
+  };
+  use jsonwebtoken::{EncodingKey, Header};
+  use serde_json::{json, Value};
+  use std::collections::HashMap;
+  use std::sync::Mutex;
+  use warp::Filter;
+
+  const CLIENT_ID: &str = "ausawards";
+  const DEFAULT_ROLE_ID: &str = "role-default";
+  const GOOD_CODE: &str = "good-code";
+  const SIGNING_KEY: &str = include_str!("../../../resources/test/oidc_signing_key.pem");
+  const JWKS: &str = include_str!("../../../resources/test/oidc_jwks.json");
+
+  #[derive(Default)]
+  struct StubState {
+    issuer: String,
+    reported_issuer: Option<String>,
+    claims: Value,
+    kid: Option<String>,
+    jwks_requests: usize,
+    token_requests: Vec<HashMap<String, String>>,
+  }
+
+  /// A local identity provider serving discovery, JWKS and a token endpoint that returns an ID
+  /// token built from `claims`.
+  async fn start_idp() -> Arc<Mutex<StubState>> {
+    let state = Arc::new(Mutex::new(StubState::default()));
+
+    let s = Arc::clone(&state);
+    let discovery = warp::path!(".well-known" / "openid-configuration").map(move || {
+      let state = s.lock().unwrap();
+      let issuer = state
+        .reported_issuer
+        .clone()
+        .unwrap_or_else(|| state.issuer.clone());
+      warp::reply::json(&json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", state.issuer),
+        "token_endpoint": format!("{}/token", state.issuer),
+        "jwks_uri": format!("{}/jwks", state.issuer),
+      }))
+    });
+    let s = Arc::clone(&state);
+    let jwks = warp::path!("jwks").map(move || {
+      s.lock().unwrap().jwks_requests += 1;
+      warp::reply::json(&serde_json::from_str::<Value>(JWKS).unwrap())
+    });
+    let s = Arc::clone(&state);
+    let token = warp::path!("token")
+      .and(warp::post())
+      .and(warp::body::form())
+      .map(move |form: HashMap<String, String>| {
+        let mut state = s.lock().unwrap();
+        let accepted = form.get("code").map(|c| c.as_str()) == Some(GOOD_CODE);
+        state.token_requests.push(form);
+        if !accepted {
+          return warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "invalid_grant" })),
+            warp::http::StatusCode::BAD_REQUEST,
+          );
+        }
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(
+          state
+            .kid
+            .clone()
+            .unwrap_or_else(|| String::from("test-key")),
+        );
+        let key = EncodingKey::from_rsa_pem(SIGNING_KEY.as_bytes()).unwrap();
+        let id_token = jsonwebtoken::encode(&header, &state.claims, &key).unwrap();
+        warp::reply::with_status(
+          warp::reply::json(&json!({ "id_token": id_token, "token_type": "Bearer" })),
+          warp::http::StatusCode::OK,
+        )
+      });
+
+    let (addr, server) =
+      warp::serve(discovery.or(jwks).or(token)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    state.lock().unwrap().issuer = format!("http://{}", addr);
+    state
+  }
+
+  struct StubSessionService {}
+
+  #[async_trait]
+  impl SessionService for StubSessionService {
+    async fn create_session(&self, _request: &LogInRequest) -> Result<LogInResult, Rejection> {
+      Err(warp::reject())
+    }
+
+    async fn complete_mfa(&self, _request: &MfaLogInRequest) -> Result<LogInResponse, Rejection> {
+      Err(warp::reject())
+    }
+
+    /// Issues the user's id as the token, so tests can tell who was logged in.
+    async fn create_session_for_user(&self, user: &User) -> Result<LogInResult, Rejection> {
+      Ok(LogInResult::Success(LogInResponse {
+        session_id: String::from("session"),
+        session_secret: String::from("secret"),
+        token: user.id.clone(),
+      }))
+    }
+
+    async fn refresh_token(&self, _request: &RefreshRequest) -> Result<RefreshResponse, Rejection> {
+      Err(warp::reject())
+    }
+
+    async fn sign_out(&self, _session_id: &str) -> Result<(), Rejection> {
+      Err(warp::reject())
+    }
+  }
+
+  struct Fixture {
+    idp: Arc<Mutex<StubState>>,
+    users: Arc<MemoryUsersRepository>,
+    service: OidcServiceImpl,
+  }
+
+  impl Fixture {
+    async fn new() -> Fixture {
+      let idp = start_idp().await;
+      let mut config = config();
+      config.oidc = Some(OidcConfig {
+        issuer: idp.lock().unwrap().issuer.clone(),
+        client_id: String::from(CLIENT_ID),
+        client_secret: String::from("client-secret"),
+        redirect_uri: String::from("http://localhost:3000/oidc/callback"),
+        scopes: vec![String::from("openid"), String::from("email")],
+        default_role_id: String::from(DEFAULT_ROLE_ID),
+        login_lifetime_sec: 300,
+      });
+
+      let users = Arc::new(MemoryUsersRepository::default());
+      let service = OidcServiceImpl::new(
+        Arc::new(StubConfigService { config }),
+        Arc::new(StubSessionService {}),
+        Arc::clone(&users) as Arc<dyn UsersRepository + Send + Sync>,
+        Arc::new(MemoryOidcLoginRepository::default()),
+      );
+      Fixture {
+        idp,
+        users,
+        service,
+      }
+    }
+
+    /// Starts a login and has the IdP issue an ID token for `sub` with the login's nonce.
+    async fn authorize(&self, sub: &str, extra_claims: Value) -> HashMap<String, String> {
+      let res = self.service.authorize().await.ok().unwrap();
+      let params: HashMap<String, String> = Url::parse(&res.authorization_url)
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+
+      let mut state = self.idp.lock().unwrap();
+      let mut claims = json!({
+        "iss": state.issuer,
+        "sub": sub,
+        "aud": CLIENT_ID,
+        "exp": Utc::now().timestamp() + 300,
+        "iat": Utc::now().timestamp(),
+        "nonce": params["nonce"],
+      });
+      for (k, v) in extra_claims.as_object().unwrap() {
+        claims[k] = v.clone();
+      }
+      state.claims = claims;
+      params
+    }
+
+    /// Completes the login, returning the id of the user a session was started for.
+    async fn callback(&self, state: &str, code: &str) -> Result<String, Rejection> {
+      let res = self
+        .service
+        .callback(&OidcCallbackRequest {
+          code: String::from(code),
+          state: String::from(state),
+        })
+        .await?;
+      match res {
+        LogInResult::Success(s) => Ok(s.token),
+        LogInResult::MfaRequired(_) => panic!("the stub session service never issues challenges"),
+      }
+    }
+
+    fn local_user(&self, login_id: &str) {
+      self.users.users.lock().unwrap().push(User {
+        id: String::from("local-admin"),
+        user_type: String::from(USER_TYPE_ADMIN),
+        company_id: None,
+        login_id: String::from(login_id),
+        password: String::from("hash"),
+        role_ids: vec![String::from("SUPER_ADMIN")],
+        mfa: None,
+        external_identity: None,
+        disabled: false,
+      });
+    }
+  }
+
+  fn is_authentication_error(result: Result<String, Rejection>) -> bool {
+    match result {
+      Ok(_) => false,
+      Err(e) => e.find::<AuthenticationError>().is_some(),
+    }
+  }
+
+  #[tokio::test]
+  async fn authorize_redirects_to_discovered_endpoint_with_pkce() {
+    let fixture = Fixture::new().await;
+    let res = fixture.service.authorize().await.ok().unwrap();
+
+    let issuer = fixture.idp.lock().unwrap().issuer.clone();
+    assert!(res
+      .authorization_url
+      .starts_with(&format!("{}/authorize?", issuer)));
+    let params: HashMap<String, String> = Url::parse(&res.authorization_url)
+      .unwrap()
+      .query_pairs()
+      .into_owned()
+      .collect();
+    assert_eq!(params["response_type"], "code");
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["state"], res.state);
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert!(!params["nonce"].is_empty());
+    assert!(!params["code_challenge"].is_empty());
+  }
+
+  #[tokio::test]
+  async fn authorize_rejects_provider_reporting_another_issuer() {
+    let fixture = Fixture::new().await;
+    fixture.idp.lock().unwrap().reported_issuer = Some(String::from("https://evil.example"));
+
+    let res = fixture.service.authorize().await;
+    assert!(res.err().unwrap().find::<ServerError>().is_some());
+  }
+
+  #[tokio::test]
+  async fn callback_exchanges_code_with_verifier_and_provisions_user() {
+    let fixture = Fixture::new().await;
+    let params = fixture
+      .authorize(
+        "subject-1",
+        json!({ "email": "jo@example.com", "email_verified": true }),
+      )
+      .await;
+
+    let res = fixture
+      .callback(&params["state"], GOOD_CODE)
+      .await
+      .ok()
+      .unwrap();
+
+    let requests = fixture.idp.lock().unwrap().token_requests.clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["grant_type"], "authorization_code");
+    assert_eq!(requests[0]["code"], GOOD_CODE);
+    assert_eq!(
+      OidcServiceImpl::code_challenge(&requests[0]["code_verifier"]),
+      params["code_challenge"]
+    );
+
+    let user = fixture
+      .users
+      .find_by_id(&res)
+      .await
+      .ok()
+      .unwrap()
+      .unwrap();
+    assert_eq!(user.login_id, "jo@example.com");
+    assert_eq!(user.role_ids, vec![String::from(DEFAULT_ROLE_ID)]);
+    let identity = user.external_identity.unwrap();
+    assert_eq!(identity.subject, "subject-1");
+    assert_eq!(identity.issuer, fixture.idp.lock().unwrap().issuer);
+  }
+
+  #[tokio::test]
+  async fn callback_reuses_user_for_known_identity() {
+    let fixture = Fixture::new().await;
+    let claims = json!({ "email": "jo@example.com", "email_verified": true });
+    let params = fixture.authorize("subject-1", claims.clone()).await;
+    let first = fixture
+      .callback(&params["state"], GOOD_CODE)
+      .await
+      .ok()
+      .unwrap();
+    let params = fixture.authorize("subject-1", claims).await;
+    let second = fixture
+      .callback(&params["state"], GOOD_CODE)
+      .await
+      .ok()
+      .unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(fixture.users.users.lock().unwrap().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_unknown_state() {
+    let fixture = Fixture::new().await;
+    fixture.authorize("subject-1", json!({})).await;
+
+    assert!(is_authentication_error(
+      fixture.callback("unknown-state", GOOD_CODE).await
+    ));
+    assert!(fixture.idp.lock().unwrap().token_requests.is_empty());
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_reused_state() {
+    let fixture = Fixture::new().await;
+    let params = fixture.authorize("subject-1", json!({})).await;
+    assert!(fixture.callback(&params["state"], GOOD_CODE).await.is_ok());
+
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], GOOD_CODE).await
+    ));
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_failed_code_exchange() {
+    let fixture = Fixture::new().await;
+    let params = fixture.authorize("subject-1", json!({})).await;
+
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], "bad-code").await
+    ));
+    assert!(fixture.users.users.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_mismatched_nonce() {
+    let fixture = Fixture::new().await;
+    let params = fixture
+      .authorize("subject-1", json!({ "nonce": "another-login" }))
+      .await;
+
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], GOOD_CODE).await
+    ));
+    assert!(fixture.users.users.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_unverified_email() {
+    let fixture = Fixture::new().await;
+    let params = fixture
+      .authorize(
+        "subject-1",
+        json!({ "email": "jo@example.com", "email_verified": false }),
+      )
+      .await;
+
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], GOOD_CODE).await
+    ));
+    assert!(fixture.users.users.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn callback_rejects_login_id_of_existing_local_user() {
+    let fixture = Fixture::new().await;
+    fixture.local_user("admin@example.com");
+    let params = fixture
+      .authorize(
+        "subject-1",
+        json!({ "email": "admin@example.com", "email_verified": true }),
+      )
+      .await;
+
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], GOOD_CODE).await
+    ));
+    assert_eq!(fixture.users.users.lock().unwrap().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn unknown_signing_keys_refetch_the_jwks_at_most_once_per_interval() {
+    let fixture = Fixture::new().await;
+    let params = fixture.authorize("subject-1", json!({})).await;
+    assert!(fixture.callback(&params["state"], GOOD_CODE).await.is_ok());
+    assert_eq!(fixture.idp.lock().unwrap().jwks_requests, 1);
+
+    fixture.idp.lock().unwrap().kid = Some(String::from("rotated-key"));
+    for _ in 0..2 {
+      let params = fixture.authorize("subject-1", json!({})).await;
+      assert!(is_authentication_error(
+        fixture.callback(&params["state"], GOOD_CODE).await
+      ));
+    }
+    assert_eq!(fixture.idp.lock().unwrap().jwks_requests, 1);
+
+    if let Some(c) = fixture.service.jwks.write().unwrap().as_mut() {
+      c.fetched_at -= JWKS_REFETCH_INTERVAL;
+    }
+    let params = fixture.authorize("subject-1", json!({})).await;
+    assert!(is_authentication_error(
+      fixture.callback(&params["state"], GOOD_CODE).await
+    ));
+    assert_eq!(fixture.idp.lock().unwrap().jwks_requests, 2);
+  }
+}
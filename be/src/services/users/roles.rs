@@ -1,27 +1,134 @@
+use crate::converters::RoleConverter;
+use crate::domain::is_valid_permission;
+use crate::dto::{CreateRoleRequest, RoleDto};
+use crate::errors::ValidationError;
 use crate::model::Role;
 use crate::repositories::RoleRepository;
 use async_trait::async_trait;
+use std::collections::HashSet;
 use std::sync::Arc;
+use uuid::Uuid;
 use warp::Rejection;
 
 #[async_trait]
 pub trait RolesService {
-  async fn get_roles_by_id(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection>;
+  async fn resolve_roles(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection>;
+  async fn list_roles(&self) -> Result<Vec<RoleDto>, Rejection>;
+  async fn create_role(&self, role: CreateRoleRequest) -> Result<RoleDto, Rejection>;
+  async fn role_exists(&self, role_id: &str) -> Result<bool, Rejection>;
 }
 
 pub struct RolesServiceImpl {
+  role_converter: Arc<dyn RoleConverter + Send + Sync>,
   role_repository: Arc<dyn RoleRepository + Send + Sync>,
 }
 
 impl RolesServiceImpl {
-  pub fn new(role_repository: Arc<dyn RoleRepository + Send + Sync>) -> RolesServiceImpl {
-    RolesServiceImpl { role_repository }
+  pub fn new(
+    role_converter: Arc<dyn RoleConverter + Send + Sync>,
+    role_repository: Arc<dyn RoleRepository + Send + Sync>,
+  ) -> RolesServiceImpl {
+    RolesServiceImpl {
+      role_converter,
+      role_repository,
+    }
   }
 }
 
 #[async_trait]
 impl RolesService for RolesServiceImpl {
-  async fn get_roles_by_id(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection> {
-    self.role_repository.find_by_ids(role_ids).await
+  async fn resolve_roles(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection> {
+    let mut visited: HashSet<String> = role_ids.iter().map(|i| String::from(*i)).collect();
+    let mut pending: Vec<String> = visited.iter().cloned().collect();
+    let mut roles = Vec::new();
+
+    while !pending.is_empty() {
+      let found = self
+        .role_repository
+        .find_by_ids(pending.iter().map(|i| i as &str).collect())
+        .await?;
+      pending = Vec::new();
+      for role in found {
+        for included in &role.included_role_ids {
+          if visited.insert(included.clone()) {
+            pending.push(included.clone());
+          }
+        }
+        roles.push(role);
+      }
+    }
+
+    Ok(roles)
+  }
+
+  async fn list_roles(&self) -> Result<Vec<RoleDto>, Rejection> {
+    let roles = self.role_repository.find_all().await?;
+    Ok(
+      roles
+        .iter()
+        .map(|r| self.role_converter.to_dto(r))
+        .collect(),
+    )
+  }
+
+  async fn create_role(&self, role: CreateRoleRequest) -> Result<RoleDto, Rejection> {
+    if role.name.is_empty() || role.permissions.is_empty() {
+      log::debug!("Attempted to create a role without a name or permissions");
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    if let Some(p) = role.permissions.iter().find(|p| !is_valid_permission(p)) {
+      log::debug!(
+        "Attempted to create Role(name={}) with malformed permission {}",
+        role.name,
+        p
+      );
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    if self
+      .role_repository
+      .find_by_name(&role.name)
+      .await?
+      .is_some()
+    {
+      log::debug!(
+        "Attempted to create Role(name={}), which already exists",
+        role.name
+      );
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+
+    let requested: HashSet<&str> = role.included_role_ids.iter().map(|i| i as &str).collect();
+    let included = self
+      .role_repository
+      .find_by_ids(requested.iter().cloned().collect())
+      .await?;
+    if included.len() != requested.len() {
+      log::debug!(
+        "Attempted to create Role(name={}) including unknown roles",
+        role.name
+      );
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+
+    log::info!("Creating role with name={}", role.name);
+    let role = Role {
+      id: Uuid::new_v4().to_hyphenated().to_string(),
+      name: role.name,
+      permissions: role.permissions,
+      included_role_ids: role.included_role_ids,
+    };
+    self.role_repository.save(&role).await?;
+
+    log::info!(
+      "Role successfully created with id={}, name={}",
+      role.id,
+      role.name
+    );
+    Ok(self.role_converter.to_dto(&role))
+  }
+
+  async fn role_exists(&self, role_id: &str) -> Result<bool, Rejection> {
+    let roles = self.role_repository.find_by_ids(vec![role_id]).await?;
+    Ok(!roles.is_empty())
   }
 }
@@ -1,10 +1,14 @@
 use crate::converters::UserConverter;
-use crate::dto::{CreateSystemAdminUserRequest, UserDto};
-use crate::model::user::USER_TYPE_ADMIN;
-use crate::model::User;
-use crate::repositories::UsersRepository;
-use crate::services::HashService;
+use crate::domain::UserAuth;
+use crate::dto::{CreateServiceUserRequest, CreateSystemAdminUserRequest, UserDto};
+use crate::errors::{AuthorizationError, ValidationError};
+use crate::model::session::EXPIRY_REASON_FORCE_EXPIRE;
+use crate::model::user::{USER_TYPE_ADMIN, USER_TYPE_SERVICE};
+use crate::model::{SessionExpiry, User};
+use crate::repositories::{UserSessionRepository, UsersRepository};
+use crate::services::{HashService, RolesService};
 use async_trait::async_trait;
+use chrono::Utc;
 use std::sync::Arc;
 use uuid::Uuid;
 use warp::Rejection;
@@ -13,39 +17,165 @@ use warp::Rejection;
 pub trait UsersService {
   async fn create_admin_user(
     &self,
+    caller: &UserAuth,
     user: CreateSystemAdminUserRequest,
   ) -> Result<UserDto, Rejection>;
+  async fn create_service_user(
+    &self,
+    caller: &UserAuth,
+    user: CreateServiceUserRequest,
+  ) -> Result<UserDto, Rejection>;
   async fn get_user(&self, user_id: &str) -> Result<Option<User>, Rejection>;
+  async fn get_user_dto(&self, user_id: &str) -> Result<UserDto, Rejection>;
   async fn get_user_by_login_id(&self, login_id: &str) -> Result<Option<User>, Rejection>;
+  async fn list_users(&self) -> Result<Vec<UserDto>, Rejection>;
+  async fn set_disabled(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    disabled: bool,
+  ) -> Result<UserDto, Rejection>;
+  async fn reset_password(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    password: &str,
+  ) -> Result<UserDto, Rejection>;
+  async fn grant_role(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    role_id: &str,
+  ) -> Result<UserDto, Rejection>;
+  async fn revoke_role(&self, user_id: &str, role_id: &str) -> Result<UserDto, Rejection>;
 }
 
 pub struct UsersServiceImpl {
   user_converter: Arc<dyn UserConverter + Send + Sync>,
   hash_service: Arc<dyn HashService + Send + Sync>,
+  roles_service: Arc<dyn RolesService + Send + Sync>,
   users_repository: Arc<dyn UsersRepository + Send + Sync>,
+  session_repository: Arc<dyn UserSessionRepository + Send + Sync>,
 }
 
 impl UsersServiceImpl {
   pub fn new(
     user_converter: Arc<dyn UserConverter + Send + Sync>,
     hash_service: Arc<dyn HashService + Send + Sync>,
+    roles_service: Arc<dyn RolesService + Send + Sync>,
     users_repository: Arc<dyn UsersRepository + Send + Sync>,
+    session_repository: Arc<dyn UserSessionRepository + Send + Sync>,
   ) -> UsersServiceImpl {
     UsersServiceImpl {
       user_converter,
       hash_service,
+      roles_service,
       users_repository,
+      session_repository,
+    }
+  }
+
+  async fn load_user(&self, user_id: &str) -> Result<User, Rejection> {
+    match self.users_repository.find_by_id(user_id).await? {
+      Some(u) => Ok(u),
+      None => {
+        log::warn!(
+          "Attempted to update User(id={}), but could not be found",
+          user_id
+        );
+        Err(warp::reject::not_found())
+      }
+    }
+  }
+
+  async fn update_user<F>(&self, user_id: &str, update: F) -> Result<UserDto, Rejection>
+  where
+    F: FnOnce(&mut User) -> Result<(), Rejection>,
+  {
+    let mut user = self.load_user(user_id).await?;
+    update(&mut user)?;
+    self.users_repository.save(&user).await?;
+
+    Ok(self.user_converter.to_dto(&user))
+  }
+
+  /// Fails unless the caller holds every permission of the roles, including included roles.
+  async fn ensure_caller_holds_roles(
+    &self,
+    caller: &UserAuth,
+    role_ids: Vec<&str>,
+  ) -> Result<(), Rejection> {
+    let roles = self.roles_service.resolve_roles(role_ids).await?;
+    let missing = roles
+      .iter()
+      .flat_map(|r| r.permissions.iter())
+      .find(|p| !caller.has_permission(p));
+    match missing {
+      Some(p) => {
+        log::warn!(
+          "User(id={}) does not hold permission {} of the roles involved",
+          caller.sub,
+          p
+        );
+        Err(warp::reject::custom(AuthorizationError::new()))
+      }
+      None => Ok(()),
     }
   }
+
+  /// Fails unless the login id is free and the roles exist and are within the caller's own.
+  async fn ensure_valid_new_user(
+    &self,
+    caller: &UserAuth,
+    login_id: &str,
+    role_ids: &[String],
+  ) -> Result<(), Rejection> {
+    if self
+      .users_repository
+      .find_by_login_id(login_id)
+      .await?
+      .is_some()
+    {
+      log::debug!("Attempted to create a user with existing loginId={}", login_id);
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    for role_id in role_ids {
+      if !self.roles_service.role_exists(role_id).await? {
+        log::debug!("Attempted to create a user with unknown Role(id={})", role_id);
+        return Err(warp::reject::custom(ValidationError::new()));
+      }
+    }
+    self
+      .ensure_caller_holds_roles(caller, role_ids.iter().map(|r| r as &str).collect())
+      .await
+  }
+
+  async fn expire_sessions(&self, caller: &UserAuth, user_id: &str) -> Result<(), Rejection> {
+    let expiry = SessionExpiry {
+      expired_by: caller.sub.clone(),
+      expired_at: bson::DateTime::from(Utc::now()),
+      reason: String::from(EXPIRY_REASON_FORCE_EXPIRE),
+    };
+    let expired = self
+      .session_repository
+      .expire_by_user_id(user_id, &expiry)
+      .await?;
+    log::info!("Expired {} sessions of User(id={})", expired, user_id);
+    Ok(())
+  }
 }
 
 #[async_trait]
 impl UsersService for UsersServiceImpl {
   async fn create_admin_user(
     &self,
+    caller: &UserAuth,
     user: CreateSystemAdminUserRequest,
   ) -> Result<UserDto, Rejection> {
     log::info!("Creating user with loginId={}", user.login_id);
+    self
+      .ensure_valid_new_user(caller, &user.login_id, &user.role_ids)
+      .await?;
     let user = User {
       id: Uuid::new_v4().to_hyphenated().to_string(),
       user_type: String::from(USER_TYPE_ADMIN),
@@ -53,6 +183,9 @@ impl UsersService for UsersServiceImpl {
       login_id: user.login_id,
       password: self.hash_service.hash_pw(&user.password)?,
       role_ids: user.role_ids,
+      mfa: None,
+      external_identity: None,
+      disabled: false,
     };
     self.users_repository.save(&user).await?;
 
@@ -64,11 +197,493 @@ impl UsersService for UsersServiceImpl {
     Ok(self.user_converter.to_dto(&user))
   }
 
+  async fn create_service_user(
+    &self,
+    caller: &UserAuth,
+    user: CreateServiceUserRequest,
+  ) -> Result<UserDto, Rejection> {
+    log::info!("Creating service user with loginId={}", user.login_id);
+    self
+      .ensure_valid_new_user(caller, &user.login_id, &user.role_ids)
+      .await?;
+    let user = User {
+      id: Uuid::new_v4().to_hyphenated().to_string(),
+      user_type: String::from(USER_TYPE_SERVICE),
+      company_id: None,
+      login_id: user.login_id,
+      password: String::new(),
+      role_ids: user.role_ids,
+      mfa: None,
+      external_identity: None,
+      disabled: false,
+    };
+    self.users_repository.save(&user).await?;
+
+    log::info!(
+      "Service user successfully created with id={}, loginId={}",
+      user.id,
+      user.login_id
+    );
+    Ok(self.user_converter.to_dto(&user))
+  }
+
   async fn get_user(&self, user_id: &str) -> Result<Option<User>, Rejection> {
     self.users_repository.find_by_id(user_id).await
   }
 
+  async fn get_user_dto(&self, user_id: &str) -> Result<UserDto, Rejection> {
+    match self.users_repository.find_by_id(user_id).await? {
+      Some(u) => Ok(self.user_converter.to_dto(&u)),
+      None => Err(warp::reject::not_found()),
+    }
+  }
+
   async fn get_user_by_login_id(&self, login_id: &str) -> Result<Option<User>, Rejection> {
     self.users_repository.find_by_login_id(login_id).await
   }
+
+  async fn list_users(&self) -> Result<Vec<UserDto>, Rejection> {
+    let users = self.users_repository.find_all().await?;
+    Ok(
+      users
+        .iter()
+        .map(|u| self.user_converter.to_dto(u))
+        .collect(),
+    )
+  }
+
+  async fn set_disabled(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    disabled: bool,
+  ) -> Result<UserDto, Rejection> {
+    let mut user = self.load_user(user_id).await?;
+    self
+      .ensure_caller_holds_roles(caller, user.role_ids.iter().map(|r| r as &str).collect())
+      .await?;
+
+    user.disabled = disabled;
+    self.users_repository.save(&user).await?;
+    log::info!("User(id={}) disabled={}", user.id, disabled);
+    if disabled {
+      self.expire_sessions(caller, user_id).await?;
+    }
+
+    Ok(self.user_converter.to_dto(&user))
+  }
+
+  async fn reset_password(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    password: &str,
+  ) -> Result<UserDto, Rejection> {
+    if password.is_empty() {
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    let mut user = self.load_user(user_id).await?;
+    if user.user_type == USER_TYPE_SERVICE {
+      log::debug!(
+        "Attempted to reset the password of service User(id={})",
+        user.id
+      );
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    self
+      .ensure_caller_holds_roles(caller, user.role_ids.iter().map(|r| r as &str).collect())
+      .await?;
+
+    user.password = self.hash_service.hash_pw(password)?;
+    self.users_repository.save(&user).await?;
+    log::info!("Password reset for User(id={})", user.id);
+    self.expire_sessions(caller, user_id).await?;
+
+    Ok(self.user_converter.to_dto(&user))
+  }
+
+  async fn grant_role(
+    &self,
+    caller: &UserAuth,
+    user_id: &str,
+    role_id: &str,
+  ) -> Result<UserDto, Rejection> {
+    if caller.sub == user_id {
+      log::warn!(
+        "User(id={}) attempted to grant Role(id={}) to themselves",
+        caller.sub,
+        role_id
+      );
+      return Err(warp::reject::custom(AuthorizationError::new()));
+    }
+    if !self.roles_service.role_exists(role_id).await? {
+      log::debug!("Attempted to grant unknown Role(id={})", role_id);
+      return Err(warp::reject::custom(ValidationError::new()));
+    }
+    self
+      .ensure_caller_holds_roles(caller, vec![role_id])
+      .await?;
+    self
+      .update_user(user_id, |u| {
+        if !u.role_ids.iter().any(|r| r == role_id) {
+          u.role_ids.push(String::from(role_id));
+          log::info!("Role(id={}) granted to User(id={})", role_id, u.id);
+        }
+        Ok(())
+      })
+      .await
+  }
+
+  async fn revoke_role(&self, user_id: &str, role_id: &str) -> Result<UserDto, Rejection> {
+    self
+      .update_user(user_id, |u| {
+        if !u.role_ids.iter().any(|r| r == role_id) {
+          log::warn!(
+            "Attempted to revoke Role(id={}) from User(id={}), but it was not granted",
+            role_id,
+            u.id
+          );
+          return Err(warp::reject::not_found());
+        }
+        u.role_ids.retain(|r| r != role_id);
+        log::info!("Role(id={}) revoked from User(id={})", role_id, u.id);
+        Ok(())
+      })
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::converters::role::RoleConverterImpl;
+  use crate::converters::user::UserConverterImpl;
+  use crate::model::session::SESSION_TYPE_USER;
+  use crate::model::{Role, UserSession};
+  use crate::repositories::RoleRepository;
+  use crate::services::users::RolesServiceImpl;
+  use crate::testing::{
+    MemoryRoleRepository, MemorySessionRepository, MemoryUsersRepository, PlainHashService,
+  };
+
+  const CALLER_ID: &str = "caller";
+  const TARGET_ID: &str = "target";
+
+  struct Fixture {
+    users: Arc<MemoryUsersRepository>,
+    sessions: Arc<MemorySessionRepository>,
+    service: UsersServiceImpl,
+  }
+
+  impl Fixture {
+    async fn new() -> Fixture {
+      let roles = Arc::new(MemoryRoleRepository::default());
+      for (id, permissions, included) in &[
+        ("superAdmin", vec!["*"], vec![]),
+        ("reader", vec!["award:get", "award:list"], vec![]),
+        ("editor", vec!["award:create"], vec!["reader"]),
+        ("userAdmin", vec!["user:list"], vec!["admin"]),
+        ("admin", vec!["user:createAdmin"], vec![]),
+      ] {
+        roles
+          .save(&Role {
+            id: id.to_string(),
+            name: id.to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            included_role_ids: included.iter().map(|i| i.to_string()).collect(),
+          })
+          .await
+          .unwrap();
+      }
+
+      let users = Arc::new(MemoryUsersRepository::default());
+      for (id, role_ids) in &[(CALLER_ID, vec!["editor"]), (TARGET_ID, vec!["reader"])] {
+        users
+          .save(&User {
+            id: id.to_string(),
+            user_type: String::from(USER_TYPE_ADMIN),
+            company_id: None,
+            login_id: id.to_string(),
+            password: String::from("hashed:password"),
+            role_ids: role_ids.iter().map(|r| r.to_string()).collect(),
+            mfa: None,
+            external_identity: None,
+            disabled: false,
+          })
+          .await
+          .unwrap();
+      }
+
+      let sessions = Arc::new(MemorySessionRepository::default());
+      let now = Utc::now();
+      sessions
+        .save(&UserSession {
+          id: String::from("session"),
+          user_id: String::from(TARGET_ID),
+          session_type: String::from(SESSION_TYPE_USER),
+          session_secret: String::from("hashed:secret"),
+          created_at: bson::DateTime::from(now),
+          expire_at: bson::DateTime::from(now + chrono::Duration::hours(1)),
+          max_expire_at: None,
+          expired: None,
+        })
+        .await
+        .unwrap();
+
+      let roles_service = Arc::new(RolesServiceImpl::new(
+        Arc::new(RoleConverterImpl::new()),
+        roles,
+      ));
+      let service = UsersServiceImpl::new(
+        Arc::new(UserConverterImpl::new()),
+        Arc::new(PlainHashService {}),
+        roles_service,
+        Arc::clone(&users) as Arc<dyn UsersRepository + Send + Sync>,
+        Arc::clone(&sessions) as Arc<dyn UserSessionRepository + Send + Sync>,
+      );
+      Fixture {
+        users,
+        sessions,
+        service,
+      }
+    }
+
+    async fn role_ids(&self, user_id: &str) -> Vec<String> {
+      self
+        .users
+        .find_by_id(user_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .role_ids
+    }
+  }
+
+  fn caller(permissions: &[&str]) -> UserAuth {
+    UserAuth {
+      sub: String::from(CALLER_ID),
+      login_id: String::from(CALLER_ID),
+      session_id: String::from("caller-session"),
+      permissions: permissions.iter().map(|p| p.to_string()).collect(),
+      iat: 0,
+      exp: i64::MAX,
+    }
+  }
+
+  fn is_forbidden(result: Result<UserDto, Rejection>) -> bool {
+    match result {
+      Err(e) => e.find::<AuthorizationError>().is_some(),
+      Ok(_) => false,
+    }
+  }
+
+  fn is_invalid(result: Result<UserDto, Rejection>) -> bool {
+    match result {
+      Err(e) => e.find::<ValidationError>().is_some(),
+      Ok(_) => false,
+    }
+  }
+
+  fn admin_request(login_id: &str, role_ids: &[&str]) -> CreateSystemAdminUserRequest {
+    CreateSystemAdminUserRequest {
+      login_id: String::from(login_id),
+      password: String::from("password"),
+      role_ids: role_ids.iter().map(|r| r.to_string()).collect(),
+    }
+  }
+
+  fn service_request(login_id: &str, role_ids: &[&str]) -> CreateServiceUserRequest {
+    CreateServiceUserRequest {
+      login_id: String::from(login_id),
+      role_ids: role_ids.iter().map(|r| r.to_string()).collect(),
+    }
+  }
+
+  #[tokio::test]
+  async fn grant_role_allows_roles_within_the_callers_permissions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["role:grant", "award:*"]);
+
+    fixture
+      .service
+      .grant_role(&caller, TARGET_ID, "editor")
+      .await
+      .unwrap();
+    assert_eq!(fixture.role_ids(TARGET_ID).await, vec!["reader", "editor"]);
+  }
+
+  #[tokio::test]
+  async fn grant_role_rejects_permissions_the_caller_lacks() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["role:grant", "award:*", "user:list"]);
+
+    let result = fixture
+      .service
+      .grant_role(&caller, TARGET_ID, "superAdmin")
+      .await;
+    assert!(is_forbidden(result));
+    let result = fixture
+      .service
+      .grant_role(&caller, TARGET_ID, "userAdmin")
+      .await;
+    assert!(is_forbidden(result));
+    assert_eq!(fixture.role_ids(TARGET_ID).await, vec!["reader"]);
+  }
+
+  #[tokio::test]
+  async fn grant_role_rejects_self_grants() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["*"]);
+
+    let result = fixture.service.grant_role(&caller, CALLER_ID, "reader").await;
+    assert!(is_forbidden(result));
+    assert_eq!(fixture.role_ids(CALLER_ID).await, vec!["editor"]);
+  }
+
+  #[tokio::test]
+  async fn reset_password_rejects_targets_with_more_permissions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:resetPassword", "award:get"]);
+
+    let result = fixture
+      .service
+      .reset_password(&caller, TARGET_ID, "new-password")
+      .await;
+    assert!(is_forbidden(result));
+    assert_eq!(fixture.sessions.active(TARGET_ID), 1);
+  }
+
+  #[tokio::test]
+  async fn reset_password_expires_sessions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:resetPassword", "award:*"]);
+
+    fixture
+      .service
+      .reset_password(&caller, TARGET_ID, "new-password")
+      .await
+      .unwrap();
+    let target = fixture.users.find_by_id(TARGET_ID).await.unwrap().unwrap();
+    assert_eq!(target.password, "hashed:new-password");
+    assert_eq!(fixture.sessions.active(TARGET_ID), 0);
+  }
+
+  #[tokio::test]
+  async fn disabling_expires_sessions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:disable", "award:*"]);
+
+    fixture
+      .service
+      .set_disabled(&caller, TARGET_ID, true)
+      .await
+      .unwrap();
+    assert_eq!(fixture.sessions.active(TARGET_ID), 0);
+  }
+
+  #[tokio::test]
+  async fn disabling_rejects_targets_with_more_permissions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:disable", "award:get"]);
+
+    let result = fixture.service.set_disabled(&caller, TARGET_ID, true).await;
+    assert!(is_forbidden(result));
+    assert!(!fixture.users.get(TARGET_ID).disabled);
+    assert_eq!(fixture.sessions.active(TARGET_ID), 1);
+  }
+
+  #[tokio::test]
+  async fn create_admin_user_allows_roles_within_the_callers_permissions() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:createAdmin", "award:*"]);
+
+    let user = fixture
+      .service
+      .create_admin_user(&caller, admin_request("new-admin", &["editor"]))
+      .await
+      .unwrap();
+    let created = fixture.users.get(&user.id);
+    assert_eq!(created.login_id, "new-admin");
+    assert_eq!(created.password, "hashed:password");
+    assert_eq!(created.role_ids, vec!["editor"]);
+  }
+
+  #[tokio::test]
+  async fn create_admin_user_rejects_permissions_the_caller_lacks() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:createAdmin", "award:*"]);
+
+    let result = fixture
+      .service
+      .create_admin_user(&caller, admin_request("new-admin", &["userAdmin"]))
+      .await;
+    assert!(is_forbidden(result));
+    let result = fixture
+      .service
+      .create_service_user(&caller, service_request("new-service", &["superAdmin"]))
+      .await;
+    assert!(is_forbidden(result));
+    assert_eq!(fixture.users.users.lock().unwrap().len(), 2);
+  }
+
+  #[tokio::test]
+  async fn create_user_rejects_unknown_roles() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["*"]);
+
+    let result = fixture
+      .service
+      .create_admin_user(&caller, admin_request("new-admin", &["reader", "missing"]))
+      .await;
+    assert!(is_invalid(result));
+    let result = fixture
+      .service
+      .create_service_user(&caller, service_request("new-service", &["missing"]))
+      .await;
+    assert!(is_invalid(result));
+    assert_eq!(fixture.users.users.lock().unwrap().len(), 2);
+  }
+
+  #[tokio::test]
+  async fn create_user_rejects_existing_login_ids() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["*"]);
+
+    let result = fixture
+      .service
+      .create_admin_user(&caller, admin_request(TARGET_ID, &["reader"]))
+      .await;
+    assert!(is_invalid(result));
+    let result = fixture
+      .service
+      .create_service_user(&caller, service_request(TARGET_ID, &[]))
+      .await;
+    assert!(is_invalid(result));
+    assert_eq!(fixture.users.get(TARGET_ID).password, "hashed:password");
+  }
+
+  #[tokio::test]
+  async fn create_service_user_has_no_password() {
+    let fixture = Fixture::new().await;
+    let caller = caller(&["user:createService", "award:get", "award:list"]);
+
+    let user = fixture
+      .service
+      .create_service_user(&caller, service_request("new-service", &["reader"]))
+      .await
+      .unwrap();
+    let created = fixture.users.get(&user.id);
+    assert_eq!(created.user_type, USER_TYPE_SERVICE);
+    assert!(created.password.is_empty());
+  }
+
+  #[tokio::test]
+  async fn get_user_dto_finds_users_by_id() {
+    let fixture = Fixture::new().await;
+
+    let user = fixture.service.get_user_dto(TARGET_ID).await.unwrap();
+    assert_eq!(user.login_id, TARGET_ID);
+    let missing = fixture.service.get_user_dto("missing").await;
+    assert!(missing.err().unwrap().is_not_found());
+  }
 }
@@ -1,11 +1,17 @@
-use crate::dto::{LogInRequest, LogInResponse, RefreshRequest, RefreshResponse};
+use crate::dto::{
+  LogInRequest, LogInResponse, LogInResult, MfaLogInRequest, RefreshRequest, RefreshResponse,
+};
 use crate::errors::{AuthenticationError, ServerError, ValidationError};
+use crate::metrics::{self, LOGIN_RESULT_FAILURE, LOGIN_RESULT_SUCCESS};
 use crate::model::session::{EXPIRY_REASON_LOG_OUT, SESSION_TYPE_USER};
+use crate::model::user::USER_TYPE_SERVICE;
 use crate::model::{SessionExpiry, User, UserSession};
 use crate::repositories::UserSessionRepository;
-use crate::services::{ConfigService, HashService, RolesService, TokenService, UsersService};
+use crate::services::{
+  ConfigService, HashService, MfaService, RolesService, TokenService, UsersService,
+};
 use async_trait::async_trait;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use pwhash::bcrypt;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
@@ -15,7 +21,11 @@ use warp::Rejection;
 
 #[async_trait]
 pub trait SessionService {
-  async fn create_session(&self, request: &LogInRequest) -> Result<LogInResponse, Rejection>;
+  async fn create_session(&self, request: &LogInRequest) -> Result<LogInResult, Rejection>;
+  async fn complete_mfa(&self, request: &MfaLogInRequest) -> Result<LogInResponse, Rejection>;
+  /// Starts a session for a user authenticated by other means, such as OIDC. Users enrolled in MFA
+  /// are issued a challenge, as with a password login.
+  async fn create_session_for_user(&self, user: &User) -> Result<LogInResult, Rejection>;
   async fn refresh_token(&self, request: &RefreshRequest) -> Result<RefreshResponse, Rejection>;
   async fn sign_out(&self, session_id: &str) -> Result<(), Rejection>;
 }
@@ -26,6 +36,7 @@ pub struct SessionServiceImpl {
   token_service: Arc<dyn TokenService + Send + Sync>,
   users_service: Arc<dyn UsersService + Send + Sync>,
   roles_service: Arc<dyn RolesService + Send + Sync>,
+  mfa_service: Arc<dyn MfaService + Send + Sync>,
   session_repository: Arc<dyn UserSessionRepository + Send + Sync>,
 }
 
@@ -36,6 +47,7 @@ impl SessionServiceImpl {
     token_service: Arc<dyn TokenService + Send + Sync>,
     users_service: Arc<dyn UsersService + Send + Sync>,
     roles_service: Arc<dyn RolesService + Send + Sync>,
+    mfa_service: Arc<dyn MfaService + Send + Sync>,
     session_repository: Arc<dyn UserSessionRepository + Send + Sync>,
   ) -> SessionServiceImpl {
     SessionServiceImpl {
@@ -44,6 +56,7 @@ impl SessionServiceImpl {
       token_service,
       users_service,
       roles_service,
+      mfa_service,
       session_repository,
     }
   }
@@ -55,38 +68,53 @@ impl SessionServiceImpl {
       .collect()
   }
 
+  fn idle_expiry(
+    now: DateTime<Utc>,
+    idle_timeout_sec: i64,
+    max_expire_at: DateTime<Utc>,
+  ) -> DateTime<Utc> {
+    let expire_at = now + Duration::seconds(idle_timeout_sec);
+    if expire_at > max_expire_at {
+      max_expire_at
+    } else {
+      expire_at
+    }
+  }
+
+  /// Sessions stored before absolute lifetimes existed take theirs from the current config.
+  fn max_expiry(session: &UserSession, max_lifetime_sec: i64) -> DateTime<Utc> {
+    session
+      .max_expire_at
+      .map(|m| *m)
+      .unwrap_or_else(|| *session.created_at + Duration::seconds(max_lifetime_sec))
+  }
+
   async fn create_token(&self, user: &User, session: &UserSession) -> Result<String, Rejection> {
     let roles = self
       .roles_service
-      .get_roles_by_id(user.role_ids.iter().map(|i| i as &str).collect())
+      .resolve_roles(user.role_ids.iter().map(|i| i as &str).collect())
       .await?;
 
     self
       .token_service
       .create_token(&user, &session, roles.iter().collect())
   }
-}
-
-#[async_trait]
-impl SessionService for SessionServiceImpl {
-  async fn create_session(&self, request: &LogInRequest) -> Result<LogInResponse, Rejection> {
-    let user = self
-      .users_service
-      .get_user_by_login_id(&request.login_id)
-      .await?;
-    if let None = user {
-      return Err(warp::reject::custom(AuthenticationError::new()));
-    }
 
-    let user = user.unwrap();
-    if !bcrypt::verify(&request.password, &user.password) {
+  async fn start_session(&self, user: &User) -> Result<LogInResponse, Rejection> {
+    if user.disabled {
+      log::debug!(
+        "Attempted to start a session for disabled User(id={})",
+        user.id
+      );
       return Err(warp::reject::custom(AuthenticationError::new()));
     }
 
     let session_secret = self.create_secret();
+    let config = self.config_service.get_config().session;
     let created_at = Utc::now();
+    let max_expire_at = created_at + Duration::seconds(config.max_lifetime_sec);
     let expire_at =
-      created_at + Duration::seconds(self.config_service.get_config().session.lifetime_sec);
+      SessionServiceImpl::idle_expiry(created_at, config.idle_timeout_sec, max_expire_at);
     let session = UserSession {
       id: Uuid::new_v4().to_hyphenated().to_string(),
       user_id: user.id.clone(),
@@ -94,6 +122,7 @@ impl SessionService for SessionServiceImpl {
       session_secret: self.hash_service.hash_pw(&session_secret)?,
       created_at: bson::DateTime::from(created_at),
       expire_at: bson::DateTime::from(expire_at),
+      max_expire_at: Some(bson::DateTime::from(max_expire_at)),
       expired: None,
     };
 
@@ -102,10 +131,76 @@ impl SessionService for SessionServiceImpl {
     Ok(LogInResponse {
       session_id: session.id.clone(),
       session_secret,
-      token: self.create_token(&user, &session).await?,
+      token: self.create_token(user, &session).await?,
     })
   }
 
+  async fn log_in(&self, request: &LogInRequest) -> Result<LogInResult, Rejection> {
+    let user = self
+      .users_service
+      .get_user_by_login_id(&request.login_id)
+      .await?;
+    if let None = user {
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    let user = user.unwrap();
+    if user.user_type == USER_TYPE_SERVICE {
+      log::debug!("Service User(id={}) attempted to log in", user.id);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    if !bcrypt::verify(&request.password, &user.password) {
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    if user.disabled {
+      log::debug!("Disabled User(id={}) attempted to log in", user.id);
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    self.start_session_or_challenge(&user).await
+  }
+
+  async fn start_session_or_challenge(&self, user: &User) -> Result<LogInResult, Rejection> {
+    if user.mfa.as_ref().map(|m| m.enabled).unwrap_or(false) {
+      log::debug!("MFA challenge issued for User(id={})", user.id);
+      return Ok(LogInResult::MfaRequired(
+        self.mfa_service.create_challenge(user).await?,
+      ));
+    }
+
+    Ok(LogInResult::Success(self.start_session(user).await?))
+  }
+}
+
+#[async_trait]
+impl SessionService for SessionServiceImpl {
+  async fn create_session(&self, request: &LogInRequest) -> Result<LogInResult, Rejection> {
+    let result = self.log_in(request).await;
+    match &result {
+      Ok(LogInResult::Success(_)) => metrics::record_login(LOGIN_RESULT_SUCCESS),
+      Ok(LogInResult::MfaRequired(_)) => (),
+      Err(_) => metrics::record_login(LOGIN_RESULT_FAILURE),
+    }
+    result
+  }
+
+  async fn complete_mfa(&self, request: &MfaLogInRequest) -> Result<LogInResponse, Rejection> {
+    let result = match self.mfa_service.verify_challenge(request).await {
+      Ok(user) => self.start_session(&user).await,
+      Err(e) => Err(e),
+    };
+    metrics::record_login(if result.is_ok() {
+      LOGIN_RESULT_SUCCESS
+    } else {
+      LOGIN_RESULT_FAILURE
+    });
+    result
+  }
+
+  async fn create_session_for_user(&self, user: &User) -> Result<LogInResult, Rejection> {
+    self.start_session_or_challenge(user).await
+  }
+
   async fn refresh_token(&self, request: &RefreshRequest) -> Result<RefreshResponse, Rejection> {
     let session = self
       .session_repository
@@ -115,7 +210,7 @@ impl SessionService for SessionServiceImpl {
       log::debug!("Could not locate session {}", request.session_id);
       return Err(warp::reject::custom(AuthenticationError::new()));
     }
-    let session = session.unwrap();
+    let mut session = session.unwrap();
 
     if !bcrypt::verify(&request.session_secret, &session.session_secret) {
       log::debug!("Invalid key for session {}", request.session_id);
@@ -131,9 +226,36 @@ impl SessionService for SessionServiceImpl {
       );
       return Err(warp::reject::custom(ServerError::new()));
     }
+    let user = user.unwrap();
+    if user.disabled {
+      log::debug!(
+        "Disabled User(id={}) attempted to refresh Session(id={})",
+        user.id,
+        session.id
+      );
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+
+    let config = self.config_service.get_config().session;
+    let now = Utc::now();
+    let max_expire_at = SessionServiceImpl::max_expiry(&session, config.max_lifetime_sec);
+    if max_expire_at <= now {
+      log::debug!(
+        "Session(id={}) has reached its maximum lifetime",
+        session.id
+      );
+      return Err(warp::reject::custom(AuthenticationError::new()));
+    }
+    session.max_expire_at = Some(bson::DateTime::from(max_expire_at));
+    session.expire_at = bson::DateTime::from(SessionServiceImpl::idle_expiry(
+      now,
+      config.idle_timeout_sec,
+      max_expire_at,
+    ));
+    self.session_repository.save(&session).await?;
 
     Ok(RefreshResponse {
-      token: self.create_token(&user.unwrap(), &session).await?,
+      token: self.create_token(&user, &session).await?,
     })
   }
 
@@ -162,3 +284,159 @@ impl SessionService for SessionServiceImpl {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::converters::role::RoleConverterImpl;
+  use crate::converters::user::UserConverterImpl;
+  use crate::model::user::USER_TYPE_ADMIN;
+  use crate::model::UserMfa;
+  use crate::repositories::UsersRepository;
+  use crate::services::users::{
+    MfaServiceImpl, RolesServiceImpl, TokenServiceImpl, UsersServiceImpl,
+  };
+  use crate::testing::{
+    config, MemoryMfaChallengeRepository, MemoryRoleRepository, MemorySessionRepository,
+    MemoryUsersRepository, PlainHashService, StubConfigService,
+  };
+  use chrono::TimeZone;
+
+  fn service(users: Arc<MemoryUsersRepository>) -> SessionServiceImpl {
+    let config_service = Arc::new(StubConfigService { config: config() });
+    let hash_service = Arc::new(PlainHashService {});
+    let roles_service = Arc::new(RolesServiceImpl::new(
+      Arc::new(RoleConverterImpl::new()),
+      Arc::new(MemoryRoleRepository::default()),
+    ));
+    let sessions = Arc::new(MemorySessionRepository::default());
+    let users_service = Arc::new(UsersServiceImpl::new(
+      Arc::new(UserConverterImpl::new()),
+      Arc::clone(&hash_service) as Arc<dyn HashService + Send + Sync>,
+      Arc::clone(&roles_service) as Arc<dyn RolesService + Send + Sync>,
+      Arc::clone(&users) as Arc<dyn UsersRepository + Send + Sync>,
+      Arc::clone(&sessions) as Arc<dyn UserSessionRepository + Send + Sync>,
+    ));
+    let mfa_service = Arc::new(MfaServiceImpl::new(
+      Arc::clone(&config_service) as Arc<dyn ConfigService + Send + Sync>,
+      Arc::clone(&hash_service) as Arc<dyn HashService + Send + Sync>,
+      users,
+      Arc::new(MemoryMfaChallengeRepository::default()),
+    ));
+    SessionServiceImpl::new(
+      Arc::clone(&config_service) as Arc<dyn ConfigService + Send + Sync>,
+      hash_service,
+      Arc::new(TokenServiceImpl::new(config_service)),
+      users_service,
+      roles_service,
+      mfa_service,
+      sessions,
+    )
+  }
+
+  fn user(id: &str, mfa_enabled: bool) -> User {
+    User {
+      id: String::from(id),
+      user_type: String::from(USER_TYPE_ADMIN),
+      company_id: None,
+      login_id: String::from(id),
+      password: String::from("hashed:password"),
+      role_ids: Vec::new(),
+      mfa: Some(UserMfa {
+        secret: String::from("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"),
+        enabled: mfa_enabled,
+        recovery_codes: Vec::new(),
+        last_used_step: None,
+      }),
+      external_identity: None,
+      disabled: false,
+    }
+  }
+
+  fn session(created_at: DateTime<Utc>, max_expire_at: Option<DateTime<Utc>>) -> UserSession {
+    UserSession {
+      id: String::from("session"),
+      user_id: String::from("user"),
+      session_type: String::from(SESSION_TYPE_USER),
+      session_secret: String::from("secret"),
+      created_at: bson::DateTime::from(created_at),
+      expire_at: bson::DateTime::from(created_at),
+      max_expire_at: max_expire_at.map(bson::DateTime::from),
+      expired: None,
+    }
+  }
+
+  #[test]
+  fn idle_expiry_slides_from_now_up_to_the_absolute_expiry() {
+    let now = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+    let max_expire_at = now + Duration::hours(2);
+
+    assert_eq!(
+      SessionServiceImpl::idle_expiry(now, 3600, max_expire_at),
+      now + Duration::hours(1)
+    );
+    assert_eq!(
+      SessionServiceImpl::idle_expiry(now, 3 * 3600, max_expire_at),
+      max_expire_at
+    );
+  }
+
+  #[test]
+  fn max_expiry_prefers_the_stored_value() {
+    let created_at = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+    let stored = created_at + Duration::days(3);
+
+    assert_eq!(
+      SessionServiceImpl::max_expiry(&session(created_at, Some(stored)), 86400),
+      stored
+    );
+  }
+
+  #[test]
+  fn max_expiry_of_legacy_sessions_counts_from_creation() {
+    let created_at = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+
+    assert_eq!(
+      SessionServiceImpl::max_expiry(&session(created_at, None), 86400),
+      created_at + Duration::days(1)
+    );
+  }
+
+  #[test]
+  fn legacy_sessions_deserialise_without_max_expiry() {
+    let now = Utc::now();
+    let doc = bson::doc! {
+      "_id": "session",
+      "userId": "user",
+      "sessionType": SESSION_TYPE_USER,
+      "sessionSecret": "secret",
+      "createdAt": now,
+      "expireAt": now,
+      "expired": bson::Bson::Null,
+    };
+
+    let session: UserSession = bson::from_document(doc).unwrap();
+    assert!(session.max_expire_at.is_none());
+  }
+
+  #[tokio::test]
+  async fn externally_authenticated_users_enrolled_in_mfa_are_challenged() {
+    let users = Arc::new(MemoryUsersRepository::default());
+    for u in &[user("enrolled", true), user("pending", false)] {
+      users.save(u).await.unwrap();
+    }
+    let service = service(users);
+
+    let res = service
+      .create_session_for_user(&user("enrolled", true))
+      .await
+      .unwrap();
+    assert!(matches!(res, LogInResult::MfaRequired(_)));
+
+    let res = service
+      .create_session_for_user(&user("pending", false))
+      .await
+      .unwrap();
+    assert!(matches!(res, LogInResult::Success(_)));
+  }
+}
@@ -0,0 +1,113 @@
+use crate::services::ConfigService;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Resolves on SIGTERM or SIGINT. The SIGTERM handler is installed when this is called rather
+/// than when first polled, so a signal arriving while the server starts up is not missed.
+pub fn shutdown() -> impl Future<Output = ()> {
+  let terminate = signal(SignalKind::terminate());
+  async move {
+    let mut terminate = match terminate {
+      Ok(s) => s,
+      Err(e) => {
+        log::error!("Could not install SIGTERM handler: {}", e);
+        panic!();
+      }
+    };
+
+    tokio::select! {
+      _ = terminate.recv() => log::info!("Received SIGTERM"),
+      _ = tokio::signal::ctrl_c() => log::info!("Received SIGINT"),
+    }
+    log::info!("Shutting down; waiting for in-flight requests to complete");
+  }
+}
+
+/// Reloads the config on each SIGHUP. As with `shutdown`, the handler is installed on the call.
+pub fn reload(config_service: Arc<dyn ConfigService + Send + Sync>) -> impl Future<Output = ()> {
+  let hangup = signal(SignalKind::hangup());
+  async move {
+    let mut hangup = match hangup {
+      Ok(s) => s,
+      Err(e) => {
+        log::error!("Could not install SIGHUP handler: {}", e);
+        return;
+      }
+    };
+
+    while hangup.recv().await.is_some() {
+      log::info!("Received SIGHUP");
+      match config_service.reload() {
+        Ok(()) => log::set_max_level(config_service.get_config().log.level_filter()),
+        Err(e) => log::error!("Configuration reload failed; keeping current config. {}", e),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::domain::AppConfig;
+  use crate::services::config::ConfigError;
+  use crate::testing;
+  use std::process::Command;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::Duration;
+  use tokio::time::{delay_for, timeout};
+
+  #[derive(Default)]
+  struct CountingConfigService {
+    reloads: AtomicUsize,
+  }
+
+  impl ConfigService for CountingConfigService {
+    fn get_config(&self) -> AppConfig {
+      testing::config()
+    }
+
+    fn reload(&self) -> Result<(), ConfigError> {
+      self.reloads.fetch_add(1, Ordering::SeqCst);
+      Ok(())
+    }
+  }
+
+  fn send(signal: &str) {
+    let pid = std::process::id().to_string();
+    assert!(Command::new("kill")
+      .args([signal, &pid])
+      .status()
+      .unwrap()
+      .success());
+  }
+
+  #[tokio::test]
+  async fn shutdown_waits_for_sigterm() {
+    let mut shutdown = Box::pin(shutdown());
+    assert!(timeout(Duration::from_millis(50), &mut shutdown)
+      .await
+      .is_err());
+
+    send("-TERM");
+    assert!(timeout(Duration::from_secs(5), shutdown).await.is_ok());
+  }
+
+  #[tokio::test]
+  async fn each_sighup_reloads_the_config() {
+    let config_service = Arc::new(CountingConfigService::default());
+    tokio::spawn(reload(
+      Arc::clone(&config_service) as Arc<dyn ConfigService + Send + Sync>
+    ));
+
+    for expected in 1..=2 {
+      send("-HUP");
+      let reloaded = timeout(Duration::from_secs(5), async {
+        while config_service.reloads.load(Ordering::SeqCst) < expected {
+          delay_for(Duration::from_millis(10)).await;
+        }
+      });
+      assert!(reloaded.await.is_ok());
+    }
+  }
+}
@@ -0,0 +1,381 @@
+//! In-memory fakes of the services and repositories, shared by the unit tests.
+
+use crate::domain::AppConfig;
+use crate::model::{
+  ApiKey, MfaChallenge, Migration, OidcLogin, Role, SessionExpiry, User, UserExternalIdentity, UserSession,
+};
+use crate::repositories::{
+  ApiKeyRepository, MfaChallengeRepository, MigrationRepository, OidcLoginRepository, RoleRepository, UserSessionRepository,
+  UsersRepository,
+};
+use crate::services::config::ConfigError;
+use crate::services::{ConfigService, HashService};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use warp::Rejection;
+
+/// Deep-copies a model, as the repositories hand out owned records.
+pub fn copy<T: Serialize + DeserializeOwned>(value: &T) -> T {
+  serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+}
+
+/// A complete configuration with the optional sections unset.
+pub fn config() -> AppConfig {
+  serde_json::from_value(json!({
+    "app_name": "ausawards-test",
+    "log": { "level": "info", "format": "text" },
+    "access_log": { "enabled": false, "log_bodies": false, "redacted_fields": [] },
+    "rate_limit": {
+      "session": { "capacity": 10, "per_minute": 10 },
+      "awards": { "capacity": 10, "per_minute": 10 },
+      "users": { "capacity": 10, "per_minute": 10 },
+      "trusted_proxies": [],
+    },
+    "server": { "host": "127.0.0.1", "port": 3030, "body_limit_bytes": 1024, "tls": null },
+    "cors": null,
+    "health": { "ready_timeout_ms": 100 },
+    "mongo": { "uri": "mongodb://127.0.0.1:1", "database": "ausawards-test" },
+    "session": { "secret_length": 30, "idle_timeout_sec": 3600, "max_lifetime_sec": 86400 },
+    "jwt": { "secret": "dGVzdC1zZWNyZXQtdGVzdC1zZWNyZXQ=", "exp_sec": 120 },
+    "mfa": {
+      "issuer": "AusAwards",
+      "challenge_lifetime_sec": 300,
+      "max_attempts": 3,
+      "recovery_code_count": 4,
+      "recovery_code_length": 10,
+    },
+    "api_key": { "secret_length": 40 },
+    "oidc": null,
+  }))
+  .unwrap()
+}
+
+pub struct StubConfigService {
+  pub config: AppConfig,
+}
+
+impl ConfigService for StubConfigService {
+  fn get_config(&self) -> AppConfig {
+    self.config.clone()
+  }
+
+  fn reload(&self) -> Result<(), ConfigError> {
+    Ok(())
+  }
+}
+
+/// Marks rather than hashes, so tests can predict stored values.
+pub struct PlainHashService {}
+
+impl HashService for PlainHashService {
+  fn hash_pw(&self, password: &str) -> Result<String, Rejection> {
+    Ok(format!("hashed:{}", password))
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryRoleRepository {
+  pub roles: Mutex<Vec<Role>>,
+}
+
+#[async_trait]
+impl RoleRepository for MemoryRoleRepository {
+  async fn save(&self, role: &Role) -> Result<(), Rejection> {
+    let mut roles = self.roles.lock().unwrap();
+    roles.retain(|r| r.id != role.id);
+    roles.push(copy(role));
+    Ok(())
+  }
+
+  async fn find_by_ids(&self, role_ids: Vec<&str>) -> Result<Vec<Role>, Rejection> {
+    let roles = self.roles.lock().unwrap();
+    Ok(
+      roles
+        .iter()
+        .filter(|r| role_ids.contains(&r.id.as_str()))
+        .map(copy)
+        .collect(),
+    )
+  }
+
+  async fn find_by_name(&self, name: &str) -> Result<Option<Role>, Rejection> {
+    let roles = self.roles.lock().unwrap();
+    Ok(roles.iter().find(|r| r.name == name).map(copy))
+  }
+
+  async fn find_all(&self) -> Result<Vec<Role>, Rejection> {
+    Ok(self.roles.lock().unwrap().iter().map(copy).collect())
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryUsersRepository {
+  pub users: Mutex<Vec<User>>,
+}
+
+impl MemoryUsersRepository {
+  pub fn get(&self, user_id: &str) -> User {
+    let users = self.users.lock().unwrap();
+    copy(users.iter().find(|u| u.id == user_id).unwrap())
+  }
+}
+
+#[async_trait]
+impl UsersRepository for MemoryUsersRepository {
+  async fn save(&self, user: &User) -> Result<(), Rejection> {
+    let mut users = self.users.lock().unwrap();
+    users.retain(|u| u.id != user.id);
+    users.push(copy(user));
+    Ok(())
+  }
+
+  async fn find_by_id(&self, user_id: &str) -> Result<Option<User>, Rejection> {
+    let users = self.users.lock().unwrap();
+    Ok(users.iter().find(|u| u.id == user_id).map(copy))
+  }
+
+  async fn find_by_login_id(&self, login_id: &str) -> Result<Option<User>, Rejection> {
+    let users = self.users.lock().unwrap();
+    Ok(users.iter().find(|u| u.login_id == login_id).map(copy))
+  }
+
+  async fn find_all(&self) -> Result<Vec<User>, Rejection> {
+    Ok(self.users.lock().unwrap().iter().map(copy).collect())
+  }
+
+  async fn find_by_external_identity(
+    &self,
+    identity: &UserExternalIdentity,
+  ) -> Result<Option<User>, Rejection> {
+    let users = self.users.lock().unwrap();
+    Ok(
+      users
+        .iter()
+        .find(|u| {
+          u.external_identity
+            .as_ref()
+            .map(|i| i.issuer == identity.issuer && i.subject == identity.subject)
+            .unwrap_or(false)
+        })
+        .map(copy),
+    )
+  }
+
+  async fn claim_mfa_step(&self, user_id: &str, step: i64) -> Result<bool, Rejection> {
+    let mut users = self.users.lock().unwrap();
+    let mfa = users
+      .iter_mut()
+      .find(|u| u.id == user_id)
+      .and_then(|u| u.mfa.as_mut());
+    match mfa {
+      Some(m) if m.last_used_step.map(|s| s < step).unwrap_or(true) => {
+        m.last_used_step = Some(step);
+        Ok(true)
+      }
+      _ => Ok(false),
+    }
+  }
+
+  async fn remove_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool, Rejection> {
+    let mut users = self.users.lock().unwrap();
+    let mfa = users
+      .iter_mut()
+      .find(|u| u.id == user_id)
+      .and_then(|u| u.mfa.as_mut());
+    match mfa {
+      Some(m) => {
+        let count = m.recovery_codes.len();
+        m.recovery_codes.retain(|c| c != code_hash);
+        Ok(m.recovery_codes.len() < count)
+      }
+      None => Ok(false),
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct MemorySessionRepository {
+  pub sessions: Mutex<Vec<UserSession>>,
+}
+
+impl MemorySessionRepository {
+  pub fn active(&self, user_id: &str) -> usize {
+    let sessions = self.sessions.lock().unwrap();
+    sessions
+      .iter()
+      .filter(|s| s.user_id == user_id && s.expired.is_none())
+      .count()
+  }
+}
+
+#[async_trait]
+impl UserSessionRepository for MemorySessionRepository {
+  async fn save(&self, session: &UserSession) -> Result<(), Rejection> {
+    let mut sessions = self.sessions.lock().unwrap();
+    sessions.retain(|s| s.id != session.id);
+    sessions.push(copy(session));
+    Ok(())
+  }
+
+  async fn find_active_by_id(&self, id: &str) -> Result<Option<UserSession>, Rejection> {
+    let sessions = self.sessions.lock().unwrap();
+    Ok(
+      sessions
+        .iter()
+        .find(|s| s.id == id && s.expired.is_none())
+        .map(copy),
+    )
+  }
+
+  async fn expire_by_user_id(
+    &self,
+    user_id: &str,
+    expiry: &SessionExpiry,
+  ) -> Result<i64, Rejection> {
+    let mut sessions = self.sessions.lock().unwrap();
+    let mut expired = 0;
+    for s in sessions
+      .iter_mut()
+      .filter(|s| s.user_id == user_id && s.expired.is_none())
+    {
+      s.expired = Some(copy(expiry));
+      expired += 1;
+    }
+    Ok(expired)
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryMfaChallengeRepository {
+  pub challenges: Mutex<Vec<MfaChallenge>>,
+}
+
+#[async_trait]
+impl MfaChallengeRepository for MemoryMfaChallengeRepository {
+  async fn save(&self, challenge: &MfaChallenge) -> Result<(), Rejection> {
+    let mut challenges = self.challenges.lock().unwrap();
+    challenges.retain(|c| c.id != challenge.id);
+    challenges.push(copy(challenge));
+    Ok(())
+  }
+
+  async fn record_attempt(
+    &self,
+    id: &str,
+    max_attempts: u32,
+  ) -> Result<Option<MfaChallenge>, Rejection> {
+    let mut challenges = self.challenges.lock().unwrap();
+    let challenge = challenges.iter_mut().find(|c| {
+      c.id == id && *c.expire_at > Utc::now() && !c.completed && c.attempts < max_attempts
+    });
+    Ok(challenge.map(|c| {
+      c.attempts += 1;
+      copy(&*c)
+    }))
+  }
+
+  async fn complete(&self, id: &str) -> Result<bool, Rejection> {
+    let mut challenges = self.challenges.lock().unwrap();
+    match challenges.iter_mut().find(|c| c.id == id && !c.completed) {
+      Some(c) => {
+        c.completed = true;
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryOidcLoginRepository {
+  pub logins: Mutex<Vec<OidcLogin>>,
+}
+
+#[async_trait]
+impl OidcLoginRepository for MemoryOidcLoginRepository {
+  async fn save(&self, login: &OidcLogin) -> Result<(), Rejection> {
+    let mut logins = self.logins.lock().unwrap();
+    logins.retain(|l| l.state != login.state);
+    logins.push(copy(login));
+    Ok(())
+  }
+
+  async fn find_active_by_state(&self, state: &str) -> Result<Option<OidcLogin>, Rejection> {
+    let logins = self.logins.lock().unwrap();
+    Ok(
+      logins
+        .iter()
+        .find(|l| l.state == state && !l.completed && *l.expire_at > Utc::now())
+        .map(copy),
+    )
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryMigrationRepository {
+  pub migrations: Mutex<Vec<Migration>>,
+}
+
+#[async_trait]
+impl MigrationRepository for MemoryMigrationRepository {
+  async fn find_all(&self) -> Result<Vec<Migration>, Rejection> {
+    let migrations = self.migrations.lock().unwrap();
+    Ok(migrations.iter().map(copy).collect())
+  }
+}
+
+#[derive(Default)]
+pub struct MemoryApiKeyRepository {
+  pub api_keys: Mutex<Vec<ApiKey>>,
+}
+
+#[async_trait]
+impl ApiKeyRepository for MemoryApiKeyRepository {
+  async fn save(&self, api_key: &ApiKey) -> Result<(), Rejection> {
+    let mut api_keys = self.api_keys.lock().unwrap();
+    api_keys.retain(|k| k.id != api_key.id);
+    api_keys.push(copy(api_key));
+    Ok(())
+  }
+
+  async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection> {
+    let api_keys = self.api_keys.lock().unwrap();
+    Ok(api_keys.iter().find(|k| k.id == id).map(copy))
+  }
+
+  async fn find_active_by_id(&self, id: &str) -> Result<Option<ApiKey>, Rejection> {
+    let api_keys = self.api_keys.lock().unwrap();
+    Ok(
+      api_keys
+        .iter()
+        .find(|k| {
+          k.id == id && !k.revoked && k.expire_at.map(|e| *e > Utc::now()).unwrap_or(true)
+        })
+        .map(copy),
+    )
+  }
+
+  async fn update_key_secret(&self, id: &str, key_secret: &str) -> Result<(), Rejection> {
+    let mut api_keys = self.api_keys.lock().unwrap();
+    if let Some(k) = api_keys.iter_mut().find(|k| k.id == id) {
+      k.key_secret = String::from(key_secret);
+    }
+    Ok(())
+  }
+
+  async fn update_last_used_at(
+    &self,
+    id: &str,
+    last_used_at: DateTime<Utc>,
+  ) -> Result<(), Rejection> {
+    let mut api_keys = self.api_keys.lock().unwrap();
+    if let Some(k) = api_keys.iter_mut().find(|k| k.id == id) {
+      k.last_used_at = Some(bson::DateTime::from(last_used_at));
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_COMMIT: &str = env!("BUILD_GIT_COMMIT");
+const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+/// Version of the HTTP API as MAJOR.MINOR. Bump the minor version when endpoints or fields are
+/// added, and the major version when existing ones change or are removed.
+pub const API_VERSION: &str = "1.0";
+
+pub fn build_timestamp() -> DateTime<Utc> {
+  let secs = BUILD_TIMESTAMP.parse::<i64>().unwrap_or(0);
+  DateTime::from_utc(NaiveDateTime::from_timestamp(secs, 0), Utc)
+}
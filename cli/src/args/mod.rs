@@ -1,3 +1,168 @@
-mod parser;
+use crate::commands::SelectCommand;
+use clap::{App, AppSettings, Arg, ArgMatches, Shell, SubCommand};
 
-pub use parser::{ArgsParser, FlagType};
+pub const APP_NAME: &str = "ausawards";
+pub const ARG_CONFIG: &str = "config";
+pub const ARG_SESSION: &str = "session";
+pub const ARG_PROFILE: &str = "profile";
+pub const ENV_PROFILE: &str = "AUSAWARDS_PROFILE";
+pub const COMMAND_COMPLETIONS: &str = "completions";
+const ARG_SHELL: &str = "shell";
+
+pub fn app(commands: &SelectCommand, version: &'static str) -> App<'static, 'static> {
+  let app = App::new(APP_NAME)
+    .bin_name(APP_NAME)
+    .version(version)
+    .about("Command line client for the AusAwards API")
+    .setting(AppSettings::SubcommandRequiredElseHelp)
+    .setting(AppSettings::VersionlessSubcommands)
+    .arg(
+      Arg::with_name(ARG_CONFIG)
+        .long(ARG_CONFIG)
+        .value_name("FILE")
+        .global(true)
+        .help("Config file [default: ~/.ausawards/config.yaml]"),
+    )
+    .arg(
+      Arg::with_name(ARG_SESSION)
+        .long(ARG_SESSION)
+        .value_name("FILE")
+        .global(true)
+        .help("Session file [default: ~/.ausawards/sessions/<profile>.json]"),
+    )
+    .arg(
+      Arg::with_name(ARG_PROFILE)
+        .long(ARG_PROFILE)
+        .value_name("NAME")
+        .env(ENV_PROFILE)
+        .global(true)
+        .help("Profile to use [default: the selected profile]"),
+    )
+    .subcommand(
+      SubCommand::with_name(COMMAND_COMPLETIONS)
+        .about("Prints a shell completion script")
+        .arg(
+          Arg::with_name(ARG_SHELL)
+            .required(true)
+            .possible_values(&["bash", "zsh", "fish"]),
+        ),
+    );
+
+  commands.definition(app)
+}
+
+pub fn completions(commands: &SelectCommand, version: &'static str, matches: &ArgMatches) {
+  let shell = matches
+    .value_of(ARG_SHELL)
+    .and_then(|s| s.parse::<Shell>().ok())
+    .unwrap_or(Shell::Bash);
+  app(commands, version).gen_completions_to(APP_NAME, shell, &mut std::io::stdout());
+}
+
+pub fn config_file(matches: &ArgMatches) -> Option<String> {
+  file_arg(matches, ARG_CONFIG, "config.yaml")
+}
+
+pub fn session_file(matches: &ArgMatches, profile: &str) -> Option<String> {
+  file_arg(matches, ARG_SESSION, &format!("sessions/{}.json", profile))
+}
+
+pub fn history_file() -> Option<String> {
+  home_file("history")
+}
+
+/// The global arguments given on the command line, to pass on to commands run by the shell.
+pub fn global_args(matches: &ArgMatches) -> Vec<String> {
+  [ARG_CONFIG, ARG_SESSION, ARG_PROFILE]
+    .iter()
+    .filter(|name| matches.occurrences_of(name) > 0)
+    .filter_map(|name| {
+      matches
+        .value_of(name)
+        .map(|v| vec![format!("--{}", name), String::from(v)])
+    })
+    .flatten()
+    .collect()
+}
+
+fn file_arg(matches: &ArgMatches, name: &str, default: &str) -> Option<String> {
+  match matches.value_of(name) {
+    Some(f) => Some(String::from(f)),
+    None => home_file(default),
+  }
+}
+
+fn home_file(name: &str) -> Option<String> {
+  std::env::var("HOME")
+    .ok()
+    .map(|home| format!("{}/.ausawards/{}", home, name))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::commands::AwardCommand;
+
+  fn commands() -> SelectCommand {
+    SelectCommand::new().command("award", AwardCommand::new())
+  }
+
+  fn matches(argv: &[&str]) -> ArgMatches<'static> {
+    app(&commands(), "0.0.0").get_matches_from(argv)
+  }
+
+  #[test]
+  fn global_args_pass_on_only_explicit_arguments() {
+    let matches = matches(&[
+      APP_NAME,
+      "--profile",
+      "staging",
+      "award",
+      "--config",
+      "/tmp/config.yaml",
+      "list",
+    ]);
+    assert_eq!(
+      global_args(&matches),
+      vec!["--config", "/tmp/config.yaml", "--profile", "staging"]
+    );
+    assert!(global_args(&self::matches(&[APP_NAME, "award", "list"])).is_empty());
+  }
+
+  #[test]
+  fn completion_scripts_cover_subcommands() {
+    for shell in &[Shell::Bash, Shell::Zsh, Shell::Fish] {
+      let mut script = Vec::new();
+      app(&commands(), "0.0.0").gen_completions_to(APP_NAME, *shell, &mut script);
+      let script = String::from_utf8(script).unwrap();
+      assert!(script.contains("export"), "{}", shell);
+      assert!(script.contains("profile"), "{}", shell);
+    }
+  }
+
+  #[test]
+  fn the_profile_flag_wins_over_the_environment() {
+    std::env::set_var(ENV_PROFILE, "from-env");
+    let from_env = matches(&[APP_NAME, "award", "list"]);
+    let from_flag = matches(&[APP_NAME, "--profile", "from-flag", "award", "list"]);
+    std::env::remove_var(ENV_PROFILE);
+
+    assert_eq!(from_env.value_of(ARG_PROFILE), Some("from-env"));
+    assert!(global_args(&from_env).is_empty());
+    assert_eq!(from_flag.value_of(ARG_PROFILE), Some("from-flag"));
+  }
+
+  #[test]
+  fn session_files_default_to_one_per_profile() {
+    let home = std::env::var("HOME").unwrap();
+    assert_eq!(
+      session_file(&matches(&[APP_NAME, "award", "list"]), "staging"),
+      Some(format!("{}/.ausawards/sessions/staging.json", home))
+    );
+    let explicit = matches(&[APP_NAME, "--session", "/tmp/s.json", "award", "list"]);
+    assert_eq!(
+      session_file(&explicit, "staging"),
+      Some(String::from("/tmp/s.json"))
+    );
+  }
+}
@@ -0,0 +1,250 @@
+use crate::args;
+use crate::config;
+use crate::domain::{Config, RemoteConfig, Session};
+use crate::session;
+use clap::ArgMatches;
+use reqwest::blocking::{Client, Response};
+use reqwest::{Method, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+
+const ERR_UNAUTHENTICATED: &str = "ERR1001";
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+  code: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+  token: String,
+}
+
+pub struct ApiClient {
+  base_url: Url,
+  session_file: String,
+  session: RefCell<Option<Session>>,
+  http: Client,
+}
+
+impl ApiClient {
+  pub fn from_args(matches: &ArgMatches, config: &Config) -> Result<ApiClient, String> {
+    let name = config::profile_name(config, matches.value_of(args::ARG_PROFILE));
+    let remote = config::profile(config, &name)?;
+    match args::session_file(matches, &name) {
+      Some(f) => ApiClient::new(remote, &f),
+      None => Err(String::from(
+        "Could not find session file, pass --session <FILE>",
+      )),
+    }
+  }
+
+  pub fn new(remote: &RemoteConfig, session_file: &str) -> Result<ApiClient, String> {
+    let base_url = ApiClient::base_url(&remote.url)?;
+
+    Ok(ApiClient {
+      base_url,
+      session_file: String::from(session_file),
+      session: RefCell::new(session::load_session(session_file)?),
+      http: Client::new(),
+    })
+  }
+
+  /// The base always ends in `/`, so joining keeps any path prefix the server is mounted under.
+  fn base_url(url: &str) -> Result<Url, String> {
+    let mut base_url = Url::parse(url).map_err(|_| format!("Malformed URL: {}", url))?;
+    if !base_url.path().ends_with('/') {
+      let path = format!("{}/", base_url.path());
+      base_url.set_path(&path);
+    }
+    Ok(base_url)
+  }
+
+  /// Resolves an API path against the base. Paths are written absolute, but are joined as relative
+  /// ones, as an absolute path would replace the base's path prefix.
+  fn endpoint(base_url: &Url, path: &str) -> Result<Url, String> {
+    base_url
+      .join(path.trim_start_matches('/'))
+      .map_err(|_| format!("Malformed path: {}", path))
+  }
+
+  pub fn session(&self) -> Option<Session> {
+    self.session.borrow().clone()
+  }
+
+  pub fn store_session(&self, session: Session) -> Result<(), String> {
+    session::save_session(&self.session_file, &session)?;
+    self.session.replace(Some(session));
+    Ok(())
+  }
+
+  pub fn clear_session(&self) -> Result<(), String> {
+    self.session.replace(None);
+    session::remove_session(&self.session_file)
+  }
+
+  pub fn call_anonymous(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+  ) -> Result<Response, String> {
+    let response = self.send(method, path, body, None)?;
+    ApiClient::check(response)
+  }
+
+  /// Sends an anonymous request, returning error statuses for the caller to inspect.
+  pub fn call_unchecked(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+  ) -> Result<Response, String> {
+    self.send(method, path, body, None)
+  }
+
+  /// Sends a request with the stored token as is, without refreshing it, returning error statuses
+  /// for the caller to inspect.
+  pub fn call_session_unchecked(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+  ) -> Result<Response, String> {
+    let token = match self.session() {
+      Some(s) => s.token,
+      None => return Err(String::from("Not logged in, run `ausawards login` first")),
+    };
+
+    self.send(method, path, body, Some(&token))
+  }
+
+  pub fn call(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Response, String> {
+    let token = match self.session() {
+      Some(s) => s.token,
+      None => return Err(String::from("Not logged in, run `ausawards login` first")),
+    };
+
+    let response = self.send(method.clone(), path, body, Some(&token))?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+      return ApiClient::check(response);
+    }
+
+    let token = self.refresh()?;
+    let response = self.send(method, path, body, Some(&token))?;
+    ApiClient::check(response)
+  }
+
+  pub fn path(segments: &[&str], query: &[(&str, &str)]) -> String {
+    let mut url = Url::parse("http://localhost/").unwrap();
+    url.path_segments_mut().unwrap().extend(segments);
+    if !query.is_empty() {
+      url.query_pairs_mut().extend_pairs(query);
+    }
+
+    match url.query() {
+      Some(q) => format!("{}?{}", url.path(), q),
+      None => String::from(url.path()),
+    }
+  }
+
+  pub fn parse<T: DeserializeOwned>(response: Response) -> Result<T, String> {
+    response
+      .json::<T>()
+      .map_err(|e| format!("Unexpected response from server: {}", e))
+  }
+
+  fn send(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&Value>,
+    token: Option<&str>,
+  ) -> Result<Response, String> {
+    let url = ApiClient::endpoint(&self.base_url, path)?;
+
+    let mut request = self.http.request(method, url);
+    if let Some(t) = token {
+      request = request.bearer_auth(t);
+    }
+    if let Some(b) = body {
+      request = request.json(b);
+    }
+    request
+      .send()
+      .map_err(|e| format!("Could not connect to server: {}", e))
+  }
+
+  fn refresh(&self) -> Result<String, String> {
+    let mut session = match self.session() {
+      Some(s) => s,
+      None => return Err(String::from("Not logged in, run `ausawards login` first")),
+    };
+
+    let body = json!({
+      "sessionId": session.session_id,
+      "sessionSecret": session.session_secret,
+    });
+    let response = self.send(Method::POST, "/session/refresh", Some(&body), None)?;
+    if !response.status().is_success() {
+      return Err(String::from(
+        "Session has expired, run `ausawards login` to log in again",
+      ));
+    }
+
+    session.token = ApiClient::parse::<RefreshResponse>(response)?.token;
+    let token = session.token.clone();
+    self.store_session(session)?;
+    Ok(token)
+  }
+
+  fn check(response: Response) -> Result<Response, String> {
+    let status = response.status();
+    if status.is_success() {
+      return Ok(response);
+    }
+
+    match response.json::<ErrorResponse>() {
+      Ok(e) if e.code == ERR_UNAUTHENTICATED => Err(format!("Authentication failed ({})", e.code)),
+      Ok(e) => Err(format!(
+        "Request failed with status {} ({})",
+        status, e.code
+      )),
+      Err(_) => Err(format!("Request failed with status {}", status)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn endpoint(base_url: &str, path: &str) -> String {
+    ApiClient::endpoint(&ApiClient::base_url(base_url).unwrap(), path)
+      .unwrap()
+      .to_string()
+  }
+
+  #[test]
+  fn paths_are_joined_below_the_base_path() {
+    assert_eq!(
+      endpoint("https://example.com/ausawards", "/session/refresh"),
+      "https://example.com/ausawards/session/refresh"
+    );
+    assert_eq!(
+      endpoint("https://example.com/ausawards/", "/awards?limit=5"),
+      "https://example.com/ausawards/awards?limit=5"
+    );
+    assert_eq!(
+      endpoint("http://127.0.0.1:3030", "/version"),
+      "http://127.0.0.1:3030/version"
+    );
+  }
+
+  #[test]
+  fn malformed_base_urls_are_reported() {
+    assert!(ApiClient::base_url("not a url").is_err());
+  }
+}
@@ -0,0 +1,46 @@
+use crate::client::ApiClient;
+use crate::commands::Command;
+use crate::domain::Config;
+use clap::{App, Arg, ArgMatches};
+
+pub const ARG_JSON: &str = "json";
+
+pub type Definition = fn(App<'static, 'static>) -> App<'static, 'static>;
+pub type Run = fn(&ArgMatches, &ApiClient) -> Result<(), String>;
+
+/// A subcommand that calls the API using the client for the selected profile.
+pub struct ApiCommand {
+  definition: Definition,
+  run: Run,
+}
+
+impl ApiCommand {
+  pub fn new(definition: Definition, run: Run) -> ApiCommand {
+    ApiCommand { definition, run }
+  }
+}
+
+impl Command for ApiCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    (self.definition)(app)
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, &config)?;
+    (self.run)(matches, &client)
+  }
+}
+
+pub fn json_arg() -> Arg<'static, 'static> {
+  Arg::with_name(ARG_JSON)
+    .long(ARG_JSON)
+    .global(true)
+    .help("Prints JSON instead of tables")
+}
+
+pub fn argument(matches: &ArgMatches, name: &str) -> Result<String, String> {
+  matches
+    .value_of(name)
+    .map(String::from)
+    .ok_or_else(|| format!("Missing argument <{}>", name))
+}
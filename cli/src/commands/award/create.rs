@@ -0,0 +1,40 @@
+use super::{argument, find_by_external_id, print_award};
+use crate::client::ApiClient;
+use crate::domain::CreateAwardRequest;
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+
+pub fn definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app.about("Creates an award from a JSON file").arg(
+    Arg::with_name("file")
+      .required(true)
+      .help("JSON file in the CreateAwardRequest shape"),
+  )
+}
+
+pub fn create(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let fname = argument(matches, "file")?;
+  let content =
+    std::fs::read_to_string(&fname).map_err(|e| format!("Could not read {}: {}", fname, e))?;
+  let award: CreateAwardRequest =
+    serde_json::from_str(&content).map_err(|e| format!("Invalid award in {}: {}", fname, e))?;
+  let errors = award.validate();
+  if !errors.is_empty() {
+    return Err(format!(
+      "Invalid award in {}:\n  - {}",
+      fname,
+      errors.join("\n  - ")
+    ));
+  }
+
+  let body = serde_json::to_value(&award).map_err(|e| e.to_string())?;
+  client.call(Method::POST, "/awards", Some(&body))?;
+
+  match find_by_external_id(client, &award.external_id)? {
+    Some(a) => print_award(matches, &a),
+    None => Err(format!(
+      "Award {} was not found after creation",
+      award.external_id
+    )),
+  }
+}
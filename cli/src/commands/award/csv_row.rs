@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// One classification of an award; award details repeat on every row.
+#[derive(Serialize, Deserialize)]
+pub struct CsvRow {
+  pub external_id: String,
+  pub name: String,
+  #[serde(rename = "industryName")]
+  pub industry_name: String,
+  #[serde(rename = "commonRule")]
+  pub common_rule: Option<String>,
+  #[serde(rename = "operativeDate")]
+  pub operative_date: String,
+  #[serde(rename = "expiredDate")]
+  pub expired_date: Option<String>,
+  #[serde(rename = "alternateIds")]
+  pub alternate_ids: Option<String>,
+  #[serde(rename = "classificationId")]
+  pub classification_id: Option<String>,
+  #[serde(rename = "classificationTitle")]
+  pub classification_title: Option<String>,
+  #[serde(rename = "classificationActive")]
+  pub classification_active: Option<bool>,
+  #[serde(rename = "classificationNote")]
+  pub classification_note: Option<String>,
+}
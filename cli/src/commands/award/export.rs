@@ -0,0 +1,351 @@
+use super::csv_row::CsvRow;
+use super::{date, date_validator};
+use crate::client::ApiClient;
+use crate::domain::{Award, AwardClassification, CreateAwardRequest};
+use chrono::{DateTime, SecondsFormat, Timelike, Utc};
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+
+const ARG_FORMAT: &str = "format";
+const ARG_OUTPUT: &str = "output";
+const ARG_IN_FORCE: &str = "in-force";
+const ARG_INDUSTRY: &str = "industry";
+
+const FORMAT_JSON: &str = "json";
+const FORMAT_CSV: &str = "csv";
+const FORMAT_MARKDOWN: &str = "markdown";
+
+pub fn definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Exports awards as JSON, CSV or Markdown")
+    .arg(
+      Arg::with_name(ARG_FORMAT)
+        .long(ARG_FORMAT)
+        .value_name("FORMAT")
+        .possible_values(&[FORMAT_JSON, FORMAT_CSV, FORMAT_MARKDOWN])
+        .help("Output format [default: from the output file extension, otherwise json]"),
+    )
+    .arg(
+      Arg::with_name(ARG_OUTPUT)
+        .long(ARG_OUTPUT)
+        .short("o")
+        .value_name("FILE")
+        .help("Where to write the export [default: stdout]"),
+    )
+    .arg(
+      Arg::with_name(ARG_IN_FORCE)
+        .long(ARG_IN_FORCE)
+        .value_name("DATE")
+        .validator(date_validator)
+        .help("Only exports awards operative on DATE and not yet expired"),
+    )
+    .arg(
+      Arg::with_name(ARG_INDUSTRY)
+        .long(ARG_INDUSTRY)
+        .value_name("NAME")
+        .help("Only exports awards for this industry, ignoring case"),
+    )
+}
+
+pub fn export(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let output = matches.value_of(ARG_OUTPUT);
+  let format = matches
+    .value_of(ARG_FORMAT)
+    .unwrap_or_else(|| output.map(format_for).unwrap_or(FORMAT_JSON));
+  let in_force = match matches.value_of(ARG_IN_FORCE) {
+    Some(d) => Some(date(d)?),
+    None => None,
+  };
+  let industry = matches.value_of(ARG_INDUSTRY).map(|i| i.to_lowercase());
+
+  let awards: Vec<Award> = ApiClient::parse(client.call(Method::GET, "/awards", None)?)?;
+  let mut awards = awards
+    .iter()
+    .map(to_request)
+    .collect::<Result<Vec<_>, _>>()?;
+  awards.retain(|a| {
+    in_force.map(|d| is_in_force(a, d)).unwrap_or(true)
+      && industry
+        .as_ref()
+        .map(|i| a.industry_name.to_lowercase() == *i)
+        .unwrap_or(true)
+  });
+  awards.sort_by(|a, b| a.external_id.cmp(&b.external_id));
+
+  let content = match format {
+    FORMAT_CSV => to_csv(&awards)?,
+    FORMAT_MARKDOWN => to_markdown(&awards),
+    _ => serde_json::to_string_pretty(&awards).map_err(|e| e.to_string())? + "\n",
+  };
+
+  match output {
+    Some(fname) => {
+      std::fs::write(fname, content).map_err(|e| format!("Could not write {}: {}", fname, e))?;
+      println!("Exported {} awards to {}", awards.len(), fname);
+    }
+    None => print!("{}", content),
+  }
+  Ok(())
+}
+
+fn format_for(fname: &str) -> &'static str {
+  let fname = fname.to_lowercase();
+  if fname.ends_with(".csv") {
+    FORMAT_CSV
+  } else if fname.ends_with(".md") || fname.ends_with(".markdown") {
+    FORMAT_MARKDOWN
+  } else {
+    FORMAT_JSON
+  }
+}
+
+fn to_request(award: &Award) -> Result<CreateAwardRequest, String> {
+  let invalid = |field: &str, e: String| {
+    format!(
+      "Award {} has an invalid {}: {}",
+      award.external_id, field, e
+    )
+  };
+  Ok(CreateAwardRequest {
+    external_id: award.external_id.clone(),
+    name: award.name.clone(),
+    industry_name: award.industry_name.clone(),
+    common_rule: award.common_rule.clone(),
+    alternate_ids: award.alternate_ids.clone(),
+    operative_date: date(&award.operative_date).map_err(|e| invalid("operativeDate", e))?,
+    expired_date: match &award.expired_date {
+      Some(d) => Some(date(d).map_err(|e| invalid("expiredDate", e))?),
+      None => None,
+    },
+    classifications: award.classifications.clone(),
+  })
+}
+
+fn is_in_force(award: &CreateAwardRequest, on: DateTime<Utc>) -> bool {
+  award.operative_date <= on && award.expired_date.map(|e| e > on).unwrap_or(true)
+}
+
+/// Formats whole days as YYYY-MM-DD, which import accepts alongside RFC 3339.
+fn format_date(value: DateTime<Utc>) -> String {
+  if value.num_seconds_from_midnight() == 0 && value.nanosecond() == 0 {
+    value.format("%Y-%m-%d").to_string()
+  } else {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+  }
+}
+
+pub fn to_csv(awards: &[CreateAwardRequest]) -> Result<String, String> {
+  let mut writer = csv::Writer::from_writer(Vec::new());
+  for award in awards {
+    let row = |c: Option<&AwardClassification>| CsvRow {
+      external_id: award.external_id.clone(),
+      name: award.name.clone(),
+      industry_name: award.industry_name.clone(),
+      common_rule: award.common_rule.clone(),
+      operative_date: format_date(award.operative_date),
+      expired_date: award.expired_date.map(format_date),
+      alternate_ids: Some(
+        award
+          .alternate_ids
+          .iter()
+          .map(|i| format!("{}:{}", i.id_type, i.id))
+          .collect::<Vec<_>>()
+          .join(";"),
+      )
+      .filter(|i| !i.is_empty()),
+      classification_id: c.map(|c| c.id.clone()),
+      classification_title: c.map(|c| c.title.clone()),
+      classification_active: c.map(|c| c.active),
+      classification_note: c.map(|c| c.note.clone()),
+    };
+
+    let rows: Vec<CsvRow> = if award.classifications.is_empty() {
+      vec![row(None)]
+    } else {
+      award.classifications.iter().map(|c| row(Some(c))).collect()
+    };
+    for r in rows {
+      writer.serialize(r).map_err(|e| e.to_string())?;
+    }
+  }
+
+  let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+  String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn to_markdown(awards: &[CreateAwardRequest]) -> String {
+  let mut lines = vec![
+    String::from("# Awards"),
+    String::new(),
+    markdown_row(&[
+      "External ID",
+      "Name",
+      "Industry",
+      "Common rule",
+      "Operative",
+      "Expired",
+      "Classifications",
+    ]),
+    markdown_row(&["---"; 7]),
+  ];
+  for a in awards {
+    lines.push(markdown_row(&[
+      &a.external_id,
+      &a.name,
+      &a.industry_name,
+      a.common_rule.as_deref().unwrap_or(""),
+      &format_date(a.operative_date),
+      &a.expired_date.map(format_date).unwrap_or_default(),
+      &a.classifications.len().to_string(),
+    ]));
+  }
+
+  for a in awards.iter().filter(|a| !a.classifications.is_empty()) {
+    lines.push(String::new());
+    lines.push(format!(
+      "## {}: {}",
+      markdown_cell(&a.external_id),
+      markdown_cell(&a.name)
+    ));
+    lines.push(String::new());
+    lines.push(markdown_row(&["Classification", "Title", "Active", "Note"]));
+    lines.push(markdown_row(&["---"; 4]));
+    for c in &a.classifications {
+      lines.push(markdown_row(&[
+        &c.id,
+        &c.title,
+        if c.active { "Yes" } else { "No" },
+        &c.note,
+      ]));
+    }
+  }
+
+  lines.join("\n") + "\n"
+}
+
+fn markdown_row(cells: &[&str]) -> String {
+  let cells: Vec<String> = cells.iter().map(|c| markdown_cell(c)).collect();
+  format!("| {} |", cells.join(" | "))
+}
+
+fn markdown_cell(value: &str) -> String {
+  value
+    .replace('|', "\\|")
+    .replace("\r\n", "<br>")
+    .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::import::parse_csv;
+  use super::*;
+  use crate::domain::AwardAlternateId;
+  use chrono::TimeZone;
+
+  fn award(external_id: &str, name: &str) -> CreateAwardRequest {
+    CreateAwardRequest {
+      external_id: String::from(external_id),
+      name: String::from(name),
+      industry_name: String::from("Clerical"),
+      common_rule: None,
+      alternate_ids: Vec::new(),
+      operative_date: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+      expired_date: None,
+      classifications: Vec::new(),
+    }
+  }
+
+  fn classification(id: &str, title: &str, active: bool, note: &str) -> AwardClassification {
+    AwardClassification {
+      id: String::from(id),
+      title: String::from(title),
+      active,
+      note: String::from(note),
+    }
+  }
+
+  #[test]
+  fn whole_days_are_formatted_as_dates() {
+    assert_eq!(format_date(Utc.with_ymd_and_hms(2020, 7, 1, 0, 0, 0).unwrap()), "2020-07-01");
+    assert_eq!(
+      format_date(Utc.with_ymd_and_hms(2020, 7, 1, 9, 30, 0).unwrap()),
+      "2020-07-01T09:30:00Z"
+    );
+  }
+
+  #[test]
+  fn formatted_dates_parse_back() {
+    for value in &[
+      Utc.with_ymd_and_hms(2020, 7, 1, 0, 0, 0).unwrap(),
+      Utc.with_ymd_and_hms(2020, 7, 1, 23, 59, 59).unwrap(),
+    ] {
+      assert_eq!(date(&format_date(*value)).unwrap(), *value);
+    }
+  }
+
+  #[test]
+  fn csv_quotes_commas_quotes_and_newlines() {
+    let mut a = award("MA000001", "Clerks, \"Private\" Sector");
+    a.classifications = vec![classification("C1", "Level 1", true, "line one\nline two")];
+    let csv = to_csv(&[a]).unwrap();
+    let row = csv.lines().nth(1).unwrap();
+    assert!(row.starts_with("MA000001,\"Clerks, \"\"Private\"\" Sector\","));
+    assert!(csv.contains("\"line one\nline two\""));
+  }
+
+  #[test]
+  fn csv_writes_a_row_per_classification() {
+    let mut a = award("MA000001", "Clerks");
+    a.classifications = vec![
+      classification("C1", "Level 1", true, ""),
+      classification("C2", "Level 2", false, ""),
+    ];
+    let csv = to_csv(&[a, award("MA000002", "Cleaners")]).unwrap();
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].contains(",C1,Level 1,true,"));
+    assert!(rows[1].contains(",C2,Level 2,false,"));
+    assert!(rows[2].ends_with(",,,,"));
+  }
+
+  #[test]
+  fn markdown_escapes_pipes_and_newlines() {
+    let mut a = award("MA000001", "Clerks | Private");
+    a.classifications = vec![classification("C1", "Level 1", false, "one\r\ntwo\nthree")];
+    let markdown = to_markdown(&[a]);
+    assert!(markdown.contains("| MA000001 | Clerks \\| Private | Clerical |  | 2020-01-01 |  | 1 |"));
+    assert!(markdown.contains("## MA000001: Clerks \\| Private"));
+    assert!(markdown.contains("| C1 | Level 1 | No | one<br>two<br>three |"));
+  }
+
+  #[test]
+  fn exported_csv_imports_as_the_same_awards() {
+    let mut clerks = award("MA000001", "Clerks, \"Private\" Sector");
+    clerks.common_rule = Some(String::from("CR1"));
+    clerks.alternate_ids = vec![
+      AwardAlternateId {
+        id: String::from("AP1"),
+        id_type: String::from("ORIGINAL_PRINT_ID"),
+      },
+      AwardAlternateId {
+        id: String::from("M2"),
+        id_type: String::from("ORIGINATING_MATTER"),
+      },
+    ];
+    clerks.expired_date = Some(Utc.with_ymd_and_hms(2021, 6, 30, 12, 0, 0).unwrap());
+    clerks.classifications = vec![
+      classification("C1", "Level 1", true, ""),
+      classification("C2", "Level | 2", false, "Retired,\nsee C3"),
+    ];
+    let awards = vec![clerks, award("MA000002", "Cleaners")];
+
+    let imported = match parse_csv(to_csv(&awards).unwrap().as_bytes()) {
+      Ok(a) => a,
+      Err(e) => panic!("{:?}", e),
+    };
+    assert_eq!(
+      serde_json::to_value(&imported).unwrap(),
+      serde_json::to_value(&awards).unwrap()
+    );
+  }
+}
@@ -0,0 +1,569 @@
+use super::csv_row::CsvRow;
+use super::{argument, date, find_by_external_id};
+use crate::client::ApiClient;
+use crate::domain::{Award, AwardAlternateId, AwardClassification, CreateAwardRequest};
+use chrono::{DateTime, Utc};
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::Path;
+
+const FAILURES_SUFFIX: &str = ".failed.json";
+const ARG_DRY_RUN: &str = "dry-run";
+const ARG_FAILURES: &str = "failures";
+
+struct Change {
+  description: String,
+  method: Method,
+  segments: Vec<String>,
+  body: Option<Value>,
+}
+
+enum Plan {
+  Create,
+  Update(String, Vec<Change>),
+  Unchanged,
+}
+
+pub fn definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Creates or updates awards from a JSON or CSV file")
+    .arg(
+      Arg::with_name("file")
+        .required(true)
+        .help("JSON array of awards, or CSV with one row per classification"),
+    )
+    .arg(
+      Arg::with_name(ARG_DRY_RUN)
+        .long(ARG_DRY_RUN)
+        .help("Validates and prints the changes without applying them"),
+    )
+    .arg(
+      Arg::with_name(ARG_FAILURES)
+        .long(ARG_FAILURES)
+        .value_name("FILE")
+        .help("Where to write failed awards [default: <file>.failed.json]"),
+    )
+}
+
+pub fn import(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let fname = argument(matches, "file")?;
+  let dry_run = matches.is_present(ARG_DRY_RUN);
+  let failures_file = matches
+    .value_of(ARG_FAILURES)
+    .map(String::from)
+    .unwrap_or_else(|| failures_file(&fname));
+
+  let awards = if fname.to_lowercase().ends_with(".csv") {
+    read_csv(&fname)?
+  } else {
+    read_json(&fname)?
+  };
+  validate(&awards)?;
+
+  let total = awards.len();
+  let mut created = 0;
+  let mut updated = 0;
+  let mut unchanged = 0;
+  let mut failed = Vec::new();
+  for (i, award) in awards.iter().enumerate() {
+    let result = plan(client, award).and_then(|p| apply(client, award, p, dry_run));
+    match result {
+      Ok((Plan::Create, outcome)) => {
+        created += 1;
+        println!("[{}/{}] {}: {}", i + 1, total, award.external_id, outcome);
+      }
+      Ok((Plan::Update(_, _), outcome)) => {
+        updated += 1;
+        println!("[{}/{}] {}: {}", i + 1, total, award.external_id, outcome);
+      }
+      Ok((Plan::Unchanged, outcome)) => {
+        unchanged += 1;
+        println!("[{}/{}] {}: {}", i + 1, total, award.external_id, outcome);
+      }
+      Err(e) => {
+        println!("[{}/{}] {}: FAILED {}", i + 1, total, award.external_id, e);
+        failed.push((award, e));
+      }
+    }
+  }
+
+  let verb = if dry_run { "Would import" } else { "Imported" };
+  println!(
+    "{} {} awards: {} created, {} updated, {} unchanged, {} failed",
+    verb,
+    total,
+    created,
+    updated,
+    unchanged,
+    failed.len()
+  );
+
+  if dry_run {
+    return if failed.is_empty() {
+      Ok(())
+    } else {
+      Err(format!("{} awards cannot be imported", failed.len()))
+    };
+  }
+
+  if failed.is_empty() {
+    if fname == failures_file {
+      std::fs::remove_file(&failures_file)
+        .map_err(|e| format!("Could not remove {}: {}", failures_file, e))?;
+    }
+    return Ok(());
+  }
+
+  println!("Failures:");
+  for (award, e) in &failed {
+    println!("  - {}: {}", award.external_id, e);
+  }
+
+  let remaining: Vec<&CreateAwardRequest> = failed.iter().map(|(a, _)| *a).collect();
+  let content = serde_json::to_string_pretty(&remaining).map_err(|e| e.to_string())?;
+  std::fs::write(&failures_file, content)
+    .map_err(|e| format!("Could not write {}: {}", failures_file, e))?;
+  Err(format!(
+    "{} awards failed and were written to {}; fix them and resume with `ausawards award import {}`",
+    failed.len(),
+    failures_file,
+    failures_file
+  ))
+}
+
+fn failures_file(fname: &str) -> String {
+  if fname.ends_with(FAILURES_SUFFIX) {
+    return String::from(fname);
+  }
+
+  let path = Path::new(fname);
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(fname);
+  path
+    .with_file_name(format!("{}{}", stem, FAILURES_SUFFIX))
+    .to_string_lossy()
+    .into_owned()
+}
+
+fn read_json(fname: &str) -> Result<Vec<CreateAwardRequest>, String> {
+  let content =
+    std::fs::read_to_string(fname).map_err(|e| format!("Could not read {}: {}", fname, e))?;
+  serde_json::from_str(&content).map_err(|e| format!("Invalid awards in {}: {}", fname, e))
+}
+
+fn read_csv(fname: &str) -> Result<Vec<CreateAwardRequest>, String> {
+  let file = std::fs::File::open(fname).map_err(|e| format!("Could not read {}: {}", fname, e))?;
+  parse_csv(file).map_err(|errors| {
+    format!(
+      "Invalid awards in {}:\n  - {}",
+      fname,
+      errors.join("\n  - ")
+    )
+  })
+}
+
+/// Groups the rows of each award, which repeat its details once per classification.
+pub fn parse_csv<R: std::io::Read>(input: R) -> Result<Vec<CreateAwardRequest>, Vec<String>> {
+  let mut reader = csv::Reader::from_reader(input);
+  let mut awards: Vec<CreateAwardRequest> = Vec::new();
+  let mut errors = Vec::new();
+  for (i, row) in reader.deserialize::<CsvRow>().enumerate() {
+    let line = i + 2;
+    let row = match row {
+      Ok(r) => r,
+      Err(e) => {
+        errors.push(format!("line {}: {}", line, e));
+        continue;
+      }
+    };
+
+    let award = match csv_award(&row) {
+      Ok(a) => a,
+      Err(e) => {
+        errors.push(format!("line {}: {}", line, e));
+        continue;
+      }
+    };
+    let CsvRow {
+      classification_id,
+      classification_title,
+      classification_active,
+      classification_note,
+      ..
+    } = row;
+    let classification = classification_id.map(|id| AwardClassification {
+      id,
+      title: classification_title.unwrap_or_default(),
+      active: classification_active.unwrap_or(true),
+      note: classification_note.unwrap_or_default(),
+    });
+
+    match awards
+      .iter_mut()
+      .find(|a| a.external_id == award.external_id)
+    {
+      Some(existing) => {
+        if !same_award(existing, &award) {
+          errors.push(format!(
+            "line {}: award {} has different details than on an earlier line",
+            line, award.external_id
+          ));
+          continue;
+        }
+        existing.classifications.extend(classification);
+      }
+      None => {
+        let mut award = award;
+        award.classifications.extend(classification);
+        awards.push(award);
+      }
+    }
+  }
+
+  if errors.is_empty() {
+    Ok(awards)
+  } else {
+    Err(errors)
+  }
+}
+
+fn csv_award(row: &CsvRow) -> Result<CreateAwardRequest, String> {
+  let alternate_ids = match &row.alternate_ids {
+    Some(ids) => ids
+      .split(';')
+      .map(|i| match i.trim().split_once(':') {
+        Some((id_type, id)) => Ok(AwardAlternateId {
+          id: String::from(id),
+          id_type: String::from(id_type),
+        }),
+        None => Err(format!("alternate id '{}' must be TYPE:ID", i)),
+      })
+      .collect::<Result<Vec<_>, _>>()?,
+    None => Vec::new(),
+  };
+
+  Ok(CreateAwardRequest {
+    external_id: row.external_id.clone(),
+    name: row.name.clone(),
+    industry_name: row.industry_name.clone(),
+    common_rule: row.common_rule.clone(),
+    alternate_ids,
+    operative_date: date(&row.operative_date)?,
+    expired_date: match &row.expired_date {
+      Some(d) => Some(date(d)?),
+      None => None,
+    },
+    classifications: Vec::new(),
+  })
+}
+
+fn same_award(a: &CreateAwardRequest, b: &CreateAwardRequest) -> bool {
+  a.name == b.name
+    && a.industry_name == b.industry_name
+    && a.common_rule == b.common_rule
+    && a.operative_date == b.operative_date
+    && a.expired_date == b.expired_date
+    && (b.alternate_ids.is_empty() || same_alt_ids(&a.alternate_ids, &b.alternate_ids))
+}
+
+fn same_alt_ids(a: &[AwardAlternateId], b: &[AwardAlternateId]) -> bool {
+  a.len() == b.len()
+    && a
+      .iter()
+      .zip(b)
+      .all(|(x, y)| x.id == y.id && x.id_type == y.id_type)
+}
+
+fn validate(awards: &[CreateAwardRequest]) -> Result<(), String> {
+  let mut errors = Vec::new();
+  let mut external_ids = HashSet::new();
+  for (i, award) in awards.iter().enumerate() {
+    if !external_ids.insert(&award.external_id) {
+      errors.push(format!(
+        "award #{} ({}): listed more than once",
+        i + 1,
+        award.external_id
+      ));
+    }
+    for e in award.validate() {
+      errors.push(format!("award #{} ({}): {}", i + 1, award.external_id, e));
+    }
+  }
+
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(format!(
+      "Validation failed, nothing was imported:\n  - {}",
+      errors.join("\n  - ")
+    ))
+  }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+  DateTime::parse_from_rfc3339(value)
+    .ok()
+    .map(|d| d.with_timezone(&Utc))
+}
+
+fn plan(client: &ApiClient, award: &CreateAwardRequest) -> Result<Plan, String> {
+  let existing: Award = match find_by_external_id(client, &award.external_id)? {
+    Some(a) => a,
+    None => return Ok(Plan::Create),
+  };
+
+  let mut immutable = Vec::new();
+  if existing.name != award.name {
+    immutable.push(String::from("name"));
+  }
+  if existing.industry_name != award.industry_name {
+    immutable.push(String::from("industryName"));
+  }
+  if existing.common_rule != award.common_rule {
+    immutable.push(String::from("commonRule"));
+  }
+  if parse_date(&existing.operative_date) != Some(award.operative_date) {
+    immutable.push(String::from("operativeDate"));
+  }
+
+  let mut changes = Vec::new();
+  for alt_id in &award.alternate_ids {
+    let exists = existing
+      .alternate_ids
+      .iter()
+      .any(|i| i.id == alt_id.id && i.id_type == alt_id.id_type);
+    if !exists {
+      changes.push(Change {
+        description: format!("add alternate id {}:{}", alt_id.id_type, alt_id.id),
+        method: Method::POST,
+        segments: vec![String::from("alternateIds")],
+        body: Some(serde_json::to_value(alt_id).map_err(|e| e.to_string())?),
+      });
+    }
+  }
+
+  let existing_expiry = existing.expired_date.as_deref().and_then(parse_date);
+  if existing_expiry != award.expired_date {
+    changes.push(match award.expired_date {
+      Some(d) => Change {
+        description: format!("set expiry {}", d.format("%Y-%m-%d")),
+        method: Method::PUT,
+        segments: vec![String::from("expired")],
+        body: Some(json!({ "expiredAt": d })),
+      },
+      None => Change {
+        description: String::from("clear expiry"),
+        method: Method::DELETE,
+        segments: vec![String::from("expired")],
+        body: None,
+      },
+    });
+  }
+
+  for c in &award.classifications {
+    let current = match existing.classifications.iter().find(|e| e.id == c.id) {
+      Some(e) => e,
+      None => {
+        changes.push(Change {
+          description: format!("add classification {}", c.id),
+          method: Method::POST,
+          segments: vec![String::from("classifications")],
+          body: Some(serde_json::to_value(c).map_err(|e| e.to_string())?),
+        });
+        continue;
+      }
+    };
+
+    if current.title != c.title {
+      immutable.push(format!("classification {} title", c.id));
+    }
+    if current.active != c.active {
+      changes.push(Change {
+        description: format!(
+          "mark classification {} {}",
+          c.id,
+          if c.active { "active" } else { "inactive" }
+        ),
+        method: Method::PUT,
+        segments: vec![
+          String::from("classifications"),
+          c.id.clone(),
+          String::from("active"),
+        ],
+        body: Some(json!({ "active": c.active })),
+      });
+    }
+    if current.note != c.note {
+      changes.push(Change {
+        description: format!("update classification {} note", c.id),
+        method: Method::PUT,
+        segments: vec![
+          String::from("classifications"),
+          c.id.clone(),
+          String::from("note"),
+        ],
+        body: Some(json!({ "note": c.note })),
+      });
+    }
+  }
+
+  if !immutable.is_empty() {
+    return Err(format!(
+      "{} differ from the server and cannot be updated",
+      immutable.join(", ")
+    ));
+  }
+
+  if changes.is_empty() {
+    Ok(Plan::Unchanged)
+  } else {
+    Ok(Plan::Update(existing.id, changes))
+  }
+}
+
+fn apply(
+  client: &ApiClient,
+  award: &CreateAwardRequest,
+  plan: Plan,
+  dry_run: bool,
+) -> Result<(Plan, String), String> {
+  let outcome = match &plan {
+    Plan::Create if dry_run => String::from("would create"),
+    Plan::Create => {
+      let body = serde_json::to_value(award).map_err(|e| e.to_string())?;
+      client.call(Method::POST, "/awards", Some(&body))?;
+      String::from("created")
+    }
+    Plan::Update(_, changes) if dry_run => {
+      let descriptions: Vec<&str> = changes.iter().map(|c| c.description.as_str()).collect();
+      format!("would {}", descriptions.join(", "))
+    }
+    Plan::Update(award_id, changes) => {
+      for change in changes {
+        let mut segments = vec!["awards", award_id.as_str()];
+        segments.extend(change.segments.iter().map(|s| s.as_str()));
+        client
+          .call(
+            change.method.clone(),
+            &ApiClient::path(&segments, &[]),
+            change.body.as_ref(),
+          )
+          .map_err(|e| format!("{} ({})", e, change.description))?;
+      }
+      format!("updated ({} changes)", changes.len())
+    }
+    Plan::Unchanged => String::from("unchanged"),
+  };
+
+  Ok((plan, outcome))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HEADER: &str = "external_id,name,industryName,commonRule,operativeDate,expiredDate,\
+    alternateIds,classificationId,classificationTitle,classificationActive,classificationNote\n";
+
+  fn parse(rows: &str) -> Result<Vec<CreateAwardRequest>, Vec<String>> {
+    parse_csv(format!("{}{}", HEADER, rows).as_bytes())
+  }
+
+  #[test]
+  fn rows_of_an_award_are_grouped_into_its_classifications() {
+    let awards = parse(
+      "MA000001,Clerks,Clerical,,2020-01-01,,ORIGINAL_PRINT_ID:AP1;ORIGINATING_MATTER:M2,\
+       C1,Level 1,true,\n\
+       MA000001,Clerks,Clerical,,2020-01-01,,,C2,Level 2,false,Retired\n\
+       MA000002,Cleaners,Cleaning,CR1,2020-01-01T09:30:00+10:00,2021-06-30,,,,,\n",
+    )
+    .unwrap();
+
+    assert_eq!(awards.len(), 2);
+    let clerks = &awards[0];
+    assert_eq!(clerks.external_id, "MA000001");
+    assert_eq!(clerks.alternate_ids.len(), 2);
+    assert_eq!(clerks.alternate_ids[1].id_type, "ORIGINATING_MATTER");
+    assert_eq!(clerks.alternate_ids[1].id, "M2");
+    let classifications: Vec<(&str, bool, &str)> = clerks
+      .classifications
+      .iter()
+      .map(|c| (c.id.as_str(), c.active, c.note.as_str()))
+      .collect();
+    assert_eq!(
+      classifications,
+      vec![("C1", true, ""), ("C2", false, "Retired")]
+    );
+
+    let cleaners = &awards[1];
+    assert_eq!(cleaners.common_rule.as_deref(), Some("CR1"));
+    assert_eq!(
+      cleaners.operative_date,
+      date("2019-12-31T23:30:00Z").unwrap()
+    );
+    assert_eq!(cleaners.expired_date, Some(date("2021-06-30").unwrap()));
+    assert!(cleaners.classifications.is_empty());
+  }
+
+  #[test]
+  fn classifications_default_to_active() {
+    let awards = parse("MA000001,Clerks,Clerical,,2020-01-01,,,C1,Level 1,,\n").unwrap();
+    assert!(awards[0].classifications[0].active);
+  }
+
+  #[test]
+  fn errors_are_reported_by_line() {
+    let errors = parse(
+      "MA000001,Clerks,Clerical,,2020-01-01,,,C1,Level 1,true,\n\
+       MA000001,Clerks Renamed,Clerical,,2020-01-01,,,C2,Level 2,true,\n\
+       MA000002,Cleaners,Cleaning,,01/01/2020,,,,,,\n\
+       MA000003,Nurses,Health,,2020-01-01,,AP1,,,,\n\
+       MA000004,Vets,Health,,2020-01-01,,,C1,Level 1,maybe,\n",
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].starts_with("line 3: award MA000001 has different details"));
+    assert!(errors[1].starts_with("line 4: Invalid date '01/01/2020'"));
+    assert_eq!(errors[2], "line 5: alternate id 'AP1' must be TYPE:ID");
+    assert!(errors[3].starts_with("line 6: "));
+  }
+
+  #[test]
+  fn later_rows_may_leave_out_the_alternate_ids() {
+    let awards = parse(
+      "MA000001,Clerks,Clerical,,2020-01-01,,ORIGINAL_PRINT_ID:AP1,C1,Level 1,true,\n\
+       MA000001,Clerks,Clerical,,2020-01-01,,,C2,Level 2,true,\n",
+    )
+    .unwrap();
+    assert_eq!(awards.len(), 1);
+    assert_eq!(awards[0].classifications.len(), 2);
+  }
+
+  #[test]
+  fn validate_reports_duplicate_awards() {
+    let awards = parse(
+      "MA000001,Clerks,Clerical,,2020-01-01,,,,,,\n\
+       MA000002,Cleaners,Cleaning,,2020-01-01,,,,,,\n",
+    )
+    .unwrap();
+    let mut duplicated = awards.clone();
+    duplicated.push(awards[0].clone());
+
+    assert!(validate(&awards).is_ok());
+    let error = validate(&duplicated).unwrap_err();
+    assert!(error.contains("award #3 (MA000001): listed more than once"));
+  }
+
+  #[test]
+  fn failures_are_written_beside_the_input() {
+    assert_eq!(failures_file("awards.csv"), "awards.failed.json");
+    assert_eq!(failures_file("in/awards.json"), "in/awards.failed.json");
+    assert_eq!(
+      failures_file("awards.failed.json"),
+      "awards.failed.json"
+    );
+  }
+}
@@ -0,0 +1,253 @@
+use super::api::{argument, json_arg, ApiCommand, ARG_JSON};
+use crate::client::ApiClient;
+use crate::commands::{Command, SelectCommand};
+use crate::domain::{Award, Config};
+use crate::output;
+use chrono::{DateTime, Utc};
+use clap::{App, ArgMatches};
+use reqwest::Method;
+
+mod create;
+mod csv_row;
+mod export;
+mod import;
+mod read;
+mod update;
+
+/// Subcommands whose first argument is an award id.
+pub const AWARD_ID_SUBCOMMANDS: &[&str] = &[
+  "get",
+  "add-classification",
+  "add-alt-id",
+  "set-expiry",
+  "clear-expiry",
+  "classification-status",
+  "classification-note",
+];
+
+pub struct AwardCommand {
+  subcommands: SelectCommand,
+}
+
+impl AwardCommand {
+  pub fn new() -> AwardCommand {
+    AwardCommand {
+      subcommands: SelectCommand::new()
+        .command(
+          "create",
+          ApiCommand::new(create::definition, create::create),
+        )
+        .command(
+          "import",
+          ApiCommand::new(import::definition, import::import),
+        )
+        .command(
+          "export",
+          ApiCommand::new(export::definition, export::export),
+        )
+        .command("get", ApiCommand::new(read::get_definition, read::get))
+        .command("list", ApiCommand::new(read::list_definition, read::list))
+        .command(
+          "add-classification",
+          ApiCommand::new(
+            update::add_classification_definition,
+            update::add_classification,
+          ),
+        )
+        .command(
+          "add-alt-id",
+          ApiCommand::new(update::add_alt_id_definition, update::add_alt_id),
+        )
+        .command(
+          "set-expiry",
+          ApiCommand::new(update::set_expiry_definition, update::set_expiry),
+        )
+        .command(
+          "clear-expiry",
+          ApiCommand::new(update::clear_expiry_definition, update::clear_expiry),
+        )
+        .command(
+          "classification-status",
+          ApiCommand::new(
+            update::classification_status_definition,
+            update::classification_status,
+          ),
+        )
+        .command(
+          "classification-note",
+          ApiCommand::new(
+            update::classification_note_definition,
+            update::classification_note,
+          ),
+        ),
+    }
+  }
+}
+
+impl Command for AwardCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    self
+      .subcommands
+      .definition(app.about("Manages awards").arg(json_arg()))
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    self.subcommands.execute(matches, config)
+  }
+
+  fn subcommand_names(&self) -> Vec<&'static str> {
+    self.subcommands.names()
+  }
+}
+
+fn date_validator(value: String) -> Result<(), String> {
+  date(&value).map(|_| ())
+}
+
+fn date(value: &str) -> Result<DateTime<Utc>, String> {
+  let parsed = if value.len() == 10 {
+    DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", value))
+  } else {
+    DateTime::parse_from_rfc3339(value)
+  };
+  parsed
+    .map(|d| d.with_timezone(&Utc))
+    .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD or RFC 3339", value))
+}
+
+fn fetch_award(client: &ApiClient, award_id: &str) -> Result<Award, String> {
+  let response = client.call(
+    Method::GET,
+    &ApiClient::path(&["awards", award_id], &[]),
+    None,
+  )?;
+  ApiClient::parse(response)
+}
+
+fn find_by_external_id(client: &ApiClient, external_id: &str) -> Result<Option<Award>, String> {
+  let path = ApiClient::path(&["awards"], &[("externalId", external_id)]);
+  let awards: Vec<Award> = ApiClient::parse(client.call(Method::GET, &path, None)?)?;
+  Ok(awards.into_iter().next())
+}
+
+fn print_award(matches: &ArgMatches, award: &Award) -> Result<(), String> {
+  if matches.is_present(ARG_JSON) {
+    return output::print_json(award);
+  }
+
+  output::print_table(
+    &["FIELD", "VALUE"],
+    &[
+      vec![String::from("id"), award.id.clone()],
+      vec![String::from("external_id"), award.external_id.clone()],
+      vec![String::from("name"), award.name.clone()],
+      vec![String::from("industryName"), award.industry_name.clone()],
+      vec![
+        String::from("commonRule"),
+        award.common_rule.clone().unwrap_or_default(),
+      ],
+      vec![String::from("operativeDate"), award.operative_date.clone()],
+      vec![
+        String::from("expiredDate"),
+        award.expired_date.clone().unwrap_or_default(),
+      ],
+    ],
+  );
+
+  if !award.alternate_ids.is_empty() {
+    println!();
+    let rows: Vec<Vec<String>> = award
+      .alternate_ids
+      .iter()
+      .map(|i| vec![i.id_type.clone(), i.id.clone()])
+      .collect();
+    output::print_table(&["ALT ID TYPE", "ALT ID"], &rows);
+  }
+
+  if !award.classifications.is_empty() {
+    println!();
+    let rows: Vec<Vec<String>> = award
+      .classifications
+      .iter()
+      .map(|c| {
+        vec![
+          c.id.clone(),
+          c.title.clone(),
+          c.active.to_string(),
+          c.note.clone(),
+        ]
+      })
+      .collect();
+    output::print_table(&["CLASSIFICATION", "TITLE", "ACTIVE", "NOTE"], &rows);
+  }
+
+  Ok(())
+}
+
+fn print_awards(matches: &ArgMatches, awards: &[Award]) -> Result<(), String> {
+  if matches.is_present(ARG_JSON) {
+    return output::print_json(&awards);
+  }
+
+  let rows: Vec<Vec<String>> = awards
+    .iter()
+    .map(|a| {
+      vec![
+        a.id.clone(),
+        a.external_id.clone(),
+        a.name.clone(),
+        a.operative_date.clone(),
+        a.expired_date.clone().unwrap_or_default(),
+        a.classifications.len().to_string(),
+      ]
+    })
+    .collect();
+  output::print_table(
+    &[
+      "ID",
+      "EXTERNAL ID",
+      "NAME",
+      "OPERATIVE",
+      "EXPIRED",
+      "CLASSIFICATIONS",
+    ],
+    &rows,
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  #[test]
+  fn dates_are_whole_days_in_utc() {
+    assert_eq!(date("2020-07-01").unwrap(), Utc.with_ymd_and_hms(2020, 7, 1, 0, 0, 0).unwrap());
+  }
+
+  #[test]
+  fn rfc3339_dates_are_converted_to_utc() {
+    assert_eq!(
+      date("2020-07-01T09:30:00+10:00").unwrap(),
+      Utc.with_ymd_and_hms(2020, 6, 30, 23, 30, 0).unwrap()
+    );
+    assert_eq!(
+      date("2020-07-01T09:30:00Z").unwrap(),
+      Utc.with_ymd_and_hms(2020, 7, 1, 9, 30, 0).unwrap()
+    );
+  }
+
+  #[test]
+  fn other_dates_are_rejected() {
+    for value in &["01/07/2020", "2020-7-1", "2020-07-32", "2020-07-01 09:30", ""] {
+      assert_eq!(
+        date(value).err(),
+        Some(format!(
+          "Invalid date '{}', expected YYYY-MM-DD or RFC 3339",
+          value
+        ))
+      );
+    }
+  }
+}
@@ -0,0 +1,40 @@
+use super::{argument, fetch_award, print_award, print_awards};
+use crate::client::ApiClient;
+use crate::domain::Award;
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+
+const ARG_EXTERNAL_ID: &str = "external-id";
+
+pub fn get_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Shows an award")
+    .arg(Arg::with_name("awardId").required(true))
+}
+
+pub fn get(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let award = fetch_award(client, &award_id)?;
+  print_award(matches, &award)
+}
+
+pub fn list_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app.about("Lists awards").arg(
+    Arg::with_name(ARG_EXTERNAL_ID)
+      .long(ARG_EXTERNAL_ID)
+      .value_name("ID")
+      .help("Only lists the award with this external id"),
+  )
+}
+
+pub fn list(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let external_id = matches.value_of(ARG_EXTERNAL_ID);
+  let query: Vec<(&str, &str)> = match &external_id {
+    Some(e) => vec![("externalId", e)],
+    None => Vec::new(),
+  };
+
+  let path = ApiClient::path(&["awards"], &query);
+  let awards: Vec<Award> = ApiClient::parse(client.call(Method::GET, &path, None)?)?;
+  print_awards(matches, &awards)
+}
@@ -0,0 +1,200 @@
+use super::{argument, date, date_validator, fetch_award, print_award};
+use crate::client::ApiClient;
+use crate::domain::{AwardAlternateId, AwardClassification, ALT_ID_TYPES};
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+use serde_json::{json, Value};
+
+const ARG_TITLE: &str = "title";
+const ARG_NOTE: &str = "note";
+const ARG_INACTIVE: &str = "inactive";
+const ARG_TYPE: &str = "type";
+const STATUS_ACTIVE: &str = "active";
+const STATUS_INACTIVE: &str = "inactive";
+
+fn update(
+  matches: &ArgMatches,
+  client: &ApiClient,
+  method: Method,
+  award_id: &str,
+  segments: &[&str],
+  body: Option<Value>,
+) -> Result<(), String> {
+  let mut path = vec!["awards", award_id];
+  path.extend_from_slice(segments);
+  client.call(method, &ApiClient::path(&path, &[]), body.as_ref())?;
+
+  let award = fetch_award(client, award_id)?;
+  print_award(matches, &award)
+}
+
+fn award_id_arg() -> Arg<'static, 'static> {
+  Arg::with_name("awardId").required(true)
+}
+
+fn classification_id_arg() -> Arg<'static, 'static> {
+  Arg::with_name("classificationId").required(true)
+}
+
+pub fn add_classification_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Adds a classification to an award")
+    .arg(award_id_arg())
+    .arg(classification_id_arg())
+    .arg(
+      Arg::with_name(ARG_TITLE)
+        .long(ARG_TITLE)
+        .value_name("TITLE")
+        .required(true),
+    )
+    .arg(Arg::with_name(ARG_NOTE).long(ARG_NOTE).value_name("NOTE"))
+    .arg(
+      Arg::with_name(ARG_INACTIVE)
+        .long(ARG_INACTIVE)
+        .help("Adds the classification as inactive"),
+    )
+}
+
+pub fn add_classification(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let classification = AwardClassification {
+    id: argument(matches, "classificationId")?,
+    title: argument(matches, ARG_TITLE)?,
+    active: !matches.is_present(ARG_INACTIVE),
+    note: String::from(matches.value_of(ARG_NOTE).unwrap_or_default()),
+  };
+
+  let body = serde_json::to_value(&classification).map_err(|e| e.to_string())?;
+  update(
+    matches,
+    client,
+    Method::POST,
+    &award_id,
+    &["classifications"],
+    Some(body),
+  )
+}
+
+pub fn add_alt_id_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Adds an alternate id to an award")
+    .arg(award_id_arg())
+    .arg(Arg::with_name("altId").required(true))
+    .arg(
+      Arg::with_name(ARG_TYPE)
+        .long(ARG_TYPE)
+        .value_name("TYPE")
+        .required(true)
+        .possible_values(ALT_ID_TYPES),
+    )
+}
+
+pub fn add_alt_id(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let alt_id = AwardAlternateId {
+    id: argument(matches, "altId")?,
+    id_type: argument(matches, ARG_TYPE)?,
+  };
+
+  let body = serde_json::to_value(&alt_id).map_err(|e| e.to_string())?;
+  update(
+    matches,
+    client,
+    Method::POST,
+    &award_id,
+    &["alternateIds"],
+    Some(body),
+  )
+}
+
+pub fn set_expiry_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Sets the expiry date of an award")
+    .arg(award_id_arg())
+    .arg(
+      Arg::with_name("date")
+        .required(true)
+        .validator(date_validator)
+        .help("YYYY-MM-DD or RFC 3339"),
+    )
+}
+
+pub fn set_expiry(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let expired_at = date(&argument(matches, "date")?)?;
+  update(
+    matches,
+    client,
+    Method::PUT,
+    &award_id,
+    &["expired"],
+    Some(json!({ "expiredAt": expired_at })),
+  )
+}
+
+pub fn clear_expiry_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Removes the expiry date of an award")
+    .arg(award_id_arg())
+}
+
+pub fn clear_expiry(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  update(
+    matches,
+    client,
+    Method::DELETE,
+    &award_id,
+    &["expired"],
+    None,
+  )
+}
+
+pub fn classification_status_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Activates or deactivates a classification")
+    .arg(award_id_arg())
+    .arg(classification_id_arg())
+    .arg(
+      Arg::with_name("status")
+        .required(true)
+        .possible_values(&[STATUS_ACTIVE, STATUS_INACTIVE]),
+    )
+}
+
+pub fn classification_status(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let classification_id = argument(matches, "classificationId")?;
+  let active = argument(matches, "status")? == STATUS_ACTIVE;
+
+  update(
+    matches,
+    client,
+    Method::PUT,
+    &award_id,
+    &["classifications", &classification_id, "active"],
+    Some(json!({ "active": active })),
+  )
+}
+
+pub fn classification_note_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Updates the note of a classification")
+    .arg(award_id_arg())
+    .arg(classification_id_arg())
+    .arg(Arg::with_name(ARG_NOTE).required(true))
+}
+
+pub fn classification_note(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let award_id = argument(matches, "awardId")?;
+  let classification_id = argument(matches, "classificationId")?;
+  let note = argument(matches, ARG_NOTE)?;
+  update(
+    matches,
+    client,
+    Method::PUT,
+    &award_id,
+    &["classifications", &classification_id, "note"],
+    Some(json!({ "note": note })),
+  )
+}
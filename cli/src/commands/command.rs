@@ -1,39 +1,63 @@
-use super::not_found::CommandNotFound;
 use crate::domain::Config;
-use crate::ArgsParser;
-use std::collections::HashMap;
+use clap::{App, AppSettings, ArgMatches, SubCommand};
 
 pub trait Command {
-  fn execute(&self, parser: ArgsParser, config: Config) -> ();
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static>;
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String>;
+
+  /// Names of the subcommands, offered as completions by the shell.
+  fn subcommand_names(&self) -> Vec<&'static str> {
+    Vec::new()
+  }
 }
 
 pub struct SelectCommand {
-  commands: HashMap<String, Box<dyn Command>>,
+  commands: Vec<(&'static str, Box<dyn Command>)>,
 }
 
 impl SelectCommand {
   pub fn new() -> SelectCommand {
     SelectCommand {
-      commands: HashMap::new(),
+      commands: Vec::new(),
     }
   }
 
-  pub fn command<T>(&mut self, command_str: &str, command: T) -> &mut SelectCommand
+  pub fn command<T>(mut self, command_str: &'static str, command: T) -> SelectCommand
   where
     T: Command + 'static,
   {
+    self.commands.push((command_str, Box::new(command)));
+
+    self
+  }
+
+  pub fn names(&self) -> Vec<&'static str> {
+    self.commands.iter().map(|(name, _)| *name).collect()
+  }
+
+  pub fn get(&self, name: &str) -> Option<&dyn Command> {
     self
       .commands
-      .insert(String::from(command_str), Box::new(command));
+      .iter()
+      .find(|(n, _)| *n == name)
+      .map(|(_, c)| c.as_ref())
+  }
 
+  pub fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
     self
+      .commands
+      .iter()
+      .fold(app, |app, (name, command)| {
+        app.subcommand(command.definition(SubCommand::with_name(name)))
+      })
+      .setting(AppSettings::SubcommandRequiredElseHelp)
   }
 
-  pub fn select(&mut self, command: &str) -> Box<dyn Command> {
-    if let Some(c) = self.commands.remove(command) {
-      c
-    } else {
-      Box::new(CommandNotFound::new(command))
+  pub fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let (name, sub_matches) = matches.subcommand();
+    match (self.get(name), sub_matches) {
+      (Some(c), Some(m)) => c.execute(m, config),
+      _ => Err(format!("Could not recognise command '{}'", name)),
     }
   }
 }
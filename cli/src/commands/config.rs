@@ -0,0 +1,142 @@
+use crate::args;
+use crate::commands::Command;
+use crate::config;
+use crate::domain::{Config, RemoteConfig};
+use crate::output;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use reqwest::Url;
+
+const COMMAND_ADD: &str = "add";
+const COMMAND_LIST: &str = "list";
+const COMMAND_USE: &str = "use";
+const ARG_NAME: &str = "name";
+const ARG_URL: &str = "url";
+const ARG_SELECT: &str = "select";
+
+pub struct ConfigCommand {}
+
+impl ConfigCommand {
+  pub fn new() -> ConfigCommand {
+    ConfigCommand {}
+  }
+
+  fn config_file(matches: &ArgMatches) -> Result<String, String> {
+    args::config_file(matches)
+      .ok_or_else(|| String::from("Could not find config file, pass --config <FILE>"))
+  }
+
+  fn add(matches: &ArgMatches, mut config: Config) -> Result<(), String> {
+    let fname = ConfigCommand::config_file(matches)?;
+    let name = matches.value_of(ARG_NAME).unwrap_or_default();
+    let url = matches.value_of(ARG_URL).unwrap_or_default();
+
+    let replaced = config
+      .profiles
+      .insert(
+        String::from(name),
+        RemoteConfig {
+          url: String::from(url),
+        },
+      )
+      .is_some();
+    if matches.is_present(ARG_SELECT) {
+      config.current_profile = Some(String::from(name));
+    }
+    config::save_config(&fname, &config)?;
+
+    let verb = if replaced { "Updated" } else { "Added" };
+    println!("{} profile '{}' ({})", verb, name, url);
+    Ok(())
+  }
+
+  fn list(matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let current = config::profile_name(&config, matches.value_of(args::ARG_PROFILE));
+    let mut rows: Vec<Vec<String>> = config
+      .profiles
+      .iter()
+      .map(|(name, remote)| (name.as_str(), remote))
+      .chain(
+        config
+          .remote
+          .iter()
+          .filter(|_| !config.profiles.contains_key(config::DEFAULT_PROFILE))
+          .map(|remote| (config::DEFAULT_PROFILE, remote)),
+      )
+      .map(|(name, remote)| {
+        let marker = if name == current { "*" } else { "" };
+        vec![String::from(marker), String::from(name), remote.url.clone()]
+      })
+      .collect();
+    rows.sort_by(|a, b| a[1].cmp(&b[1]));
+
+    if rows.is_empty() {
+      println!("No profiles configured, add one with `ausawards config add <name> <url>`");
+      return Ok(());
+    }
+    output::print_table(&["", "NAME", "URL"], &rows);
+    Ok(())
+  }
+
+  fn select(matches: &ArgMatches, mut config: Config) -> Result<(), String> {
+    let fname = ConfigCommand::config_file(matches)?;
+    let name = matches.value_of(ARG_NAME).unwrap_or_default();
+    config::profile(&config, name)?;
+
+    config.current_profile = Some(String::from(name));
+    config::save_config(&fname, &config)?;
+    println!("Using profile '{}'", name);
+    Ok(())
+  }
+}
+
+fn url_validator(value: String) -> Result<(), String> {
+  match Url::parse(&value) {
+    Ok(u) if u.scheme() == "http" || u.scheme() == "https" => Ok(()),
+    _ => Err(format!(
+      "Invalid URL '{}', expected http(s)://host[:port]",
+      value
+    )),
+  }
+}
+
+impl Command for ConfigCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app
+      .about("Manages profiles for multiple environments")
+      .setting(AppSettings::SubcommandRequiredElseHelp)
+      .subcommand(
+        SubCommand::with_name(COMMAND_ADD)
+          .about("Adds or updates a profile")
+          .arg(Arg::with_name(ARG_NAME).required(true))
+          .arg(
+            Arg::with_name(ARG_URL)
+              .required(true)
+              .validator(url_validator),
+          )
+          .arg(
+            Arg::with_name(ARG_SELECT)
+              .long(ARG_SELECT)
+              .help("Also makes this the selected profile"),
+          ),
+      )
+      .subcommand(SubCommand::with_name(COMMAND_LIST).about("Lists profiles"))
+      .subcommand(
+        SubCommand::with_name(COMMAND_USE)
+          .about("Selects the profile used by default")
+          .arg(Arg::with_name(ARG_NAME).required(true)),
+      )
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    match matches.subcommand() {
+      (COMMAND_ADD, Some(m)) => ConfigCommand::add(m, config),
+      (COMMAND_LIST, Some(m)) => ConfigCommand::list(m, config),
+      (COMMAND_USE, Some(m)) => ConfigCommand::select(m, config),
+      (name, _) => Err(format!("Could not recognise command '{}'", name)),
+    }
+  }
+
+  fn subcommand_names(&self) -> Vec<&'static str> {
+    vec![COMMAND_ADD, COMMAND_LIST, COMMAND_USE]
+  }
+}
@@ -1,12 +1,55 @@
-use crate::args::ArgsParser;
+use crate::client::ApiClient;
 use crate::commands::Command;
 use crate::domain::Config;
-use reqwest;
+use crate::output;
+use crate::version;
+use chrono::{SecondsFormat, Utc};
+use clap::{App, Arg, ArgMatches};
+use reqwest::{Method, StatusCode};
 use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+const ARG_WATCH: &str = "watch";
+const ARG_INTERVAL: &str = "interval";
+const STATUS_UP: &str = "UP";
+const DEFAULT_INTERVAL_SEC: u64 = 30;
+
+#[derive(Deserialize)]
+struct HealthResponse {
+  name: String,
+  version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DependencyStatus {
+  name: String,
+  status: String,
+  #[serde(rename = "latencyMs")]
+  latency_ms: u128,
+  error: Option<String>,
+}
 
 #[derive(Deserialize)]
-struct Response {
+struct ReadinessResponse {
+  dependencies: Vec<DependencyStatus>,
+}
+
+struct Check {
   name: String,
+  passed: Option<bool>,
+  latency_ms: Option<u128>,
+  detail: String,
+}
+
+impl Check {
+  fn new(name: &str, passed: Option<bool>, latency_ms: Option<u128>, detail: String) -> Check {
+    Check {
+      name: String::from(name),
+      passed,
+      latency_ms,
+      detail,
+    }
+  }
 }
 
 pub struct HealthCheckCommand {}
@@ -15,25 +58,182 @@ impl HealthCheckCommand {
   pub fn new() -> HealthCheckCommand {
     HealthCheckCommand {}
   }
+
+  fn timed<T>(call: impl FnOnce() -> Result<T, String>) -> (Result<T, String>, u128) {
+    let started = Instant::now();
+    let result = call();
+    (result, started.elapsed().as_millis())
+  }
+
+  fn run_checks(client: &ApiClient) -> Vec<Check> {
+    let (health, latency) = HealthCheckCommand::timed(|| {
+      client
+        .call_anonymous(Method::GET, "/health", None)
+        .and_then(ApiClient::parse::<HealthResponse>)
+    });
+    let health = match health {
+      Ok(h) => h,
+      Err(e) => return vec![Check::new("server", Some(false), None, e)],
+    };
+    let version = health.version.unwrap_or_else(|| String::from("unknown"));
+    let mut checks = vec![Check::new(
+      "server",
+      Some(true),
+      Some(latency),
+      format!("{} version {}", health.name, version),
+    )];
+
+    let (server_version, latency) = HealthCheckCommand::timed(|| version::server_version(client));
+    checks.push(match server_version {
+      Ok(v) => match version::incompatibility(&v) {
+        Some(w) => Check::new("api version", Some(false), Some(latency), w),
+        None => Check::new(
+          "api version",
+          Some(true),
+          Some(latency),
+          format!(
+            "API {}, schema {}, commit {}",
+            v.api_version,
+            version::schema_version(&v),
+            v.git_commit
+          ),
+        ),
+      },
+      Err(e) => Check::new("api version", Some(false), Some(latency), e),
+    });
+
+    let (readiness, latency) = HealthCheckCommand::timed(|| {
+      client
+        .call_unchecked(Method::GET, "/health/ready", None)
+        .and_then(ApiClient::parse::<ReadinessResponse>)
+    });
+    match readiness {
+      Ok(r) => checks.extend(r.dependencies.into_iter().map(|d| {
+        Check::new(
+          &d.name,
+          Some(d.status == STATUS_UP),
+          Some(d.latency_ms),
+          d.error.unwrap_or_default(),
+        )
+      })),
+      Err(e) => checks.push(Check::new("readiness", Some(false), Some(latency), e)),
+    }
+
+    if client.session().is_none() {
+      checks.push(Check::new(
+        "session",
+        None,
+        None,
+        String::from("not logged in"),
+      ));
+      return checks;
+    }
+    // The stored token is checked as is; refreshing it here would hide an expired token and
+    // rewrite the session file.
+    let (secure, latency) = HealthCheckCommand::timed(|| {
+      client.call_session_unchecked(Method::GET, "/health/secure", None)
+    });
+    checks.push(match secure {
+      Ok(r) if r.status().is_success() => Check::new(
+        "session",
+        Some(true),
+        Some(latency),
+        String::from("token accepted"),
+      ),
+      Ok(r) if r.status() == StatusCode::UNAUTHORIZED => Check::new(
+        "session",
+        Some(false),
+        Some(latency),
+        String::from("token rejected, it may have expired"),
+      ),
+      Ok(r) => Check::new(
+        "session",
+        Some(false),
+        Some(latency),
+        format!("Request failed with status {}", r.status()),
+      ),
+      Err(e) => Check::new("session", Some(false), Some(latency), e),
+    });
+    checks
+  }
+
+  fn check(client: &ApiClient) -> Result<(), String> {
+    let checks = HealthCheckCommand::run_checks(client);
+    let rows: Vec<Vec<String>> = checks
+      .iter()
+      .map(|c| {
+        vec![
+          c.name.clone(),
+          String::from(match c.passed {
+            Some(true) => "OK",
+            Some(false) => "FAILED",
+            None => "SKIPPED",
+          }),
+          c.latency_ms
+            .map(|l| format!("{} ms", l))
+            .unwrap_or_default(),
+          c.detail.clone(),
+        ]
+      })
+      .collect();
+    output::print_table(&["CHECK", "STATUS", "LATENCY", "DETAIL"], &rows);
+
+    let failed: Vec<&str> = checks
+      .iter()
+      .filter(|c| c.passed == Some(false))
+      .map(|c| c.name.as_str())
+      .collect();
+    if failed.is_empty() {
+      Ok(())
+    } else {
+      Err(format!("Health check failed: {}", failed.join(", ")))
+    }
+  }
+
+  fn interval_validator(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+      Ok(s) if s > 0 => Ok(()),
+      _ => Err(String::from("must be a positive number of seconds")),
+    }
+  }
 }
 
 impl Command for HealthCheckCommand {
-  fn execute(&self, _: ArgsParser, config: Config) -> () {
-    let base_url = reqwest::Url::parse(&config.remote.url);
-    if base_url.is_err() {
-      println!("Malformed URL: {}", config.remote.url);
-      return;
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app
+      .about("Checks the server, its dependencies and the stored session")
+      .arg(
+        Arg::with_name(ARG_WATCH)
+          .long(ARG_WATCH)
+          .help("Repeats the checks until interrupted"),
+      )
+      .arg(
+        Arg::with_name(ARG_INTERVAL)
+          .long(ARG_INTERVAL)
+          .value_name("SECONDS")
+          .requires(ARG_WATCH)
+          .validator(HealthCheckCommand::interval_validator)
+          .help("Seconds between checks in watch mode [default: 30]"),
+      )
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, &config)?;
+    if !matches.is_present(ARG_WATCH) {
+      return HealthCheckCommand::check(&client);
     }
-    let base_url = base_url.unwrap();
-
-    let response = reqwest::blocking::get(base_url.join("/health").unwrap())
-      .ok()
-      .map(|r| r.json::<Response>().ok())
-      .flatten();
-    if let Some(r) = response {
-      println!("Successfully connected to {}", r.name);
-    } else {
-      println!("Could not connect to server");
+
+    let interval = matches
+      .value_of(ARG_INTERVAL)
+      .and_then(|i| i.parse::<u64>().ok())
+      .unwrap_or(DEFAULT_INTERVAL_SEC);
+    loop {
+      println!("{}", Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+      if let Err(e) = HealthCheckCommand::check(&client) {
+        eprintln!("Error: {}", e);
+      }
+      println!();
+      std::thread::sleep(Duration::from_secs(interval));
     }
   }
 }
@@ -0,0 +1,87 @@
+use crate::client::ApiClient;
+use crate::commands::Command;
+use crate::domain::{Config, Session};
+use crate::version;
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::Write;
+
+const ARG_LOGIN_ID: &str = "login-id";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LogInResult {
+  Success(Session),
+  MfaRequired {
+    #[serde(rename = "challengeId")]
+    challenge_id: String,
+  },
+}
+
+pub struct LoginCommand {}
+
+impl LoginCommand {
+  pub fn new() -> LoginCommand {
+    LoginCommand {}
+  }
+
+  fn prompt(label: &str) -> Result<String, String> {
+    print!("{}: ", label);
+    std::io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    std::io::stdin()
+      .read_line(&mut line)
+      .map_err(|e| e.to_string())?;
+    Ok(String::from(line.trim()))
+  }
+
+  fn log_in(matches: &ArgMatches, config: &Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, config)?;
+
+    let login_id = match matches.value_of(ARG_LOGIN_ID) {
+      Some(l) => String::from(l),
+      None => LoginCommand::prompt("Login ID")?,
+    };
+    let password = rpassword::read_password_from_tty(Some("Password: "))
+      .map_err(|e| format!("Could not read password: {}", e))?;
+
+    let body = json!({ "loginId": login_id, "password": password });
+    let response = client.call_anonymous(Method::POST, "/session", Some(&body))?;
+    let session = match ApiClient::parse::<LogInResult>(response)? {
+      LogInResult::Success(s) => s,
+      LogInResult::MfaRequired { challenge_id } => {
+        let code = LoginCommand::prompt("MFA code (or recovery code)")?;
+        let body = if code.chars().all(|c| c.is_ascii_digit()) {
+          json!({ "challengeId": challenge_id, "code": code })
+        } else {
+          json!({ "challengeId": challenge_id, "recoveryCode": code })
+        };
+        let response = client.call_anonymous(Method::POST, "/session/mfa", Some(&body))?;
+        ApiClient::parse::<Session>(response)?
+      }
+    };
+
+    client.store_session(session)?;
+    println!("Logged in as {}", login_id);
+    version::warn_if_incompatible(&client);
+    Ok(())
+  }
+}
+
+impl Command for LoginCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app.about("Logs in and stores the session").arg(
+      Arg::with_name(ARG_LOGIN_ID)
+        .long(ARG_LOGIN_ID)
+        .value_name("LOGIN_ID")
+        .help("Login ID, prompted for when omitted"),
+    )
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    LoginCommand::log_in(matches, &config)
+  }
+}
@@ -0,0 +1,38 @@
+use crate::client::ApiClient;
+use crate::commands::Command;
+use crate::domain::Config;
+use clap::{App, ArgMatches};
+use reqwest::Method;
+
+pub struct LogoutCommand {}
+
+impl LogoutCommand {
+  pub fn new() -> LogoutCommand {
+    LogoutCommand {}
+  }
+
+  fn log_out(matches: &ArgMatches, config: &Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, config)?;
+    if client.session().is_none() {
+      println!("Not logged in");
+      return Ok(());
+    }
+
+    let result = client.call(Method::DELETE, "/session", None).map(|_| ());
+    client.clear_session()?;
+    result?;
+
+    println!("Logged out");
+    Ok(())
+  }
+}
+
+impl Command for LogoutCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app.about("Ends the stored session")
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    LogoutCommand::log_out(matches, &config)
+  }
+}
@@ -1,6 +1,22 @@
+mod api;
 mod command;
-mod not_found;
 mod healthcheck;
+mod login;
+mod logout;
+mod award;
+mod config;
+mod role;
+mod shell;
+mod user;
+mod version;
 
+pub use award::AwardCommand;
 pub use command::{Command, SelectCommand};
+pub use config::ConfigCommand;
 pub use healthcheck::HealthCheckCommand;
+pub use login::LoginCommand;
+pub use logout::LogoutCommand;
+pub use role::RoleCommand;
+pub use shell::ShellCommand;
+pub use user::UserCommand;
+pub use version::VersionCommand;
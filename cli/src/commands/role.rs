@@ -0,0 +1,173 @@
+use super::api::{argument, json_arg, ApiCommand, ARG_JSON};
+use super::user::{find_user, print_user};
+use crate::client::ApiClient;
+use crate::commands::{Command, SelectCommand};
+use crate::domain::{Config, Role};
+use crate::output;
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+use serde_json::json;
+
+const ARG_PERMISSION: &str = "permission";
+const ARG_INCLUDE: &str = "include";
+
+pub struct RoleCommand {
+  subcommands: SelectCommand,
+}
+
+impl RoleCommand {
+  pub fn new() -> RoleCommand {
+    RoleCommand {
+      subcommands: SelectCommand::new()
+        .command("list", ApiCommand::new(list_definition, list))
+        .command("create", ApiCommand::new(create_definition, create))
+        .command("grant", ApiCommand::new(grant_definition, grant))
+        .command("revoke", ApiCommand::new(revoke_definition, revoke)),
+    }
+  }
+}
+
+impl Command for RoleCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    self
+      .subcommands
+      .definition(app.about("Manages roles").arg(json_arg()))
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    self.subcommands.execute(matches, config)
+  }
+
+  fn subcommand_names(&self) -> Vec<&'static str> {
+    self.subcommands.names()
+  }
+}
+
+/// Finds a role by id or name.
+pub fn find_role(client: &ApiClient, role: &str) -> Result<Role, String> {
+  let roles: Vec<Role> = ApiClient::parse(client.call(Method::GET, "/roles", None)?)?;
+  roles
+    .into_iter()
+    .find(|r| r.id == role || r.name == role)
+    .ok_or_else(|| format!("Role '{}' was not found", role))
+}
+
+fn print_roles(matches: &ArgMatches, roles: &[Role]) -> Result<(), String> {
+  if matches.is_present(ARG_JSON) {
+    return output::print_json(&roles);
+  }
+
+  let rows: Vec<Vec<String>> = roles
+    .iter()
+    .map(|r| {
+      vec![
+        r.id.clone(),
+        r.name.clone(),
+        r.permissions.join(", "),
+        r.included_role_ids.join(", "),
+      ]
+    })
+    .collect();
+  output::print_table(&["ID", "NAME", "PERMISSIONS", "INCLUDED ROLES"], &rows);
+  Ok(())
+}
+
+fn role_and_user_args(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .arg(
+      Arg::with_name("role")
+        .required(true)
+        .help("Role id or name"),
+    )
+    .arg(
+      Arg::with_name("user")
+        .required(true)
+        .help("User id or login ID"),
+    )
+}
+
+fn list_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app.about("Lists roles")
+}
+
+fn list(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let roles: Vec<Role> = ApiClient::parse(client.call(Method::GET, "/roles", None)?)?;
+  print_roles(matches, &roles)
+}
+
+fn create_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Creates a role")
+    .arg(Arg::with_name("name").required(true))
+    .arg(
+      Arg::with_name(ARG_PERMISSION)
+        .long(ARG_PERMISSION)
+        .value_name("PERMISSION")
+        .required(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Permission granted by the role, e.g. award:create or award:*; may be repeated"),
+    )
+    .arg(
+      Arg::with_name(ARG_INCLUDE)
+        .long(ARG_INCLUDE)
+        .value_name("ROLE")
+        .multiple(true)
+        .number_of_values(1)
+        .help("Role id or name whose permissions are included; may be repeated"),
+    )
+}
+
+fn create(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let name = argument(matches, "name")?;
+  if find_role(client, &name).is_ok() {
+    return Err(format!("Role '{}' already exists", name));
+  }
+  let permissions: Vec<&str> = matches
+    .values_of(ARG_PERMISSION)
+    .map(|p| p.collect())
+    .unwrap_or_default();
+  let included_role_ids = matches
+    .values_of(ARG_INCLUDE)
+    .map(|roles| {
+      roles
+        .map(|r| find_role(client, r).map(|r| r.id))
+        .collect::<Result<Vec<String>, String>>()
+    })
+    .unwrap_or_else(|| Ok(Vec::new()))?;
+
+  let body = json!({
+    "name": name,
+    "permissions": permissions,
+    "includedRoleIds": included_role_ids,
+  });
+  let role: Role = ApiClient::parse(client.call(Method::POST, "/roles", Some(&body))?)?;
+  print_roles(matches, &[role])
+}
+
+fn grant_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  role_and_user_args(app.about("Grants a role to a user"))
+}
+
+fn grant(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  update_grant(matches, client, Method::PUT)
+}
+
+fn revoke_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  role_and_user_args(app.about("Revokes a role from a user"))
+}
+
+fn revoke(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  update_grant(matches, client, Method::DELETE)
+}
+
+fn update_grant(matches: &ArgMatches, client: &ApiClient, method: Method) -> Result<(), String> {
+  let role = find_role(client, &argument(matches, "role")?)?;
+  let user = find_user(client, &argument(matches, "user")?)?;
+  let response = client.call(
+    method,
+    &ApiClient::path(&["users", &user.id, "roles", &role.id], &[]),
+    None,
+  )?;
+  print_user(matches, &ApiClient::parse(response)?)
+}
@@ -0,0 +1,320 @@
+use super::award::AWARD_ID_SUBCOMMANDS;
+use crate::args;
+use crate::client::ApiClient;
+use crate::commands::{Command, SelectCommand};
+use crate::config;
+use crate::domain::{Award, Config};
+use crate::version::{self, VERSION};
+use clap::{App, ArgMatches, ErrorKind};
+use reqwest::Method;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Context, Editor, Helper};
+use std::cell::RefCell;
+use std::path::Path;
+
+const COMMAND_EXIT: &str = "exit";
+const COMMAND_QUIT: &str = "quit";
+const COMMAND_HELP: &str = "help";
+const COMMAND_AWARD: &str = "award";
+
+pub struct ShellCommand {
+  commands: SelectCommand,
+}
+
+impl ShellCommand {
+  pub fn new(commands: SelectCommand) -> ShellCommand {
+    ShellCommand { commands }
+  }
+
+  fn prompt(matches: &ArgMatches) -> String {
+    let profile = config::load_config(args::config_file(matches))
+      .map(|c| config::profile_name(&c, matches.value_of(args::ARG_PROFILE)))
+      .unwrap_or_else(|_| String::from(config::DEFAULT_PROFILE));
+    format!("{} ({})> ", args::APP_NAME, profile)
+  }
+
+  fn run_line(&self, matches: &ArgMatches, line: &str) -> Result<(), String> {
+    let words = shell_words::split(line).map_err(|e| e.to_string())?;
+    let argv = std::iter::once(String::from(args::APP_NAME))
+      .chain(args::global_args(matches))
+      .chain(words);
+
+    let line_matches = match args::app(&self.commands, VERSION).get_matches_from_safe(argv) {
+      Ok(m) => m,
+      Err(e) if e.kind == ErrorKind::HelpDisplayed || e.kind == ErrorKind::VersionDisplayed => {
+        println!("{}", e.message);
+        return Ok(());
+      }
+      Err(e) => {
+        eprintln!("{}", e.message);
+        return Ok(());
+      }
+    };
+
+    if let (args::COMMAND_COMPLETIONS, Some(m)) = line_matches.subcommand() {
+      args::completions(&self.commands, VERSION, m);
+      return Ok(());
+    }
+    let config = config::load_config(args::config_file(&line_matches))?;
+    self.commands.execute(&line_matches, config)
+  }
+}
+
+impl Command for ShellCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app.about("Starts an interactive shell that runs commands against one session")
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, &config)?;
+    if client.session().is_none() {
+      println!("Not logged in; run `login` to start a session.");
+    }
+    version::warn_if_incompatible(&client);
+    println!("Type `help` for commands and `exit` to quit.");
+
+    let editor_config = rustyline::Config::builder()
+      .completion_type(CompletionType::List)
+      .history_ignore_dups(true)
+      .history_ignore_space(true)
+      .build();
+    let mut editor = Editor::with_config(editor_config);
+    editor.set_helper(Some(ShellHelper::new(&self.commands, matches)));
+
+    let history_file = args::history_file();
+    if let Some(f) = &history_file {
+      if Path::new(f).exists() {
+        editor
+          .load_history(f)
+          .map_err(|e| format!("Could not read history {}: {}", f, e))?;
+      }
+    }
+
+    loop {
+      let line = match editor.readline(&ShellCommand::prompt(matches)) {
+        Ok(l) => l,
+        Err(ReadlineError::Interrupted) => continue,
+        Err(ReadlineError::Eof) => break,
+        Err(e) => return Err(format!("Could not read input: {}", e)),
+      };
+      let line = line.trim();
+      if line.is_empty() {
+        continue;
+      }
+      editor.add_history_entry(line);
+      if line == COMMAND_EXIT || line == COMMAND_QUIT {
+        break;
+      }
+
+      if let Err(e) = self.run_line(matches, line) {
+        eprintln!("Error: {}", e);
+      }
+      if let Some(h) = editor.helper_mut() {
+        h.clear_award_ids();
+      }
+    }
+
+    if let Some(f) = &history_file {
+      if let Some(dir) = Path::new(f).parent() {
+        std::fs::create_dir_all(dir)
+          .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+      }
+      editor
+        .save_history(f)
+        .map_err(|e| format!("Could not write history {}: {}", f, e))?;
+    }
+    Ok(())
+  }
+}
+
+/// Completes command names, and award ids for the award subcommands that take one.
+struct ShellHelper<'a> {
+  commands: &'a SelectCommand,
+  matches: &'a ArgMatches<'a>,
+  award_ids: RefCell<Option<Vec<String>>>,
+}
+
+impl<'a> ShellHelper<'a> {
+  fn new(commands: &'a SelectCommand, matches: &'a ArgMatches<'a>) -> ShellHelper<'a> {
+    ShellHelper {
+      commands,
+      matches,
+      award_ids: RefCell::new(None),
+    }
+  }
+
+  fn clear_award_ids(&mut self) {
+    self.award_ids.replace(None);
+  }
+
+  fn fetch_award_ids(&self) -> Result<Vec<String>, String> {
+    let config = config::load_config(args::config_file(self.matches))?;
+    let client = ApiClient::from_args(self.matches, &config)?;
+    if client.session().is_none() {
+      return Ok(Vec::new());
+    }
+    let awards: Vec<Award> = ApiClient::parse(client.call(Method::GET, "/awards", None)?)?;
+    Ok(awards.into_iter().map(|a| a.id).collect())
+  }
+
+  fn award_ids(&self) -> Vec<String> {
+    let mut award_ids = self.award_ids.borrow_mut();
+    if award_ids.is_none() {
+      // Completion must not print over the prompt, so failures offer nothing and retry next time.
+      match self.fetch_award_ids() {
+        Ok(ids) => *award_ids = Some(ids),
+        Err(_) => return Vec::new(),
+      }
+    }
+    award_ids.clone().unwrap_or_default()
+  }
+
+  fn candidates(&self, words: &[&str]) -> Vec<String> {
+    match words {
+      [] => {
+        let mut names: Vec<String> = self
+          .commands
+          .names()
+          .into_iter()
+          .map(String::from)
+          .collect();
+        names.extend(
+          [
+            COMMAND_HELP,
+            args::COMMAND_COMPLETIONS,
+            COMMAND_EXIT,
+            COMMAND_QUIT,
+          ]
+          .iter()
+          .map(|n| String::from(*n)),
+        );
+        names
+      }
+      [command] => self
+        .commands
+        .get(command)
+        .map(|c| c.subcommand_names().into_iter().map(String::from).collect())
+        .unwrap_or_default(),
+      [command, subcommand]
+        if *command == COMMAND_AWARD && AWARD_ID_SUBCOMMANDS.contains(subcommand) =>
+      {
+        self.award_ids()
+      }
+      _ => Vec::new(),
+    }
+  }
+}
+
+impl<'a> Completer for ShellHelper<'a> {
+  type Candidate = String;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    let line = &line[..pos];
+    let start = line.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let prefix = &line[start..];
+    let words: Vec<&str> = line[..start]
+      .split_whitespace()
+      .filter(|w| !w.starts_with('-'))
+      .collect();
+    if prefix.starts_with('-') {
+      return Ok((start, Vec::new()));
+    }
+
+    let mut candidates: Vec<String> = self
+      .candidates(&words)
+      .into_iter()
+      .filter(|c| c.starts_with(prefix))
+      .map(|c| c + " ")
+      .collect();
+    candidates.sort();
+    Ok((start, candidates))
+  }
+}
+
+impl<'a> Hinter for ShellHelper<'a> {}
+
+impl<'a> Highlighter for ShellHelper<'a> {}
+
+impl<'a> Validator for ShellHelper<'a> {}
+
+impl<'a> Helper for ShellHelper<'a> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::commands::{AwardCommand, UserCommand};
+  use rustyline::history::History;
+
+  fn commands() -> SelectCommand {
+    SelectCommand::new()
+      .command("award", AwardCommand::new())
+      .command("user", UserCommand::new())
+  }
+
+  fn complete(helper: &ShellHelper, line: &str) -> (usize, Vec<String>) {
+    let history = History::new();
+    helper
+      .complete(line, line.len(), &Context::new(&history))
+      .unwrap()
+  }
+
+  #[test]
+  fn completes_command_names() {
+    let commands = commands();
+    let matches = ArgMatches::default();
+    let helper = ShellHelper::new(&commands, &matches);
+
+    assert_eq!(complete(&helper, "a"), (0, vec![String::from("award ")]));
+    let (start, all) = complete(&helper, "");
+    assert_eq!(start, 0);
+    assert_eq!(
+      all,
+      vec!["award ", "completions ", "exit ", "help ", "quit ", "user "]
+    );
+  }
+
+  #[test]
+  fn completes_subcommand_names() {
+    let commands = commands();
+    let matches = ArgMatches::default();
+    let helper = ShellHelper::new(&commands, &matches);
+
+    assert_eq!(
+      complete(&helper, "award ex"),
+      (6, vec![String::from("export ")])
+    );
+    assert_eq!(
+      complete(&helper, "user --json di"),
+      (12, vec![String::from("disable ")])
+    );
+    assert_eq!(complete(&helper, "unknown "), (8, Vec::<String>::new()));
+  }
+
+  #[test]
+  fn completes_award_ids_for_award_id_subcommands() {
+    let commands = commands();
+    let matches = ArgMatches::default();
+    let helper = ShellHelper::new(&commands, &matches);
+    helper.award_ids.replace(Some(vec![
+      String::from("a1"),
+      String::from("a2"),
+      String::from("b1"),
+    ]));
+
+    assert_eq!(
+      complete(&helper, "award get a"),
+      (10, vec![String::from("a1 "), String::from("a2 ")])
+    );
+    assert_eq!(complete(&helper, "award list a").1, Vec::<String>::new());
+    assert_eq!(complete(&helper, "award get a1 --"), (13, Vec::<String>::new()));
+  }
+}
@@ -0,0 +1,238 @@
+use super::api::{argument, json_arg, ApiCommand, ARG_JSON};
+use super::role::find_role;
+use crate::client::ApiClient;
+use crate::commands::{Command, SelectCommand};
+use crate::domain::{Config, User};
+use crate::output;
+use clap::{App, Arg, ArgMatches};
+use reqwest::Method;
+use serde_json::json;
+
+const ARG_ROLE: &str = "role";
+const ARG_ENABLE: &str = "enable";
+
+pub struct UserCommand {
+  subcommands: SelectCommand,
+}
+
+impl UserCommand {
+  pub fn new() -> UserCommand {
+    UserCommand {
+      subcommands: SelectCommand::new()
+        .command(
+          "create-admin",
+          ApiCommand::new(create_admin_definition, create_admin),
+        )
+        .command("list", ApiCommand::new(list_definition, list))
+        .command("disable", ApiCommand::new(disable_definition, disable))
+        .command(
+          "reset-password",
+          ApiCommand::new(reset_password_definition, reset_password),
+        ),
+    }
+  }
+}
+
+impl Command for UserCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    self
+      .subcommands
+      .definition(app.about("Manages users").arg(json_arg()))
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    self.subcommands.execute(matches, config)
+  }
+
+  fn subcommand_names(&self) -> Vec<&'static str> {
+    self.subcommands.names()
+  }
+}
+
+/// Finds a user by id or login ID. Only login IDs need the full user list.
+pub fn find_user(client: &ApiClient, user: &str) -> Result<User, String> {
+  if is_user_id(user) {
+    let response = client.call(Method::GET, &ApiClient::path(&["users", user], &[]), None)?;
+    return ApiClient::parse(response);
+  }
+
+  let users: Vec<User> = ApiClient::parse(client.call(Method::GET, "/users", None)?)?;
+  users
+    .into_iter()
+    .find(|u| u.login_id == user)
+    .ok_or_else(|| format!("User '{}' was not found", user))
+}
+
+/// User ids are hyphenated UUIDs.
+fn is_user_id(value: &str) -> bool {
+  value.len() == 36
+    && value.char_indices().all(|(i, c)| match i {
+      8 | 13 | 18 | 23 => c == '-',
+      _ => c.is_ascii_hexdigit(),
+    })
+}
+
+pub fn print_user(matches: &ArgMatches, user: &User) -> Result<(), String> {
+  if matches.is_present(ARG_JSON) {
+    return output::print_json(user);
+  }
+
+  output::print_table(
+    &["FIELD", "VALUE"],
+    &[
+      vec![String::from("id"), user.id.clone()],
+      vec![String::from("userType"), user.user_type.clone()],
+      vec![
+        String::from("companyId"),
+        user.company_id.clone().unwrap_or_default(),
+      ],
+      vec![String::from("loginId"), user.login_id.clone()],
+      vec![String::from("roleIds"), user.role_ids.join(", ")],
+      vec![String::from("disabled"), user.disabled.to_string()],
+    ],
+  );
+  Ok(())
+}
+
+fn print_users(matches: &ArgMatches, users: &[User]) -> Result<(), String> {
+  if matches.is_present(ARG_JSON) {
+    return output::print_json(&users);
+  }
+
+  let rows: Vec<Vec<String>> = users
+    .iter()
+    .map(|u| {
+      vec![
+        u.id.clone(),
+        u.login_id.clone(),
+        u.user_type.clone(),
+        u.company_id.clone().unwrap_or_default(),
+        u.role_ids.join(", "),
+        u.disabled.to_string(),
+      ]
+    })
+    .collect();
+  output::print_table(
+    &["ID", "LOGIN ID", "TYPE", "COMPANY", "ROLES", "DISABLED"],
+    &rows,
+  );
+  Ok(())
+}
+
+fn user_arg() -> Arg<'static, 'static> {
+  Arg::with_name("user")
+    .required(true)
+    .help("User id or login ID")
+}
+
+fn read_new_password() -> Result<String, String> {
+  let password = rpassword::read_password_from_tty(Some("New password: "))
+    .map_err(|e| format!("Could not read password: {}", e))?;
+  if password.is_empty() {
+    return Err(String::from("Password must not be empty"));
+  }
+  let confirmation = rpassword::read_password_from_tty(Some("Confirm password: "))
+    .map_err(|e| format!("Could not read password: {}", e))?;
+  if password != confirmation {
+    return Err(String::from("Passwords do not match"));
+  }
+  Ok(password)
+}
+
+fn create_admin_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Creates an admin user, prompting for the password")
+    .arg(Arg::with_name("loginId").required(true))
+    .arg(
+      Arg::with_name(ARG_ROLE)
+        .long(ARG_ROLE)
+        .value_name("ROLE")
+        .multiple(true)
+        .number_of_values(1)
+        .help("Role id or name to grant; may be repeated"),
+    )
+}
+
+fn create_admin(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let login_id = argument(matches, "loginId")?;
+  if find_user(client, &login_id).is_ok() {
+    return Err(format!("User '{}' already exists", login_id));
+  }
+  let role_ids = matches
+    .values_of(ARG_ROLE)
+    .map(|roles| {
+      roles
+        .map(|r| find_role(client, r).map(|r| r.id))
+        .collect::<Result<Vec<String>, String>>()
+    })
+    .unwrap_or_else(|| Ok(Vec::new()))?;
+  let password = read_new_password()?;
+
+  let body = json!({ "loginId": login_id, "password": password, "roleIds": role_ids });
+  let response = client.call(Method::POST, "/users/create/admin", Some(&body))?;
+  print_user(matches, &ApiClient::parse(response)?)
+}
+
+fn list_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app.about("Lists users")
+}
+
+fn list(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let users: Vec<User> = ApiClient::parse(client.call(Method::GET, "/users", None)?)?;
+  print_users(matches, &users)
+}
+
+fn disable_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Disables a user so they can no longer log in or use API keys")
+    .arg(user_arg())
+    .arg(
+      Arg::with_name(ARG_ENABLE)
+        .long(ARG_ENABLE)
+        .help("Re-enables the user instead"),
+    )
+}
+
+fn disable(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let user = find_user(client, &argument(matches, "user")?)?;
+  let body = json!({ "disabled": !matches.is_present(ARG_ENABLE) });
+  let response = client.call(
+    Method::PUT,
+    &ApiClient::path(&["users", &user.id, "disabled"], &[]),
+    Some(&body),
+  )?;
+  print_user(matches, &ApiClient::parse(response)?)
+}
+
+fn reset_password_definition(app: App<'static, 'static>) -> App<'static, 'static> {
+  app
+    .about("Sets a new password for an admin user, prompting for it")
+    .arg(user_arg())
+}
+
+fn reset_password(matches: &ArgMatches, client: &ApiClient) -> Result<(), String> {
+  let user = find_user(client, &argument(matches, "user")?)?;
+  let password = read_new_password()?;
+  let body = json!({ "password": password });
+  let response = client.call(
+    Method::PUT,
+    &ApiClient::path(&["users", &user.id, "password"], &[]),
+    Some(&body),
+  )?;
+  print_user(matches, &ApiClient::parse(response)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn user_ids_are_told_apart_from_login_ids() {
+    assert!(is_user_id("0f8fad5b-d9cb-469f-a165-70867728950e"));
+    assert!(is_user_id("0F8FAD5B-D9CB-469F-A165-70867728950E"));
+    assert!(!is_user_id("admin"));
+    assert!(!is_user_id("0f8fad5bd9cb469fa16570867728950e"));
+    assert!(!is_user_id("0f8fad5b-d9cb-469f-a165-70867728950g"));
+    assert!(!is_user_id("0f8fad5b-d9cb-469f-a165_70867728950e"));
+  }
+}
@@ -0,0 +1,61 @@
+use super::api::{json_arg, ARG_JSON};
+use crate::client::ApiClient;
+use crate::commands::Command;
+use crate::domain::Config;
+use crate::output;
+use crate::version;
+use clap::{App, ArgMatches};
+use serde_json::json;
+
+pub struct VersionCommand {}
+
+impl VersionCommand {
+  pub fn new() -> VersionCommand {
+    VersionCommand {}
+  }
+
+  fn show(matches: &ArgMatches, config: &Config) -> Result<(), String> {
+    let client = ApiClient::from_args(matches, config)?;
+    let server = version::server_version(&client)?;
+    let warning = version::incompatibility(&server);
+
+    if matches.is_present(ARG_JSON) {
+      output::print_json(&json!({
+        "client": { "version": version::VERSION, "apiVersion": version::API_VERSION },
+        "server": server,
+        "compatible": warning.is_none(),
+      }))?;
+    } else {
+      let row = |field: &str, value: String| vec![String::from(field), value];
+      output::print_table(
+        &["FIELD", "VALUE"],
+        &[
+          row("clientVersion", String::from(version::VERSION)),
+          row("clientApiVersion", String::from(version::API_VERSION)),
+          row("serverVersion", server.version.clone()),
+          row("serverApiVersion", server.api_version.clone()),
+          row("gitCommit", server.git_commit.clone()),
+          row("buildTimestamp", server.build_timestamp.clone()),
+          row("schemaVersion", version::schema_version(&server)),
+        ],
+      );
+    }
+
+    if let Some(w) = warning {
+      eprintln!("Warning: {}", w);
+    }
+    Ok(())
+  }
+}
+
+impl Command for VersionCommand {
+  fn definition(&self, app: App<'static, 'static>) -> App<'static, 'static> {
+    app
+      .about("Shows the client and server versions and whether they are compatible")
+      .arg(json_arg())
+  }
+
+  fn execute(&self, matches: &ArgMatches, config: Config) -> Result<(), String> {
+    VersionCommand::show(matches, &config)
+  }
+}
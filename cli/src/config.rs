@@ -1,21 +1,151 @@
-use crate::domain::Config;
-use serde_yaml;
+use crate::domain::{Config, RemoteConfig};
+use std::path::Path;
 
-pub fn load_config(fname: Option<String>) -> Config {
-  if let None = fname {
-    panic!("Could not find config file");
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub fn load_config(fname: Option<String>) -> Result<Config, String> {
+  let fname = match fname {
+    Some(f) => f,
+    None => {
+      return Err(String::from(
+        "Could not find config file, pass --config <FILE>",
+      ))
+    }
+  };
+  if !Path::new(&fname).exists() {
+    return Ok(Config::default());
+  }
+
+  let content = std::fs::read_to_string(&fname)
+    .map_err(|e| format!("Could not load config file at {}: {}", fname, e))?;
+  serde_yaml::from_str(&content).map_err(|e| format!("Error reading config file {}: {}", fname, e))
+}
+
+pub fn save_config(fname: &str, config: &Config) -> Result<(), String> {
+  if let Some(dir) = Path::new(fname).parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+  }
+
+  let content = serde_yaml::to_string(config).map_err(|e| e.to_string())?;
+  std::fs::write(fname, content)
+    .map_err(|e| format!("Could not write config file {}: {}", fname, e))
+}
+
+pub fn profile_name(config: &Config, selected: Option<&str>) -> String {
+  if let Some(p) = selected.or(config.current_profile.as_deref()) {
+    return String::from(p);
   }
-  let fname = fname.unwrap();
 
-  let content = std::fs::read_to_string(&fname);
-  if content.is_err() {
-    panic!("Could not load config file at {}", fname);
+  match config.profiles.keys().next() {
+    Some(p) if config.profiles.len() == 1 && config.remote.is_none() => p.clone(),
+    _ => String::from(DEFAULT_PROFILE),
   }
+}
+
+pub fn profile<'a>(config: &'a Config, name: &str) -> Result<&'a RemoteConfig, String> {
+  let remote = match config.profiles.get(name) {
+    Some(r) => Some(r),
+    None if name == DEFAULT_PROFILE => config.remote.as_ref(),
+    None => None,
+  };
+
+  remote.ok_or_else(|| {
+    format!(
+      "Profile '{}' is not configured, add it with `ausawards config add {} <url>`",
+      name, name
+    )
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-  let config: Result<Config, _> = serde_yaml::from_str(&content.unwrap());
-  if config.is_err() {
-    panic!("Error reading config file {}", fname);
+  fn remote(url: &str) -> RemoteConfig {
+    RemoteConfig {
+      url: String::from(url),
+    }
   }
 
-  config.unwrap()
+  fn config(profiles: &[&str], current: Option<&str>, legacy: bool) -> Config {
+    Config {
+      remote: if legacy {
+        Some(remote("https://legacy.example.com"))
+      } else {
+        None
+      },
+      profiles: profiles
+        .iter()
+        .map(|p| (String::from(*p), remote(&format!("https://{}.example.com", p))))
+        .collect(),
+      current_profile: current.map(String::from),
+    }
+  }
+
+  #[test]
+  fn the_given_profile_wins_over_the_current_one() {
+    let config = config(&["prod", "staging"], Some("prod"), false);
+    assert_eq!(profile_name(&config, Some("staging")), "staging");
+    assert_eq!(profile_name(&config, None), "prod");
+  }
+
+  #[test]
+  fn a_lone_profile_is_used_without_selecting_it() {
+    assert_eq!(profile_name(&config(&["prod"], None, false), None), "prod");
+    assert_eq!(
+      profile_name(&config(&["prod"], None, true), None),
+      DEFAULT_PROFILE
+    );
+    assert_eq!(
+      profile_name(&config(&["prod", "staging"], None, false), None),
+      DEFAULT_PROFILE
+    );
+    assert_eq!(profile_name(&config(&[], None, false), None), DEFAULT_PROFILE);
+  }
+
+  #[test]
+  fn the_default_profile_falls_back_to_the_top_level_remote() {
+    let legacy = config(&["prod"], None, true);
+    assert_eq!(
+      profile(&legacy, DEFAULT_PROFILE).unwrap().url,
+      "https://legacy.example.com"
+    );
+    assert_eq!(profile(&legacy, "prod").unwrap().url, "https://prod.example.com");
+
+    let named_default = config(&[DEFAULT_PROFILE], None, true);
+    assert_eq!(
+      profile(&named_default, DEFAULT_PROFILE).unwrap().url,
+      "https://default.example.com"
+    );
+  }
+
+  #[test]
+  fn unknown_profiles_are_reported() {
+    let error = profile(&config(&["prod"], None, true), "staging").err().unwrap();
+    assert_eq!(
+      error,
+      "Profile 'staging' is not configured, add it with `ausawards config add staging <url>`"
+    );
+    assert!(profile(&config(&[], None, false), DEFAULT_PROFILE).is_err());
+  }
+
+  #[test]
+  fn configs_round_trip_through_files() {
+    let fname = std::env::temp_dir()
+      .join(format!("ausawards-config-{}/config.yaml", std::process::id()))
+      .to_string_lossy()
+      .into_owned();
+    assert!(load_config(Some(fname.clone())).unwrap().profiles.is_empty());
+
+    save_config(&fname, &config(&["prod", "staging"], Some("staging"), false)).unwrap();
+    let loaded = load_config(Some(fname.clone())).unwrap();
+    std::fs::remove_dir_all(Path::new(&fname).parent().unwrap()).unwrap();
+    assert_eq!(profile_name(&loaded, None), "staging");
+    assert_eq!(
+      profile(&loaded, "prod").unwrap().url,
+      "https://prod.example.com"
+    );
+    assert!(loaded.remote.is_none());
+  }
 }
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const ALT_ID_TYPES: &[&str] = &["ORIGINAL_PRINT_ID", "ORIGINATING_MATTER"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AwardAlternateId {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub id_type: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AwardClassification {
+  pub id: String,
+  pub title: String,
+  pub active: bool,
+  pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Award {
+  pub id: String,
+  pub external_id: String,
+  pub name: String,
+  #[serde(rename = "industryName")]
+  pub industry_name: String,
+  #[serde(rename = "commonRule")]
+  pub common_rule: Option<String>,
+  #[serde(rename = "alternateIds")]
+  pub alternate_ids: Vec<AwardAlternateId>,
+  #[serde(rename = "operativeDate")]
+  pub operative_date: String,
+  #[serde(rename = "expiredDate")]
+  pub expired_date: Option<String>,
+  pub classifications: Vec<AwardClassification>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CreateAwardRequest {
+  pub external_id: String,
+  pub name: String,
+  #[serde(rename = "industryName")]
+  pub industry_name: String,
+  #[serde(rename = "commonRule")]
+  pub common_rule: Option<String>,
+  #[serde(rename = "alternateIds")]
+  pub alternate_ids: Vec<AwardAlternateId>,
+  #[serde(rename = "operativeDate")]
+  pub operative_date: DateTime<Utc>,
+  #[serde(rename = "expiredDate")]
+  pub expired_date: Option<DateTime<Utc>>,
+  pub classifications: Vec<AwardClassification>,
+}
+
+impl CreateAwardRequest {
+  pub fn validate(&self) -> Vec<String> {
+    let mut errors = Vec::new();
+    if self.external_id.trim().is_empty() {
+      errors.push(String::from("external_id must not be empty"));
+    }
+    if self.name.trim().is_empty() {
+      errors.push(String::from("name must not be empty"));
+    }
+    if self.industry_name.trim().is_empty() {
+      errors.push(String::from("industryName must not be empty"));
+    }
+    if let Some(e) = self.expired_date {
+      if e < self.operative_date {
+        errors.push(String::from("expiredDate must not be before operativeDate"));
+      }
+    }
+
+    for alt_id in &self.alternate_ids {
+      if !ALT_ID_TYPES.contains(&alt_id.id_type.as_str()) {
+        errors.push(format!(
+          "alternate id {} has type '{}', expected one of {}",
+          alt_id.id,
+          alt_id.id_type,
+          ALT_ID_TYPES.join("|")
+        ));
+      }
+    }
+
+    let mut classification_ids = HashSet::new();
+    for c in &self.classifications {
+      if c.id.trim().is_empty() {
+        errors.push(String::from("classification id must not be empty"));
+      } else if !classification_ids.insert(&c.id) {
+        errors.push(format!("classification {} is listed more than once", c.id));
+      }
+      if c.title.trim().is_empty() {
+        errors.push(format!("classification {} must have a title", c.id));
+      }
+    }
+
+    errors
+  }
+}
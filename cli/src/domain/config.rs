@@ -1,11 +1,17 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RemoteConfig {
   pub url: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct Config {
-  pub remote: RemoteConfig,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub remote: Option<RemoteConfig>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub profiles: BTreeMap<String, RemoteConfig>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub current_profile: Option<String>,
 }
@@ -1,3 +1,11 @@
+mod award;
 mod config;
+mod session;
+mod user;
+mod version;
 
-pub use config::Config;
+pub use award::{Award, AwardAlternateId, AwardClassification, CreateAwardRequest, ALT_ID_TYPES};
+pub use config::{Config, RemoteConfig};
+pub use session::Session;
+pub use user::{Role, User};
+pub use version::ServerVersion;
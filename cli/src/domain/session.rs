@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Session {
+  #[serde(rename = "sessionId")]
+  pub session_id: String,
+  #[serde(rename = "sessionSecret")]
+  pub session_secret: String,
+  pub token: String,
+}
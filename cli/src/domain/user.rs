@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct User {
+  pub id: String,
+  #[serde(rename = "userType")]
+  pub user_type: String,
+  #[serde(rename = "companyId")]
+  pub company_id: Option<String>,
+  #[serde(rename = "loginId")]
+  pub login_id: String,
+  #[serde(rename = "roleIds")]
+  pub role_ids: Vec<String>,
+  #[serde(default)]
+  pub disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Role {
+  pub id: String,
+  pub name: String,
+  pub permissions: Vec<String>,
+  #[serde(rename = "includedRoleIds", default)]
+  pub included_role_ids: Vec<String>,
+}
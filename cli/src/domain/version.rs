@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct ServerVersion {
+  pub version: String,
+  #[serde(rename = "gitCommit")]
+  pub git_commit: String,
+  #[serde(rename = "buildTimestamp")]
+  pub build_timestamp: String,
+  /// None when the server has no migration recorded or could not read it.
+  #[serde(rename = "schemaVersion")]
+  pub schema_version: Option<u32>,
+  #[serde(rename = "apiVersion")]
+  pub api_version: String,
+}
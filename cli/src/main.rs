@@ -1,29 +1,38 @@
 mod args;
+mod client;
 mod commands;
 mod config;
 mod domain;
+mod output;
+mod session;
+mod version;
 
-use args::ArgsParser;
+use version::VERSION;
 
-const VERSION: &str = "0.0.1-dev";
+fn commands() -> commands::SelectCommand {
+    commands::SelectCommand::new()
+        .command("healthcheck", commands::HealthCheckCommand::new())
+        .command("login", commands::LoginCommand::new())
+        .command("logout", commands::LogoutCommand::new())
+        .command("award", commands::AwardCommand::new())
+        .command("user", commands::UserCommand::new())
+        .command("role", commands::RoleCommand::new())
+        .command("config", commands::ConfigCommand::new())
+        .command("version", commands::VersionCommand::new())
+}
 
 fn main() {
-    let mut parser = ArgsParser::new();
-    if let Ok(home) = std::env::var("HOME") {
-        parser.arg_str_default("config", &format!("{}/.ausawards/config.yaml", home));
-    }
-
-    parser.parse();
-    parser.serve_command();
+    let commands = commands().command("shell", commands::ShellCommand::new(commands()));
 
-    if parser.is_flag("version") {
-        println!("{}", VERSION);
+    let matches = args::app(&commands, VERSION).get_matches();
+    if let (args::COMMAND_COMPLETIONS, Some(m)) = matches.subcommand() {
+        args::completions(&commands, VERSION, m);
         return;
     }
-    let config = config::load_config(parser.get_flag("config"));
-
-    commands::SelectCommand::new()
-        .command("healthcheck", commands::HealthCheckCommand::new())
-        .select(&parser.serve_command().unwrap_or(String::from("")))
-        .execute(parser, config);
+    let result = config::load_config(args::config_file(&matches))
+        .and_then(|config| commands.execute(&matches, config));
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
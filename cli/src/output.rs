@@ -0,0 +1,31 @@
+use serde::Serialize;
+
+pub fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+  let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+  println!("{}", json);
+  Ok(())
+}
+
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+  let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+  for row in rows {
+    for (i, cell) in row.iter().enumerate() {
+      widths[i] = widths[i].max(cell.chars().count());
+    }
+  }
+
+  let headers: Vec<String> = headers.iter().map(|h| String::from(*h)).collect();
+  print_row(&headers, &widths);
+  for row in rows {
+    print_row(row, &widths);
+  }
+}
+
+fn print_row(row: &[String], widths: &[usize]) {
+  let cells: Vec<String> = row
+    .iter()
+    .zip(widths)
+    .map(|(c, w)| format!("{:width$}", c, width = w))
+    .collect();
+  println!("{}", cells.join("  ").trim_end());
+}
@@ -0,0 +1,58 @@
+use crate::domain::Session;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::fs::Permissions;
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+
+pub fn load_session(fname: &str) -> Result<Option<Session>, String> {
+  if !Path::new(fname).exists() {
+    return Ok(None);
+  }
+
+  let content = std::fs::read_to_string(fname)
+    .map_err(|e| format!("Could not read session file {}: {}", fname, e))?;
+  serde_json::from_str(&content)
+    .map(Some)
+    .map_err(|_| format!("Session file {} is corrupt, please log in again", fname))
+}
+
+pub fn save_session(fname: &str, session: &Session) -> Result<(), String> {
+  if let Some(dir) = Path::new(fname).parent() {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder
+      .create(dir)
+      .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+  }
+
+  let mut options = OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  options.mode(0o600);
+
+  let content = serde_json::to_string(session).map_err(|e| e.to_string())?;
+  options
+    .open(fname)
+    .and_then(|mut f| {
+      // The mode above only applies when the file is created, so tighten an existing file before
+      // the tokens are written to it.
+      #[cfg(unix)]
+      f.set_permissions(Permissions::from_mode(0o600))?;
+      f.write_all(content.as_bytes())
+    })
+    .map_err(|e| format!("Could not write session file {}: {}", fname, e))
+}
+
+pub fn remove_session(fname: &str) -> Result<(), String> {
+  if !Path::new(fname).exists() {
+    return Ok(());
+  }
+
+  std::fs::remove_file(fname).map_err(|e| format!("Could not remove session file {}: {}", fname, e))
+}
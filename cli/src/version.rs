@@ -0,0 +1,112 @@
+use crate::client::ApiClient;
+use crate::domain::ServerVersion;
+use reqwest::Method;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Server API version this client was built against, as MAJOR.MINOR. A server is compatible
+/// when its major version matches and its minor version is at least this one.
+pub const API_VERSION: &str = "1.0";
+
+fn parse_api_version(value: &str) -> Option<(u32, u32)> {
+  let mut parts = value.splitn(2, '.');
+  let major = parts.next()?.parse().ok()?;
+  let minor = parts.next()?.parse().ok()?;
+  Some((major, minor))
+}
+
+pub fn server_version(client: &ApiClient) -> Result<ServerVersion, String> {
+  client
+    .call_anonymous(Method::GET, "/version", None)
+    .and_then(ApiClient::parse::<ServerVersion>)
+}
+
+/// Returns why this client cannot work with the server, if it cannot.
+pub fn incompatibility(server: &ServerVersion) -> Option<String> {
+  let (major, minor) = parse_api_version(API_VERSION)?;
+  let compatible = match parse_api_version(&server.api_version) {
+    Some((server_major, server_minor)) => server_major == major && server_minor >= minor,
+    None => false,
+  };
+  if compatible {
+    None
+  } else {
+    Some(format!(
+      "ausawards {} requires server API version {}.x (at least {}), but the server provides {}",
+      VERSION, major, API_VERSION, server.api_version
+    ))
+  }
+}
+
+/// The server's schema version for display, as the server reports null when it has none.
+pub fn schema_version(server: &ServerVersion) -> String {
+  server
+    .schema_version
+    .map_or_else(|| String::from("unknown"), |v| v.to_string())
+}
+
+/// Prints a warning when the server cannot be checked or is incompatible, without failing the
+/// command that called it.
+pub fn warn_if_incompatible(client: &ApiClient) {
+  match server_version(client) {
+    Ok(v) => {
+      if let Some(w) = incompatibility(&v) {
+        eprintln!("Warning: {}", w);
+      }
+    }
+    Err(e) => eprintln!("Warning: could not check the server API version: {}", e),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn server(api_version: &str) -> ServerVersion {
+    ServerVersion {
+      version: String::from("1.4.0"),
+      git_commit: String::from("abc1234"),
+      build_timestamp: String::from("2020-07-01T00:00:00Z"),
+      schema_version: Some(1),
+      api_version: String::from(api_version),
+    }
+  }
+
+  #[test]
+  fn missing_schema_versions_are_shown_as_unknown() {
+    let mut server = server(API_VERSION);
+    server.schema_version = None;
+    assert_eq!(schema_version(&server), "unknown");
+    server.schema_version = Some(3);
+    assert_eq!(schema_version(&server), "3");
+  }
+
+  #[test]
+  fn api_versions_are_major_and_minor() {
+    assert_eq!(parse_api_version("1.0"), Some((1, 0)));
+    assert_eq!(parse_api_version("2.13"), Some((2, 13)));
+    assert_eq!(parse_api_version("1"), None);
+    assert_eq!(parse_api_version("1.x"), None);
+    assert_eq!(parse_api_version("1.0.3"), None);
+    assert_eq!(parse_api_version(""), None);
+  }
+
+  #[test]
+  fn servers_with_the_same_major_and_a_later_minor_are_compatible() {
+    let (major, minor) = parse_api_version(API_VERSION).unwrap();
+    assert_eq!(incompatibility(&server(API_VERSION)), None);
+    assert_eq!(
+      incompatibility(&server(&format!("{}.{}", major, minor + 1))),
+      None
+    );
+  }
+
+  #[test]
+  fn other_majors_and_unparseable_versions_are_incompatible() {
+    let (major, _) = parse_api_version(API_VERSION).unwrap();
+    let next_major = format!("{}.0", major + 1);
+    let warning = incompatibility(&server(&next_major)).unwrap();
+    assert!(warning.ends_with(&format!("but the server provides {}", next_major)));
+    assert!(incompatibility(&server("unknown")).is_some());
+  }
+}
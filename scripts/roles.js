@@ -5,11 +5,36 @@ const roles = [
   {
     name: 'SUPER_ADMIN',
     permissions: [
-      'createAdminUser',
+      '*',
     ],
+    includedRoleIds: [],
   },
 ];
 
+// Permissions were renamed from camelCase names to resource:action.
+const legacyPermissions = {
+  createAward: 'award:create',
+  listAwards: 'award:list',
+  getAward: 'award:get',
+  addAwardAlternateId: 'award:addAlternateId',
+  addAwardClassification: 'award:addClassification',
+  updateAwardClassificationActive: 'award:updateClassificationActive',
+  updateAwardClassificationNote: 'award:updateClassificationNote',
+  updateAwardExpiryDate: 'award:updateExpiryDate',
+  removeAwardExpiryDate: 'award:removeExpiryDate',
+  createAdminUser: 'user:createAdmin',
+  createServiceUser: 'user:createService',
+  listUsers: 'user:list',
+  disableUser: 'user:disable',
+  resetUserPassword: 'user:resetPassword',
+  listRoles: 'role:list',
+  createRole: 'role:create',
+  grantRole: 'role:grant',
+  revokeRole: 'role:revoke',
+  createApiKey: 'apiKey:create',
+  revokeApiKey: 'apiKey:revoke',
+};
+
 const updateRole = (collection) => async (role) => {
   const roleDb = await collection.findOne({ name: role.name });
   if (roleDb) {
@@ -25,13 +50,41 @@ const updateRole = (collection) => async (role) => {
   }
 };
 
+const migratePermissions = async (collection) => {
+  const docs = await collection
+    .find({ permissions: { $in: Object.keys(legacyPermissions) } })
+    .toArray();
+
+  await Promise.all(docs.map((doc) => collection.updateOne(
+    { _id: doc._id },
+    { $set: { permissions: doc.permissions.map((p) => legacyPermissions[p] || p) } },
+  )));
+};
+
+// Recorded in the Migrations collection once applied; /version reports the highest version.
+const migration = {
+  _id: 'permissions-resource-action',
+  version: 1,
+};
+
+const recordMigration = async (collection) => {
+  await collection.updateOne(
+    { _id: migration._id },
+    { $set: { version: migration.version, appliedAt: new Date() } },
+    { upsert: true },
+  );
+};
+
 async function main() {
   const config = await utils.getConfig();
   const [db, client] = await utils.getDb(config);
 
+  await migratePermissions(db.collection('Roles'));
+  await migratePermissions(db.collection('ApiKeys'));
   await Promise.all(
     roles.map(updateRole(db.collection('Roles'))),
   );
+  await recordMigration(db.collection('Migrations'));
 
   client.close();
 }